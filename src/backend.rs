@@ -0,0 +1,175 @@
+use gtk::{gio::{self, prelude::SettingsExt}, glib};
+use std::any::Any;
+
+use crate::{execute_gpaste_command, ClipboardError};
+
+/// One item of the clipboard history as listed by the backend.
+#[derive(Debug, Clone)]
+pub struct HistoryItem {
+    pub uuid: String,
+    /// Text of the item, or a summary such as ` [Files] /path` for non-text items.
+    pub content: String,
+}
+
+/// A slice of the history, newest items first.
+#[derive(Debug, Default)]
+pub struct HistoryPage {
+    pub items: Vec<HistoryItem>,
+    /// Lines of the listing that could not be parsed.
+    pub invalid_lines: Vec<String>,
+    /// Offset of the page after this one, or `None` if this page reaches the end of the history.
+    pub next_offset: Option<usize>,
+}
+
+/// Keeps a change subscription alive; dropping it unsubscribes.
+pub struct ChangeWatch {
+    _subscription: Box<dyn Any>,
+}
+
+/// Source of clipboard history. Implementations are used from worker threads.
+pub trait ClipboardBackend: Send + Sync {
+    /// Lists up to `limit` items starting `offset` items from the newest one. `None` lists all
+    /// remaining items.
+    fn history(&self, offset: usize, limit: Option<usize>) -> Result<HistoryPage, ClipboardError>;
+
+    /// Path of the file an image item refers to.
+    fn entry_path(&self, uuid: &str) -> Result<String, ClipboardError>;
+
+    /// Paths of the files copied together in a file item.
+    fn entry_files(&self, uuid: &str) -> Result<Vec<String>, ClipboardError>;
+
+    /// Removes an item from the history.
+    fn delete(&self, uuid: &str) -> Result<(), ClipboardError>;
+
+    /// Whether new clipboard contents are added to the history, or `None` if that is unknown.
+    fn is_recording(&self) -> Option<bool>;
+
+    /// Pauses or resumes adding new clipboard contents to the history.
+    fn set_recording(&self, recording: bool) -> Result<(), ClipboardError>;
+
+    /// Calls `on_change` on the main loop whenever the history changes, for as long as the
+    /// returned watch is kept. Returns `None` if the backend cannot report changes. Must be
+    /// called from the main thread.
+    fn watch_changes(&self, on_change: Box<dyn Fn()>) -> Option<ChangeWatch>;
+}
+
+/// History kept by the GPaste daemon, accessed through `gpaste-client`.
+pub struct GpasteBackend;
+
+struct DbusSubscription {
+    connection: gio::DBusConnection,
+    id: Option<gio::SignalSubscriptionId>,
+}
+
+impl Drop for DbusSubscription {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.connection.signal_unsubscribe(id);
+        }
+    }
+}
+
+impl GpasteBackend {
+    const BUS_NAME: &'static str = "org.gnome.GPaste";
+    const OBJECT_PATH: &'static str = "/org/gnome/GPaste";
+    const SETTINGS_SCHEMA: &'static str = "org.gnome.GPaste";
+
+    fn parse_line(line: &str) -> Option<HistoryItem> {
+        let (uuid, content) = line.split_once(':')?;
+        Some(HistoryItem {
+            uuid: uuid.to_string(),
+            content: content.to_string(),
+        })
+    }
+}
+
+impl ClipboardBackend for GpasteBackend {
+    /// `gpaste-client` cannot list part of the history, so the listing is sliced here. Only
+    /// the items of the page are parsed and resolved further.
+    fn history(&self, offset: usize, limit: Option<usize>) -> Result<HistoryPage, ClipboardError> {
+        let history = execute_gpaste_command(&["history", "--zero"], None)?;
+
+        let lines: Vec<&str> = history.split('\0').filter(|s| !s.is_empty()).collect();
+        let end = match limit {
+            Some(limit) => lines.len().min(offset.saturating_add(limit)),
+            None => lines.len(),
+        };
+
+        let mut page = HistoryPage {
+            next_offset: (end < lines.len()).then_some(end),
+            ..HistoryPage::default()
+        };
+
+        for line in lines.get(offset..end).unwrap_or_default() {
+            match Self::parse_line(line) {
+                Some(item) => page.items.push(item),
+                None => page.invalid_lines.push(line.to_string()),
+            }
+        }
+
+        Ok(page)
+    }
+
+    fn entry_path(&self, uuid: &str) -> Result<String, ClipboardError> {
+        execute_gpaste_command(&["--raw", "get", uuid], Some(uuid))
+    }
+
+    /// GPaste stores copied files one per line, as paths or `file://` URIs.
+    fn entry_files(&self, uuid: &str) -> Result<Vec<String>, ClipboardError> {
+        let raw = self.entry_path(uuid)?;
+        let paths = raw
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.strip_prefix("file://") {
+                Some(_) => glib::filename_from_uri(line)
+                    .map(|(path, _)| path.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| line.to_string()),
+                None => line.to_string(),
+            })
+            .collect();
+        Ok(paths)
+    }
+
+    fn delete(&self, uuid: &str) -> Result<(), ClipboardError> {
+        execute_gpaste_command(&["delete", uuid], Some(uuid)).map(|_| ())
+    }
+
+    /// The daemon keeps its tracking switch in its settings.
+    fn is_recording(&self) -> Option<bool> {
+        let schema = gio::SettingsSchemaSource::default()?.lookup(Self::SETTINGS_SCHEMA, true)?;
+        let settings = gio::Settings::new_full(&schema, gio::SettingsBackend::NONE, None);
+        Some(settings.boolean("track-changes"))
+    }
+
+    fn set_recording(&self, recording: bool) -> Result<(), ClipboardError> {
+        let command = if recording { "start" } else { "stop" };
+        execute_gpaste_command(&[command], None).map(|_| ())
+    }
+
+    /// Listens for the daemon's `Update` signal on the session bus, which both the GPaste1 and
+    /// GPaste2 interfaces emit when the history changes.
+    fn watch_changes(&self, on_change: Box<dyn Fn()>) -> Option<ChangeWatch> {
+        let connection = match gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE) {
+            Ok(connection) => connection,
+            Err(e) => {
+                eprintln!("Error connecting to the session bus: {}", e);
+                return None;
+            }
+        };
+
+        let id = connection.signal_subscribe(
+            Some(Self::BUS_NAME),
+            None,
+            Some("Update"),
+            Some(Self::OBJECT_PATH),
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |_, _, _, _, _, _| on_change(),
+        );
+
+        Some(ChangeWatch {
+            _subscription: Box::new(DbusSubscription { connection, id: Some(id) }),
+        })
+    }
+}
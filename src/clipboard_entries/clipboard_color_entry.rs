@@ -0,0 +1,214 @@
+use gtk::{
+    gdk_pixbuf::{Colorspace, Pixbuf},
+    prelude::*,
+    Box as GTKBox, Button, Grid, Image, Label, ListBoxRow, Orientation, Widget,
+};
+use std::cell::RefCell;
+
+use crate::{
+    color_value::{contrast_level, ColorValue},
+    copy_text_to_clipboard, copy_to_clipboard_by_gpaste_uuid, open_in_editor, open_in_external_app,
+    session_files::write_session_file, ClipboardError,
+};
+
+use super::clipboard_entry::{
+    create_error_label, highlight_format, highlight_in_text, report_result, ClipboardEntry,
+};
+
+/// A copied colour literal, shown with a swatch of the colour.
+#[derive(Debug, Clone)]
+pub struct ClipboardColorEntry {
+    color: ColorValue,
+    /// The literal as copied.
+    text: String,
+    uuid: String,
+    row: ListBoxRow,
+    row_width: i32,
+    row_label: RefCell<Option<Label>>,
+}
+
+impl ClipboardColorEntry {
+    const MARGIN: i32 = 10;
+    const ROW_SWATCH_SIZE: i32 = 24;
+    const DETAIL_SWATCH_SIZE: i32 = 96;
+
+    pub fn new(color: ColorValue, text: String, uuid: String, row_width: i32) -> Self {
+        let row = ListBoxRow::new();
+        row.style_context().add_class("clipboard-row");
+        row.set_size_request(row_width, Self::ROW_SWATCH_SIZE + (2 * Self::MARGIN));
+        Self {
+            color,
+            text: text.trim().to_string(),
+            uuid,
+            row,
+            row_width,
+            row_label: RefCell::new(None),
+        }
+    }
+
+    fn row_label(&self) -> Label {
+        self.render_row();
+        self.row_label.borrow().clone().expect("render_row creates the row label")
+    }
+
+    fn create_swatch(&self, width: i32, height: i32) -> Image {
+        let swatch = match Pixbuf::new(Colorspace::Rgb, true, 8, width, height) {
+            Some(pixbuf) => {
+                pixbuf.fill(self.color.to_rgba_u32());
+                Image::from_pixbuf(Some(&pixbuf))
+            }
+            None => Image::from_icon_name(Some("applications-graphics"), gtk::IconSize::LargeToolbar),
+        };
+        swatch.style_context().add_class("clipboard-color-swatch");
+        swatch
+    }
+
+    /// The colour in every notation, each with a button to copy it.
+    fn create_forms_grid(&self, report: impl Fn(Result<(), ClipboardError>) + Clone + 'static) -> Grid {
+        let grid = Grid::new();
+        grid.set_column_spacing(Self::MARGIN as u32);
+        grid.set_row_spacing(2);
+
+        let (name, exact) = self.color.name();
+        let name = if exact { name.to_string() } else { format!("≈ {}", name) };
+        let forms = [
+            ("Hex", self.color.to_hex()),
+            ("RGB", self.color.to_rgb()),
+            ("HSL", self.color.to_hsl()),
+            ("Name", name),
+        ];
+
+        for (row, (title, value)) in forms.into_iter().enumerate() {
+            let title_label = Label::new(Some(title));
+            title_label.set_xalign(0.0);
+            title_label.style_context().add_class("dim-label");
+
+            let value_label = Label::new(Some(&value));
+            value_label.set_xalign(0.0);
+            value_label.set_hexpand(true);
+            value_label.set_selectable(true);
+
+            let copy_button = Button::from_icon_name(Some("edit-copy-symbolic"), gtk::IconSize::Button);
+            copy_button.set_tooltip_text(Some(&format!("Copy {}", title)));
+            copy_button.set_focus_on_click(false);
+            let copied = value.trim_start_matches("≈ ").to_string();
+            let report = report.clone();
+            copy_button.connect_clicked(move |_| report(copy_text_to_clipboard(&copied)));
+
+            grid.attach(&title_label, 0, row as i32, 1, 1);
+            grid.attach(&value_label, 1, row as i32, 1, 1);
+            grid.attach(&copy_button, 2, row as i32, 1, 1);
+        }
+
+        grid
+    }
+
+    fn create_contrast_label(&self) -> Label {
+        let line = |background: &str, other: &ColorValue| {
+            let ratio = self.color.contrast_ratio(other);
+            format!("Contrast with {}: {:.2}:1 ({})", background, ratio, contrast_level(ratio))
+        };
+        let mut text = format!("{}\n{}", line("black", &ColorValue::BLACK), line("white", &ColorValue::WHITE));
+        if self.color.alpha < 1.0 {
+            text.push_str("\nContrast ignores the colour's transparency.");
+        }
+
+        let label = Label::new(Some(&text));
+        label.set_xalign(0.0);
+        label.set_selectable(true);
+        label
+    }
+}
+
+impl ClipboardEntry for ClipboardColorEntry {
+    fn get_row(&self) -> ListBoxRow {
+        self.row.clone()
+    }
+
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    fn render_row(&self) {
+        if self.row_label.borrow().is_some() {
+            return;
+        }
+
+        let swatch = self.create_swatch(Self::ROW_SWATCH_SIZE, Self::ROW_SWATCH_SIZE);
+
+        let label = Label::new(Some(&self.text));
+        label.set_xalign(0.0);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        label.style_context().add_class("clipboard-row-label");
+
+        let row_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        row_box.set_margin(Self::MARGIN);
+        row_box.set_size_request(self.row_width - (2 * Self::MARGIN), -1);
+        row_box.pack_start(&swatch, false, false, 0);
+        row_box.pack_start(&label, true, true, 0);
+
+        self.row.add(&row_box);
+        self.row.show_all();
+        self.row_label.replace(Some(label));
+    }
+
+    /// A large swatch, the colour in hex, rgb(a), hsl and named form, and its contrast
+    /// against black and white text.
+    fn create_more_info_widget(&self, _width: i32, _height: i32, _search_query: Option<String>) -> gtk::Widget {
+        let container = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+        container.set_margin(Self::MARGIN);
+        container.style_context().add_class("clipboard-color-details");
+
+        let swatch = self.create_swatch(Self::DETAIL_SWATCH_SIZE * 2, Self::DETAIL_SWATCH_SIZE);
+        swatch.set_halign(gtk::Align::Start);
+
+        let error_label = create_error_label();
+
+        let report = {
+            let error_label = error_label.clone();
+            move |result: Result<(), ClipboardError>| report_result(&error_label, result)
+        };
+
+        container.pack_start(&swatch, false, false, 0);
+        container.pack_start(&self.create_forms_grid(report), false, false, 0);
+        container.pack_start(&self.create_contrast_label(), false, false, 0);
+        container.pack_start(&error_label, false, false, 0);
+
+        container.upcast::<Widget>()
+    }
+
+    fn contains_text(&self, search_text: &String) -> bool {
+        self.text.to_lowercase().contains(&search_text.to_lowercase())
+    }
+
+    fn set_highlight_in_row(&self, search_query: Option<String>) {
+        let row_label = self.row_label();
+        let format = highlight_format(&row_label, self.row.is_selected());
+        let highlighted = search_query
+            .filter(|query| !query.is_empty())
+            .and_then(|query| highlight_in_text(&self.text, &query, &format));
+        match highlighted {
+            Some(highlighted) => row_label.set_markup(&highlighted),
+            None => row_label.set_text(&self.text),
+        }
+    }
+
+    /// With `copy_path`, copies the colour as a hex literal.
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError> {
+        if copy_path {
+            copy_text_to_clipboard(&self.color.to_hex())
+        } else {
+            copy_to_clipboard_by_gpaste_uuid(&self.uuid)
+        }
+    }
+
+    fn open_in_external_app(&self) -> Result<(), ClipboardError> {
+        let file_path = write_session_file(&self.uuid, &self.text, "txt")?;
+        open_in_external_app(&file_path)
+    }
+
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError> {
+        let file_path = write_session_file(&self.uuid, &self.text, "txt")?;
+        open_in_editor(editor_command, &file_path)
+    }
+}
@@ -0,0 +1,385 @@
+use gtk::{
+    glib, prelude::*, Box as GTKBox, Button, CellRendererText, Label, ListBoxRow, Orientation, PolicyType,
+    ScrolledWindow, TreeIter, TreeStore, TreeView, TreeViewColumn, Widget,
+};
+use serde_json::Value;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    copy_text_to_clipboard, copy_to_clipboard_by_gpaste_uuid, open_in_editor, open_in_external_app,
+    session_files::write_session_file,
+    structured_data::{child_path, child_pointer, StructuredData},
+    syntax_highlight::{highlight_text, Language},
+    ClipboardError,
+};
+
+use super::{
+    clipboard_entry::{create_error_label, highlight_format, highlight_in_text, report_result, ClipboardEntry},
+    code_view::CodeView,
+};
+
+/// Copied JSON, YAML or TOML. The detail view shows the data as a collapsible tree, or the
+/// parse error when the data is invalid.
+#[derive(Debug, Clone)]
+pub struct ClipboardDataEntry {
+    data: Rc<StructuredData>,
+    text: String,
+    /// The first lines of the text, shown in the row.
+    preview: String,
+    uuid: String,
+    row: ListBoxRow,
+    row_width: i32,
+    row_label: RefCell<Option<Label>>,
+}
+
+impl ClipboardDataEntry {
+    const MARGIN: i32 = 10;
+    const ESTIMATED_LINE_HEIGHT: i32 = 18;
+    /// Larger data is listed only in part, as filling the tree would stall the detail view.
+    const MAX_TREE_NODES: usize = 5000;
+    const MAX_VALUE_PREVIEW_CHARS: usize = 200;
+
+    const KEY_COLUMN: u32 = 0;
+    const VALUE_COLUMN: u32 = 1;
+    const PATH_COLUMN: u32 = 2;
+    const POINTER_COLUMN: u32 = 3;
+
+    pub fn new(data: StructuredData, text: String, uuid: String, row_width: i32, row_max_lines: i32) -> Self {
+        let max_lines = row_max_lines.max(1) as usize;
+        let mut preview = text.trim().lines().take(max_lines).collect::<Vec<_>>().join("\n");
+        if text.trim().lines().count() > max_lines {
+            preview.push_str("\n...");
+        }
+        let row_lines = preview.lines().count() as i32 + 1;
+
+        let row = ListBoxRow::new();
+        row.style_context().add_class("clipboard-row");
+        row.set_size_request(row_width, row_lines * Self::ESTIMATED_LINE_HEIGHT + (2 * Self::MARGIN));
+        Self {
+            data: Rc::new(data),
+            text,
+            preview,
+            uuid,
+            row,
+            row_width,
+            row_label: RefCell::new(None),
+        }
+    }
+
+    fn row_label(&self) -> Label {
+        self.render_row();
+        self.row_label.borrow().clone().expect("render_row creates the row label")
+    }
+
+    /// One-line form of a value for the tree: containers show their size, long strings are cut.
+    fn value_preview(value: &Value) -> String {
+        match value {
+            Value::Object(object) if object.len() == 1 => "{1 key}".to_string(),
+            Value::Object(object) => format!("{{{} keys}}", object.len()),
+            Value::Array(array) if array.len() == 1 => "[1 item]".to_string(),
+            Value::Array(array) => format!("[{} items]", array.len()),
+            Value::String(string) => {
+                let mut preview: String = string.chars().take(Self::MAX_VALUE_PREVIEW_CHARS).collect();
+                if preview.len() < string.len() {
+                    preview.push('…');
+                }
+                Value::String(preview.replace('\n', " ")).to_string()
+            }
+            value => value.to_string(),
+        }
+    }
+
+    /// Adds the children of `value` below `parent`, stopping once `remaining` nodes are used up.
+    /// Returns whether every child was added.
+    fn insert_children(
+        store: &TreeStore,
+        parent: Option<&TreeIter>,
+        value: &Value,
+        path: &str,
+        pointer: &str,
+        remaining: &mut usize,
+    ) -> bool {
+        let children: Box<dyn Iterator<Item = (String, String, String, &Value)>> = match value {
+            Value::Object(object) => Box::new(object.iter().map(|(key, child)| {
+                (key.clone(), child_path(path, Some(key), None), child_pointer(pointer, key), child)
+            })),
+            Value::Array(array) => Box::new(array.iter().enumerate().map(|(index, child)| {
+                (
+                    format!("[{}]", index),
+                    child_path(path, None, Some(index)),
+                    child_pointer(pointer, &index.to_string()),
+                    child,
+                )
+            })),
+            _ => return true,
+        };
+
+        for (key, child_path, child_pointer, child) in children {
+            if *remaining == 0 {
+                return false;
+            }
+            *remaining -= 1;
+            let iter = store.insert_with_values(
+                parent,
+                None,
+                &[
+                    (Self::KEY_COLUMN, &key),
+                    (Self::VALUE_COLUMN, &Self::value_preview(child)),
+                    (Self::PATH_COLUMN, &child_path),
+                    (Self::POINTER_COLUMN, &child_pointer),
+                ],
+            );
+            if !Self::insert_children(store, Some(&iter), child, &child_path, &child_pointer, remaining) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn create_tree_view(store: &TreeStore) -> TreeView {
+        let tree_view = TreeView::with_model(store);
+        tree_view.set_headers_visible(false);
+        tree_view.set_enable_tree_lines(true);
+        tree_view.set_tooltip_column(Self::PATH_COLUMN as i32);
+
+        for (column_index, expand) in [(Self::KEY_COLUMN, false), (Self::VALUE_COLUMN, true)] {
+            let column = TreeViewColumn::new();
+            let renderer = CellRendererText::new();
+            renderer.set_property("ellipsize", gtk::pango::EllipsizeMode::End);
+            column.pack_start(&renderer, true);
+            column.add_attribute(&renderer, "text", column_index as i32);
+            column.set_expand(expand);
+            column.set_resizable(true);
+            tree_view.append_column(&column);
+        }
+
+        tree_view
+    }
+
+    fn selected_column(tree_view: &TreeView, column: u32) -> Option<String> {
+        let (model, iter) = tree_view.selection().selected()?;
+        model.value(&iter, column as i32).get::<String>().ok()
+    }
+
+    /// The value at `pointer`: strings as they are, anything else as pretty-printed JSON.
+    fn value_text(&self, pointer: &str) -> Option<String> {
+        let value = self.data.value.as_ref().ok()?.pointer(pointer)?;
+        match value {
+            Value::String(string) => Some(string.clone()),
+            value => serde_json::to_string_pretty(value).ok(),
+        }
+    }
+
+    fn create_error_widget(&self, search_query: Option<String>) -> Widget {
+        let container = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+
+        let message = match &self.data.value {
+            Err(error) if error.location.is_some() => format!("Invalid {} at {}", self.data.format, error),
+            Err(error) => format!("Invalid {}: {}", self.data.format, error),
+            Ok(_) => format!("Invalid {}", self.data.format),
+        };
+        let error_label = Label::new(Some(&message));
+        error_label.set_xalign(0.0);
+        error_label.set_line_wrap(true);
+        error_label.set_selectable(true);
+        error_label.style_context().add_class("clipboard-error");
+
+        let language = Language::for_path(&format!("data.{}", self.data.format.extension()));
+        let code_view = CodeView::new(language, search_query);
+        let styles = match language {
+            Some(language) => highlight_text(&self.text, language, code_view.theme_variant()),
+            None => Vec::new(),
+        };
+        code_view.append(&self.text, &styles);
+
+        container.pack_start(&error_label, false, false, 0);
+        container.pack_start(&code_view.widget(), true, true, 0);
+        container.upcast::<Widget>()
+    }
+}
+
+impl ClipboardEntry for ClipboardDataEntry {
+    fn get_row(&self) -> ListBoxRow {
+        self.row.clone()
+    }
+
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    fn render_row(&self) {
+        if self.row_label.borrow().is_some() {
+            return;
+        }
+
+        let summary_label = Label::new(Some(&self.data.summary()));
+        summary_label.set_xalign(0.0);
+        summary_label.style_context().add_class("dim-label");
+
+        let label = Label::new(Some(&self.preview));
+        label.set_xalign(0.0);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        label.style_context().add_class("clipboard-row-label");
+
+        let row_box = GTKBox::new(Orientation::Vertical, 2);
+        row_box.set_margin(Self::MARGIN);
+        row_box.set_size_request(self.row_width - (2 * Self::MARGIN), -1);
+        row_box.pack_start(&summary_label, false, false, 0);
+        row_box.pack_start(&label, false, false, 0);
+
+        self.row.set_size_request(self.row_width, -1);
+        self.row.add(&row_box);
+        self.row.show_all();
+        self.row_label.replace(Some(label));
+    }
+
+    /// Valid data is shown as a tree with its first level expanded, with buttons to copy it
+    /// minified, pretty-printed, or just the selected value. Invalid data is shown as text
+    /// below the parse error.
+    fn create_more_info_widget(&self, _width: i32, _height: i32, search_query: Option<String>) -> gtk::Widget {
+        let container = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+        container.set_margin(Self::MARGIN);
+        container.style_context().add_class("clipboard-data-details");
+
+        let summary_label = Label::new(Some(&self.data.summary()));
+        summary_label.set_xalign(0.0);
+        summary_label.style_context().add_class("dim-label");
+        container.pack_start(&summary_label, false, false, 0);
+
+        let Ok(value) = &self.data.value else {
+            container.pack_start(&self.create_error_widget(search_query), true, true, 0);
+            return container.upcast::<Widget>();
+        };
+
+        let error_label = create_error_label();
+
+        let report = {
+            let error_label = error_label.clone();
+            move |result: Result<(), ClipboardError>| report_result(&error_label, result)
+        };
+
+        let minified_button = Button::with_label("Copy minified");
+        let pretty_button = Button::with_label("Copy pretty-printed");
+        let value_button = Button::with_label("Copy value");
+        value_button.set_tooltip_text(Some("Copy the selected value"));
+        value_button.set_sensitive(false);
+
+        let button_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        for button in [&minified_button, &pretty_button, &value_button] {
+            button.set_focus_on_click(false);
+            button_box.pack_start(button, false, false, 0);
+        }
+
+        let path_label = Label::new(Some("$"));
+        path_label.set_xalign(0.0);
+        path_label.set_selectable(true);
+        path_label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        path_label.style_context().add_class("dim-label");
+
+        let store = TreeStore::new(&[glib::Type::STRING, glib::Type::STRING, glib::Type::STRING, glib::Type::STRING]);
+        let mut remaining = Self::MAX_TREE_NODES;
+        let complete = Self::insert_children(&store, None, value, "$", "", &mut remaining);
+        if !complete {
+            summary_label.set_text(&format!(
+                "{} · only the first {} values are listed",
+                self.data.summary(),
+                Self::MAX_TREE_NODES
+            ));
+        }
+
+        let tree_view = Self::create_tree_view(&store);
+        if let Some(iter) = store.iter_first() {
+            loop {
+                if let Some(path) = store.path(&iter) {
+                    tree_view.expand_row(&path, false);
+                }
+                if !store.iter_next(&iter) {
+                    break;
+                }
+            }
+        }
+
+        let scrolled_window = ScrolledWindow::new(gtk::Adjustment::NONE, gtk::Adjustment::NONE);
+        scrolled_window.set_policy(PolicyType::Automatic, PolicyType::Automatic);
+        scrolled_window.add(&tree_view);
+
+        let minified = self.data.minified();
+        minified_button.set_sensitive(minified.is_some());
+        let report_minified = report.clone();
+        minified_button.connect_clicked(move |_| {
+            if let Some(minified) = &minified {
+                report_minified(copy_text_to_clipboard(minified));
+            }
+        });
+
+        let pretty = self.data.pretty();
+        pretty_button.set_sensitive(pretty.is_some());
+        let report_pretty = report.clone();
+        pretty_button.connect_clicked(move |_| {
+            if let Some(pretty) = &pretty {
+                report_pretty(copy_text_to_clipboard(pretty));
+            }
+        });
+
+        let entry = self.clone();
+        let selected_tree_view = tree_view.clone();
+        value_button.connect_clicked(move |_| {
+            let value = Self::selected_column(&selected_tree_view, Self::POINTER_COLUMN)
+                .and_then(|pointer| entry.value_text(&pointer));
+            if let Some(value) = value {
+                report(copy_text_to_clipboard(&value));
+            }
+        });
+
+        let selection_value_button = value_button.clone();
+        let selection_path_label = path_label.clone();
+        tree_view.selection().connect_changed(move |selection| {
+            let path = selection
+                .selected()
+                .and_then(|(model, iter)| model.value(&iter, Self::PATH_COLUMN as i32).get::<String>().ok());
+            selection_value_button.set_sensitive(path.is_some());
+            selection_path_label.set_text(path.as_deref().unwrap_or("$"));
+        });
+
+        container.pack_start(&button_box, false, false, 0);
+        container.pack_start(&path_label, false, false, 0);
+        container.pack_start(&error_label, false, false, 0);
+        container.pack_start(&scrolled_window, true, true, 0);
+
+        container.upcast::<Widget>()
+    }
+
+    fn contains_text(&self, search_text: &String) -> bool {
+        self.text.to_lowercase().contains(&search_text.to_lowercase())
+    }
+
+    fn set_highlight_in_row(&self, search_query: Option<String>) {
+        let row_label = self.row_label();
+        let format = highlight_format(&row_label, self.row.is_selected());
+        let highlighted = search_query
+            .filter(|query| !query.is_empty())
+            .and_then(|query| highlight_in_text(&self.preview, &query, &format));
+        match highlighted {
+            Some(highlighted) => row_label.set_markup(&highlighted),
+            None => row_label.set_text(&self.preview),
+        }
+    }
+
+    /// With `copy_path`, copies the data pretty-printed.
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError> {
+        match self.data.pretty() {
+            Some(pretty) if copy_path => copy_text_to_clipboard(&pretty),
+            _ => copy_to_clipboard_by_gpaste_uuid(&self.uuid),
+        }
+    }
+
+    fn open_in_external_app(&self) -> Result<(), ClipboardError> {
+        let file_path = write_session_file(&self.uuid, &self.text, self.data.format.extension())?;
+        open_in_external_app(&file_path)
+    }
+
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError> {
+        let file_path = write_session_file(&self.uuid, &self.text, self.data.format.extension())?;
+        open_in_editor(editor_command, &file_path)
+    }
+}
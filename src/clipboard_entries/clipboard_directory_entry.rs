@@ -0,0 +1,332 @@
+use gtk::{
+    gio,
+    glib::{self, Continue},
+    prelude::*,
+    Box as GTKBox, Button, CellRendererPixbuf, CellRendererText, Image, Label, ListBoxRow, Orientation,
+    PolicyType, ScrolledWindow, Spinner, TreeIter, TreeStore, TreeView, TreeViewColumn, Widget,
+};
+use std::cell::RefCell;
+
+use crate::{
+    copy_text_to_clipboard, copy_to_clipboard_by_gpaste_uuid,
+    directory_listing::{list_directory_async, DirectoryListing, ListingEvent, ListingLimits, ListingNode},
+    file_info::format_size,
+    open_in_editor, open_in_external_app, ClipboardError,
+};
+
+use super::{
+    clipboard_entry::{
+        create_error_label, highlight_format, highlight_in_text, report_result, ClipboardEntry,
+    },
+};
+
+/// A copied folder. The detail view lists its contents as a tree.
+#[derive(Debug, Clone)]
+pub struct ClipboardDirectoryEntry {
+    path: String,
+    uuid: String,
+    row: ListBoxRow,
+    row_height: i32,
+    row_label: RefCell<Option<Label>>,
+    listing_limits: ListingLimits,
+}
+
+impl ClipboardDirectoryEntry {
+    const MARGIN: i32 = 10;
+    const ICON_SIZE: i32 = 32;
+
+    const ICON_COLUMN: u32 = 0;
+    const NAME_COLUMN: u32 = 1;
+    const SIZE_COLUMN: u32 = 2;
+    const PATH_COLUMN: u32 = 3;
+
+    /// `listing_limits` bound how much of the folder the detail view lists.
+    pub fn new(
+        path: String,
+        uuid: String,
+        row_width: i32,
+        max_row_height: i32,
+        listing_limits: ListingLimits,
+    ) -> Self {
+        let row_height = (Self::ICON_SIZE + (2 * Self::MARGIN)).min(max_row_height);
+        let row = ListBoxRow::new();
+        row.style_context().add_class("clipboard-row");
+        row.set_size_request(row_width, row_height);
+        Self {
+            path,
+            uuid,
+            row,
+            row_height,
+            row_label: RefCell::new(None),
+            listing_limits,
+        }
+    }
+
+    fn create_row_box(&self, height: i32) -> (GTKBox, Label) {
+        let label = Label::new(Some(&self.path));
+        label.set_xalign(0.0);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        label.style_context().add_class("clipboard-row-label");
+
+        let icon = Image::from_icon_name(Some("folder"), gtk::IconSize::LargeToolbar);
+        icon.set_pixel_size(height - (2 * Self::MARGIN));
+
+        let row_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        row_box.set_margin(Self::MARGIN);
+        row_box.pack_start(&icon, false, false, 0);
+        row_box.pack_start(&label, true, true, 0);
+
+        (row_box, label)
+    }
+
+    fn row_label(&self) -> Label {
+        self.render_row();
+        self.row_label.borrow().clone().expect("render_row creates the row label")
+    }
+
+    fn node_icon(node: &ListingNode) -> gio::Icon {
+        if node.is_dir {
+            return gio::ThemedIcon::new("folder").upcast::<gio::Icon>();
+        }
+        let (content_type, _) = gio::content_type_guess(Some(&node.path), &[]);
+        gio::content_type_get_icon(&content_type)
+    }
+
+    fn insert_nodes(store: &TreeStore, parent: Option<&TreeIter>, nodes: &[ListingNode]) {
+        for node in nodes {
+            let size = match node.size {
+                Some(size) => format_size(size),
+                None => String::new(),
+            };
+            let iter = store.insert_with_values(
+                parent,
+                None,
+                &[
+                    (Self::ICON_COLUMN, &Self::node_icon(node)),
+                    (Self::NAME_COLUMN, &node.name),
+                    (Self::SIZE_COLUMN, &size),
+                    (Self::PATH_COLUMN, &node.path),
+                ],
+            );
+            Self::insert_nodes(store, Some(&iter), &node.children);
+        }
+    }
+
+    /// Item count and total size, e.g. `12 items · 3.4 MB`, noting when not everything is listed.
+    fn summary(listing: &DirectoryListing) -> String {
+        let count = if listing.count_incomplete {
+            format!("More than {} items", listing.item_count)
+        } else if listing.item_count == 1 {
+            "1 item".to_string()
+        } else {
+            format!("{} items", listing.item_count)
+        };
+        let mut summary = format!("{} · {}", count, format_size(listing.total_size));
+        if listing.truncated {
+            summary.push_str(" · not all items are listed");
+        }
+        summary
+    }
+
+    fn create_tree_view(store: &TreeStore) -> TreeView {
+        let tree_view = TreeView::with_model(store);
+        tree_view.set_headers_visible(false);
+        tree_view.set_enable_search(false);
+        tree_view.set_tooltip_column(Self::PATH_COLUMN as i32);
+
+        let name_column = TreeViewColumn::new();
+        let icon_renderer = CellRendererPixbuf::new();
+        name_column.pack_start(&icon_renderer, false);
+        name_column.add_attribute(&icon_renderer, "gicon", Self::ICON_COLUMN as i32);
+        let name_renderer = CellRendererText::new();
+        name_renderer.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        name_column.pack_start(&name_renderer, true);
+        name_column.add_attribute(&name_renderer, "text", Self::NAME_COLUMN as i32);
+        name_column.set_expand(true);
+        tree_view.append_column(&name_column);
+
+        let size_column = TreeViewColumn::new();
+        let size_renderer = CellRendererText::new();
+        size_renderer.set_xalign(1.0);
+        size_column.pack_start(&size_renderer, false);
+        size_column.add_attribute(&size_renderer, "text", Self::SIZE_COLUMN as i32);
+        tree_view.append_column(&size_column);
+
+        tree_view
+    }
+
+    fn selected_path(tree_view: &TreeView) -> Option<String> {
+        let (model, iter) = tree_view.selection().selected()?;
+        model.value(&iter, Self::PATH_COLUMN as i32).get::<String>().ok()
+    }
+
+    fn action_button(label: &str, icon_name: &str) -> Button {
+        let button = Button::with_label(label);
+        button.set_image(Some(&Image::from_icon_name(Some(icon_name), gtk::IconSize::Button)));
+        button.set_always_show_image(true);
+        button.set_focus_on_click(false);
+        button
+    }
+}
+
+impl ClipboardEntry for ClipboardDirectoryEntry {
+    fn get_row(&self) -> ListBoxRow {
+        self.row.clone()
+    }
+
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    fn render_row(&self) {
+        if self.row_label.borrow().is_some() {
+            return;
+        }
+
+        let (row_box, label) = self.create_row_box(self.row_height);
+        self.row.add(&row_box);
+        self.row.show_all();
+        self.row_label.replace(Some(label));
+    }
+
+    /// The folder is listed on a worker thread each time the detail view opens. Activating an
+    /// item opens it; the buttons open the folder or copy the selected item's path.
+    fn create_more_info_widget(&self, _width: i32, _height: i32, _search_query: Option<String>) -> gtk::Widget {
+        let container = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+        container.set_margin(Self::MARGIN);
+        container.style_context().add_class("clipboard-directory-listing");
+
+        let path_label = Label::new(Some(&self.path));
+        path_label.set_xalign(0.0);
+        path_label.set_line_wrap(true);
+        path_label.set_selectable(true);
+
+        let summary_label = Label::new(Some("Listing folder…"));
+        summary_label.set_xalign(0.0);
+        summary_label.style_context().add_class("dim-label");
+
+        let error_label = create_error_label();
+
+        let open_button = Self::action_button("Open folder", "folder-open-symbolic");
+        let copy_button = Self::action_button("Copy path", "edit-copy-symbolic");
+        copy_button.set_tooltip_text(Some("Copy the path of the selected item"));
+        copy_button.set_sensitive(false);
+
+        let button_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        button_box.pack_start(&open_button, false, false, 0);
+        button_box.pack_start(&copy_button, false, false, 0);
+
+        let store = TreeStore::new(&[
+            gio::Icon::static_type(),
+            glib::Type::STRING,
+            glib::Type::STRING,
+            glib::Type::STRING,
+        ]);
+        let tree_view = Self::create_tree_view(&store);
+
+        let scrolled_window = ScrolledWindow::new(gtk::Adjustment::NONE, gtk::Adjustment::NONE);
+        scrolled_window.set_policy(PolicyType::Never, PolicyType::Automatic);
+        scrolled_window.add(&tree_view);
+
+        let spinner = Spinner::new();
+        spinner.start();
+
+        container.pack_start(&path_label, false, false, 0);
+        container.pack_start(&summary_label, false, false, 0);
+        container.pack_start(&button_box, false, false, 0);
+        container.pack_start(&error_label, false, false, 0);
+        container.pack_start(&spinner, true, true, 0);
+
+        let report = {
+            let error_label = error_label.clone();
+            move |result: Result<(), ClipboardError>| report_result(&error_label, result)
+        };
+
+        let path = self.path.clone();
+        let report_open = report.clone();
+        open_button.connect_clicked(move |_| report_open(open_in_external_app(&path)));
+
+        let selected_tree_view = tree_view.clone();
+        let report_copy = report.clone();
+        copy_button.connect_clicked(move |_| {
+            if let Some(path) = Self::selected_path(&selected_tree_view) {
+                report_copy(copy_text_to_clipboard(&path));
+            }
+        });
+
+        let selection_copy_button = copy_button.clone();
+        tree_view
+            .selection()
+            .connect_changed(move |selection| selection_copy_button.set_sensitive(selection.count_selected_rows() > 0));
+
+        tree_view.connect_row_activated(move |tree_view, _, _| {
+            if let Some(path) = Self::selected_path(tree_view) {
+                report(open_in_external_app(&path));
+            }
+        });
+
+        // Once the view is gone, the next progress report finds the receiver dropped and the
+        // walk stops.
+        let listed_container = container.downgrade();
+        list_directory_async(&self.path, self.listing_limits).attach(None, move |event| {
+            let Some(listed_container) = listed_container.upgrade() else {
+                return Continue(false);
+            };
+            let listing = match event {
+                ListingEvent::Progress { item_count } => {
+                    summary_label.set_text(&format!("Listing folder… {} items", item_count));
+                    return Continue(true);
+                }
+                ListingEvent::Finished(listing) => listing,
+                ListingEvent::Failed(error) => {
+                    listed_container.remove(&spinner);
+                    summary_label.set_text(&format!("Could not list the folder: {}", error));
+                    return Continue(false);
+                }
+            };
+            listed_container.remove(&spinner);
+            summary_label.set_text(&Self::summary(&listing));
+            if !listing.nodes.is_empty() {
+                Self::insert_nodes(&store, None, &listing.nodes);
+                listed_container.pack_start(&scrolled_window, true, true, 0);
+                scrolled_window.show_all();
+            }
+            Continue(false)
+        });
+
+        container.upcast::<Widget>()
+    }
+
+    fn contains_text(&self, search_text: &String) -> bool {
+        self.path.to_lowercase().contains(&search_text.to_lowercase())
+    }
+
+    fn set_highlight_in_row(&self, search_query: Option<String>) {
+        let row_label = self.row_label();
+        let format = highlight_format(&row_label, self.row.is_selected());
+        let highlighted = search_query
+            .filter(|query| !query.is_empty())
+            .and_then(|query| highlight_in_text(&self.path, &query, &format));
+        match highlighted {
+            Some(highlighted) => row_label.set_markup(&highlighted),
+            None => row_label.set_text(&self.path),
+        }
+    }
+
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError> {
+        if copy_path {
+            copy_text_to_clipboard(&self.path)
+        } else {
+            copy_to_clipboard_by_gpaste_uuid(&self.uuid)
+        }
+    }
+
+    fn open_in_external_app(&self) -> Result<(), ClipboardError> {
+        open_in_external_app(&self.path)
+    }
+
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError> {
+        open_in_editor(editor_command, &self.path)
+    }
+}
+
@@ -0,0 +1,284 @@
+use gtk::{
+    gdk,
+    gdk_pixbuf::{prelude::*, PixbufLoader},
+    glib::{self, Continue},
+    prelude::*,
+    Box as GTKBox, Clipboard, Image, Label, ListBoxRow, Orientation, Spinner, Widget,
+};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::Arc,
+    thread,
+};
+
+use crate::{
+    copy_text_to_clipboard,
+    encrypted_history::{EncryptedHistory, EncryptedKind},
+    image_loader::load_image_data_async,
+    open_in_editor, open_in_external_app,
+    secret_detection::detect_secret,
+    session_files::{text_file_extension, write_session_file},
+    ClipboardError,
+};
+
+use super::{
+    clipboard_entry::ClipboardEntry,
+    clipboard_text_entry::ClipboardTextEntry,
+    image_view::{ImageCommand, ImageView},
+};
+
+/// An item of the encrypted history. The row only tells what it holds; the content is
+/// decrypted on a worker thread when the detail view is shown, and when it is copied or opened.
+#[derive(Debug, Clone)]
+pub struct ClipboardEncryptedEntry {
+    uuid: String,
+    kind: EncryptedKind,
+    /// What the row shows, e.g. `Encrypted text, 1.2 KB`.
+    description: String,
+    history: Arc<EncryptedHistory>,
+    row: ListBoxRow,
+    row_width: i32,
+    row_height: i32,
+    rendered: Cell<bool>,
+    /// Shows decrypted text once the detail view decrypted it, masking secrets like any text.
+    text_entry: Rc<RefCell<Option<ClipboardTextEntry>>>,
+    /// The zoomable image of the current detail view.
+    image_view: RefCell<Option<ImageView>>,
+}
+
+impl ClipboardEncryptedEntry {
+    const MARGIN: i32 = 10;
+    const ICON_SIZE: i32 = 32;
+
+    pub fn new(
+        uuid: String,
+        kind: EncryptedKind,
+        description: String,
+        history: Arc<EncryptedHistory>,
+        row_width: i32,
+        max_row_height: i32,
+    ) -> Self {
+        let row_height = (Self::ICON_SIZE + (2 * Self::MARGIN)).min(max_row_height);
+        let row = ListBoxRow::new();
+        row.style_context().add_class("clipboard-row");
+        row.set_size_request(row_width, row_height);
+        Self {
+            uuid,
+            kind,
+            description,
+            history,
+            row,
+            row_width,
+            row_height,
+            rendered: Cell::new(false),
+            text_entry: Rc::new(RefCell::new(None)),
+            image_view: RefCell::new(None),
+        }
+    }
+
+    fn read_text(history: &EncryptedHistory, uuid: &str) -> Result<String, ClipboardError> {
+        String::from_utf8(history.read(uuid)?).map_err(|_| ClipboardError::Decryption {
+            reason: "the decrypted entry is not text".to_string(),
+        })
+    }
+
+    /// The text entry showing `text`, masked if it looks like a secret.
+    fn create_text_entry(&self, text: String) -> ClipboardTextEntry {
+        match detect_secret(&text) {
+            Some(secret) => ClipboardTextEntry::new_sensitive(text, secret, self.uuid.clone(), self.row_width, 1),
+            None => ClipboardTextEntry::new(text, self.uuid.clone(), self.row_width, 1),
+        }
+    }
+
+    /// Decrypts the text on a worker thread and shows it in place of a spinner.
+    fn create_text_widget(&self, width: i32, height: i32, search_query: Option<String>) -> Widget {
+        if let Some(text_entry) = self.text_entry.borrow().as_ref() {
+            return text_entry.create_more_info_widget(width, height, search_query);
+        }
+
+        let container = GTKBox::new(Orientation::Vertical, 0);
+        let spinner = Spinner::new();
+        spinner.start();
+        container.pack_start(&spinner, true, true, 0);
+
+        let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+        let history = self.history.clone();
+        let uuid = self.uuid.clone();
+        let worker_sender = sender.clone();
+        let spawned = thread::Builder::new()
+            .name("decrypt-entry".to_string())
+            .spawn(move || {
+                let _ = worker_sender.send(Self::read_text(&history, &uuid));
+            });
+        if let Err(e) = spawned {
+            let _ = sender.send(Err(e.into()));
+        }
+
+        let entry = self.clone();
+        let weak_container = container.downgrade();
+        receiver.attach(None, move |decrypted: Result<String, ClipboardError>| {
+            let Some(container) = weak_container.upgrade() else {
+                return Continue(false);
+            };
+            container.remove(&spinner);
+            let widget = match decrypted {
+                Ok(text) => {
+                    let text_entry = entry.create_text_entry(text);
+                    let widget = text_entry.create_more_info_widget(width, height, search_query.clone());
+                    entry.text_entry.replace(Some(text_entry));
+                    widget
+                }
+                Err(e) => {
+                    let label = Label::new(Some(&e.to_string()));
+                    label.set_margin(Self::MARGIN);
+                    label.set_xalign(0.0);
+                    label.set_line_wrap(true);
+                    label.style_context().add_class("clipboard-error");
+                    label.upcast::<Widget>()
+                }
+            };
+            container.pack_start(&widget, true, true, 0);
+            container.show_all();
+            Continue(false)
+        });
+
+        container.upcast::<Widget>()
+    }
+
+    fn create_image_widget(&self, width: i32, height: i32) -> Widget {
+        let more_info_box = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+        more_info_box.set_margin(Self::MARGIN);
+        more_info_box.style_context().add_class("clipboard-detail-image");
+
+        let image_view = ImageView::new(width - (Self::MARGIN * 2), height - (Self::MARGIN * 2));
+        more_info_box.pack_start(&image_view.widget(), true, true, 0);
+
+        let history = self.history.clone();
+        let uuid = self.uuid.clone();
+        let read = move || {
+            history
+                .read(&uuid)
+                .map_err(|e| eprintln!("Error decrypting image {}: {}", uuid, e))
+                .ok()
+        };
+        let loaded_view = image_view.clone();
+        load_image_data_async(read, move |loaded| loaded_view.set_image(loaded.pixbuf));
+        self.image_view.replace(Some(image_view));
+
+        more_info_box.upcast::<Widget>()
+    }
+
+    /// Hands the image to the clipboard and asks the clipboard manager to keep it once the
+    /// picker exits. Whether GPaste records it cannot be told from here, so the encrypted
+    /// copy is kept.
+    fn copy_image(&self) -> Result<(), ClipboardError> {
+        let data = self.history.read(&self.uuid)?;
+        let loader = PixbufLoader::new();
+        let pixbuf = loader
+            .write(&data)
+            .and_then(|()| loader.close())
+            .ok()
+            .and_then(|()| loader.pixbuf())
+            .ok_or_else(|| ClipboardError::Decryption {
+                reason: "the decrypted image could not be decoded".to_string(),
+            })?;
+        let clipboard = Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+        clipboard.set_image(&pixbuf);
+        clipboard.store();
+        Ok(())
+    }
+
+    /// Writes the decrypted text to a session file to open it in another application.
+    fn write_text_file(&self) -> Result<String, ClipboardError> {
+        if self.kind != EncryptedKind::Text {
+            return Err(ClipboardError::OpenFailed {
+                target: self.description.clone(),
+                reason: "encrypted images are only shown here, never written to disk".to_string(),
+            });
+        }
+        let text = Self::read_text(&self.history, &self.uuid)?;
+        write_session_file(&self.uuid, &text, text_file_extension(&text))
+    }
+}
+
+impl ClipboardEntry for ClipboardEncryptedEntry {
+    fn get_row(&self) -> ListBoxRow {
+        self.row.clone()
+    }
+
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    fn render_row(&self) {
+        if self.rendered.replace(true) {
+            return;
+        }
+
+        let icon = Image::from_icon_name(Some("channel-secure-symbolic"), gtk::IconSize::LargeToolbar);
+        icon.set_pixel_size(self.row_height - (2 * Self::MARGIN));
+
+        let label = Label::new(Some(&self.description));
+        label.set_xalign(0.0);
+        label.style_context().add_class("clipboard-row-label");
+        label.style_context().add_class("dim-label");
+
+        let row_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        row_box.set_margin(Self::MARGIN);
+        row_box.style_context().add_class("clipboard-encrypted");
+        row_box.pack_start(&icon, false, false, 0);
+        row_box.pack_start(&label, true, true, 0);
+
+        self.row.add(&row_box);
+        self.row.show_all();
+    }
+
+    fn create_more_info_widget(&self, width: i32, height: i32, search_query: Option<String>) -> Widget {
+        match self.kind {
+            EncryptedKind::Text => self.create_text_widget(width, height, search_query),
+            EncryptedKind::Image => self.create_image_widget(width, height),
+        }
+    }
+
+    /// Copied text goes back into the backend's history as its newest item, so the encrypted
+    /// copy is removed; it is encrypted again once something else is copied.
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError> {
+        if copy_path {
+            return Ok(());
+        }
+        if self.kind == EncryptedKind::Image {
+            return self.copy_image();
+        }
+
+        copy_text_to_clipboard(&Self::read_text(&self.history, &self.uuid)?)?;
+        if let Err(e) = self.history.remove(&self.uuid) {
+            eprintln!("Error removing encrypted entry {}: {}", self.uuid, e);
+        }
+        Ok(())
+    }
+
+    fn open_in_external_app(&self) -> Result<(), ClipboardError> {
+        open_in_external_app(&self.write_text_file()?)
+    }
+
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError> {
+        open_in_editor(editor_command, &self.write_text_file()?)
+    }
+
+    /// Only known once the detail view decrypted the text.
+    fn is_sensitive(&self) -> bool {
+        self.text_entry.borrow().as_ref().is_some_and(|text_entry| text_entry.is_sensitive())
+    }
+
+    fn toggle_revealed(&self) -> bool {
+        self.text_entry.borrow().as_ref().is_some_and(|text_entry| text_entry.toggle_revealed())
+    }
+
+    fn handle_image_command(&self, command: ImageCommand) -> bool {
+        match self.image_view.borrow().as_ref() {
+            Some(image_view) => image_view.run(command),
+            None => false,
+        }
+    }
+}
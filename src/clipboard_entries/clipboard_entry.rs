@@ -1,14 +1,170 @@
-use gtk::ListBoxRow;
-use std::io;
+use gtk::{glib::markup_escape_text, prelude::*, Label, ListBoxRow, StyleContext, Widget};
 
+use crate::ClipboardError;
+
+use super::image_view::ImageCommand;
+
+/// Named colours the stylesheets define for search matches, with the fallback used when a
+/// stylesheet does not define them.
+const HIGHLIGHT_BACKGROUND: (&str, &str) = ("clipboard_highlight_bg", "#E95420");
+const HIGHLIGHT_FOREGROUND: (&str, &str) = ("clipboard_highlight_fg", "#FFFFFF");
+const HIGHLIGHT_SELECTED_BACKGROUND: (&str, &str) = ("clipboard_highlight_selected_bg", "#333333");
+
+fn lookup_hex_color(style_context: &StyleContext, (name, fallback): (&str, &str)) -> String {
+    match style_context.lookup_color(name) {
+        Some(rgba) => format!(
+            "#{:02x}{:02x}{:02x}",
+            (rgba.red() * 255.0).round() as u8,
+            (rgba.green() * 255.0).round() as u8,
+            (rgba.blue() * 255.0).round() as u8,
+        ),
+        None => fallback.to_string(),
+    }
+}
+
+/// Pango markup template for a search match, with `%s` standing for the escaped match.
+/// Colours come from the `@define-color` entries of the active stylesheets, since Pango
+/// spans cannot carry CSS classes.
+pub fn highlight_format(widget: &impl IsA<Widget>, selected: bool) -> String {
+    let (background, foreground) = highlight_colors(widget, selected);
+    format!(
+        "<span background='{}' foreground='{}' weight='bold'>%s</span>",
+        background, foreground,
+    )
+}
+
+/// Background and foreground colour of a search match, as `#rrggbb`.
+pub fn highlight_colors(widget: &impl IsA<Widget>, selected: bool) -> (String, String) {
+    let style_context = widget.style_context();
+    let background = if selected {
+        HIGHLIGHT_SELECTED_BACKGROUND
+    } else {
+        HIGHLIGHT_BACKGROUND
+    };
+    (
+        lookup_hex_color(&style_context, background),
+        lookup_hex_color(&style_context, HIGHLIGHT_FOREGROUND),
+    )
+}
+
+/// Pango markup of `text` with every case-insensitive match of `query` wrapped in `format`, a
+/// template from [`highlight_format`]. `None` if nothing matches.
+pub fn highlight_in_text(text: &str, query: &str, format: &str) -> Option<String> {
+    if query.is_empty() {
+        return None;
+    }
+    
+    let query_lower = query.to_lowercase();
+    let text_lower = text.to_lowercase();
+    // Lowercasing can change byte lengths, which would shift the matches.
+    if text_lower.len() != text.len() {
+        return None;
+    }
+    
+    let mut result = String::new();
+    let mut last_end = 0;
+    let mut found_match = false; 
+    
+    let mut search_start = 0;
+    while let Some(match_start) = text_lower[search_start..].find(&query_lower) {
+        let absolute_start = search_start + match_start;
+        let absolute_end = absolute_start + query_lower.len();
+        search_start = absolute_end;
+        if !text.is_char_boundary(absolute_start) || !text.is_char_boundary(absolute_end) {
+            continue;
+        }
+        found_match = true; 
+        
+        if absolute_start > last_end {
+            result.push_str(&markup_escape_text(&text[last_end..absolute_start]));
+        }
+        
+        let matched_text = &text[absolute_start..absolute_end];
+        let highlighted = format.replace("%s", &markup_escape_text(matched_text));
+        result.push_str(&highlighted);
+        
+        last_end = absolute_end;
+    }
+    
+    if !found_match {
+        return None; 
+    }
+    
+    if last_end < text.len() {
+        result.push_str(&markup_escape_text(&text[last_end..]));
+    }
+    
+    Some(result)
+}
+
+/// Label under an entry's buttons showing why the last action failed. Hidden until then.
+pub fn create_error_label() -> Label {
+    let error_label = Label::new(None);
+    error_label.set_xalign(0.0);
+    error_label.set_line_wrap(true);
+    error_label.style_context().add_class("clipboard-error");
+    error_label.set_no_show_all(true);
+    error_label
+}
+
+/// Shows the error of a failed action in `error_label`, or hides the label if it succeeded.
+pub fn report_result(error_label: &Label, result: Result<(), ClipboardError>) {
+    match result {
+        Ok(()) => error_label.hide(),
+        Err(e) => {
+            error_label.set_text(&e.to_string());
+            error_label.show();
+        }
+    }
+}
 
 pub trait ClipboardEntry {
+    /// Returns the entry's row. Rows start out as sized placeholders; their widgets are
+    /// built by `render_row` once the row scrolls into view.
     fn get_row(&self) -> ListBoxRow;
-    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), io::Error>;
+    /// Identifier of the history item in the backend.
+    fn uuid(&self) -> &str;
+    /// Builds the row's widgets if that has not happened yet.
+    fn render_row(&self);
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError>;
     fn create_more_info_widget(&self, width: i32, height: i32, _search_query: Option<String>) -> gtk::Widget;
-    fn open_in_external_app(&self) -> Result<(), io::Error>;
+    fn open_in_external_app(&self) -> Result<(), ClipboardError>;
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError>;
     fn contains_text(&self, _search_text: &String) -> bool {
         return false;
     }
     fn set_highlight_in_row(&self, _search_text: Option<String>) {}
+    /// Whether the entry looks like a password, key or token and is masked until revealed.
+    fn is_sensitive(&self) -> bool {
+        return false;
+    }
+    /// Text of a sensitive entry, used to tell whether it is still on the clipboard.
+    fn secret_text(&self) -> Option<&str> {
+        return None;
+    }
+    /// Reveals a masked sensitive entry or masks it again. Returns whether it is revealed.
+    fn toggle_revealed(&self) -> bool {
+        return false;
+    }
+    /// Zooms or scrolls the image in the big detail view. Returns whether the entry used the
+    /// command.
+    fn handle_image_command(&self, _command: ImageCommand) -> bool {
+        return false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_matches_ignoring_case() {
+        assert_eq!(highlight_in_text("Foo & foo", "FOO", "<b>%s</b>").as_deref(), Some("<b>Foo</b> &amp; <b>foo</b>"));
+        assert_eq!(highlight_in_text("Foo", "bar", "<b>%s</b>"), None);
+    }
+
+    #[test]
+    fn skips_text_whose_length_changes_when_lowercased() {
+        assert_eq!(highlight_in_text("İstanbul", "is", "<b>%s</b>"), None);
+    }
 }
@@ -1,105 +1,357 @@
 use gtk::{
-    glib::markup_escape_text, prelude::*, Box as GTKBox, Image, Label, ListBoxRow, Orientation, Widget,
+    gio,
+    glib::Continue,
+    prelude::*,
+    Box as GTKBox, Button, Grid, Image, Label, ListBoxRow, Orientation, Widget,
 };
-use std::{fs, io};
+use std::{cell::RefCell, path::Path, rc::Rc};
 
-use crate::{copy_text_to_clipboard, copy_to_clipboard_by_gpaste_uuid, open_in_external_app};
+use crate::{
+    copy_file_contents_async, copy_text_to_clipboard, copy_to_clipboard_by_gpaste_uuid,
+    file_info::{format_permissions, format_size, format_timestamp, query_file_details_async, FileLookup, PathKind},
+    file_preview::{stream_file_preview, PreviewEvent},
+    open_containing_folder, open_files_in_editor, open_in_external_app,
+    syntax_highlight::Language,
+    ClipboardError,
+};
 
-use super::clipboard_entry::ClipboardEntry;
+use super::{
+    clipboard_entry::{
+        create_error_label, highlight_format, highlight_in_text, report_result, ClipboardEntry,
+    },
+    code_view::CodeView,
+};
 
 #[derive(Debug, Clone)]
 pub struct ClipboardFileEntry {
-    file_path: String,
+    file_paths: Vec<String>,
+    /// Text of the row label: the path of a single file, or the names of several.
+    row_text: String,
+    /// What each of `file_paths` was when the history was loaded.
+    path_kinds: Vec<PathKind>,
     uuid: String,
     row: ListBoxRow,
-    row_label: Label,
-    file_content: Result<String, String>,
+    row_height: i32,
+    row_label: RefCell<Option<Label>>,
+    preview_max_bytes: u64,
 }
 
 impl ClipboardFileEntry {
     const MARGIN: i32 = 10;
     const ICON_SIZE: i32 = 32;
-    const HIGHLIGHT_FORMAT: &'static str = "<span background='#E95420' foreground='white' weight='bold'>%s</span>";
-    const HIGHLIGHT_FORMAT_SELECTED: &'static str = "<span background='#333' foreground='white' weight='bold'>%s</span>";
 
-    pub fn new(file_path: String, uuid: String, row_width: i32, max_row_height: i32) -> Self {
+    const MAX_LISTED_NAMES: usize = 5;
+
+    /// `file_paths` are the files copied together and `path_kinds` what each of them is;
+    /// `preview_max_bytes` caps how much of a single file the detail view reads.
+    pub fn new(
+        file_paths: Vec<String>,
+        path_kinds: Vec<PathKind>,
+        uuid: String,
+        row_width: i32,
+        max_row_height: i32,
+        preview_max_bytes: u64,
+    ) -> Self {
         let row_height = if Self::ICON_SIZE + (2 * Self::MARGIN) > max_row_height {
             max_row_height
         } else {
             Self::ICON_SIZE + (2 * Self::MARGIN)
         };
-        let (row, row_label) = Self::create_entry_row(&file_path, row_width, row_height);
-        let file_content = Self::read_file_content(&file_path);
-        return Self {file_path, uuid, row, row_label, file_content}
+        let row = ListBoxRow::new();
+        row.style_context().add_class("clipboard-row");
+        row.set_size_request(row_width, row_height);
+        let row_text = match file_paths.as_slice() {
+            [file_path] => file_path.clone(),
+            _ => Self::file_names(&file_paths),
+        };
+        return Self {
+            file_paths,
+            row_text,
+            path_kinds,
+            uuid,
+            row,
+            row_height,
+            row_label: RefCell::new(None),
+            preview_max_bytes,
+        }
     }
 
-    fn create_entry_row(text: &String, width: i32, height: i32) -> (ListBoxRow, Label) {
-        let label = Label::new(Some(text));
+    fn file_names(file_paths: &[String]) -> String {
+        let mut names: Vec<String> = file_paths
+            .iter()
+            .take(Self::MAX_LISTED_NAMES)
+            .map(|path| {
+                Path::new(path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.clone())
+            })
+            .collect();
+        if file_paths.len() > Self::MAX_LISTED_NAMES {
+            names.push("…".to_string());
+        }
+        names.join(", ")
+    }
+
+    /// Count and total size of several files, e.g. `3 files · 1.2 MB`.
+    fn summary(&self) -> String {
+        let total_size: u64 = self.path_kinds.iter().map(PathKind::size).sum();
+        format!("{} files · {}", self.file_paths.len(), format_size(total_size))
+    }
+
+    fn create_row_box(&self, height: i32) -> (GTKBox, Label) {
+        let label = Label::new(Some(&self.row_text));
         label.set_xalign(0.0);
         label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        label.style_context().add_class("clipboard-row-label");
 
-        let icon = Image::from_icon_name(Some("text-x-generic"), gtk::IconSize::LargeToolbar);
+        let icon_name = if self.file_paths.len() > 1 { "folder-documents" } else { "text-x-generic" };
+        let icon = Image::from_icon_name(Some(icon_name), gtk::IconSize::LargeToolbar);
         icon.set_pixel_size(height - (2 * Self::MARGIN));
 
+        let text_box = GTKBox::new(Orientation::Vertical, 2);
+        text_box.set_valign(gtk::Align::Center);
+        text_box.pack_start(&label, false, false, 0);
+
+        if self.file_paths.len() > 1 {
+            let summary_label = Label::new(Some(&self.summary()));
+            summary_label.set_xalign(0.0);
+            summary_label.style_context().add_class("dim-label");
+            text_box.pack_start(&summary_label, false, false, 0);
+            label.set_tooltip_text(Some(&self.file_paths.join("\n")));
+        }
+
         let row_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
         row_box.set_margin(Self::MARGIN);
         row_box.pack_start(&icon, false, false, 0);
-        row_box.pack_start(&label, true, true, 0);
+        row_box.pack_start(&text_box, true, true, 0);
 
-        let row = ListBoxRow::new();
-        row.set_size_request(width, -1);
-        row.add(&row_box);
+        return (row_box, label);
+    }
+
+    fn file_icon(file_path: &str, kind: PathKind) -> Image {
+        let gicon = if kind == PathKind::Directory {
+            gio::ThemedIcon::new("folder").upcast::<gio::Icon>()
+        } else {
+            let (content_type, _) = gio::content_type_guess(Some(file_path), &[]);
+            gio::content_type_get_icon(&content_type)
+        };
+        Image::from_gicon(&gicon, gtk::IconSize::LargeToolbar)
+    }
 
-        return (row, label);
+    fn action_button(icon_name: &str, tooltip: &str) -> Button {
+        let button = Button::from_icon_name(Some(icon_name), gtk::IconSize::Button);
+        button.set_tooltip_text(Some(tooltip));
+        button.set_valign(gtk::Align::Center);
+        button.set_focus_on_click(false);
+        button
     }
 
-    fn highlight_in_text(text: &str, query: &str, format: &str) -> Option<String> {
-        if query.is_empty() {
-            return None;
+    /// One line of the file list: icon, name, size and buttons to open the file or copy its
+    /// path. Failures are shown below the line.
+    fn create_file_line(file_path: &str, kind: PathKind) -> GTKBox {
+        let name = Path::new(file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| file_path.to_string());
+
+        let name_label = Label::new(Some(&name));
+        name_label.set_xalign(0.0);
+        name_label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        name_label.set_tooltip_text(Some(file_path));
+
+        let size_text = match kind {
+            PathKind::File { size } => format_size(size),
+            PathKind::Directory => "Folder".to_string(),
+            PathKind::Missing => "Missing".to_string(),
+        };
+        let size_label = Label::new(Some(&size_text));
+        size_label.style_context().add_class("dim-label");
+
+        let error_label = create_error_label();
+
+        let open_button = Self::action_button("document-open-symbolic", "Open");
+        let copy_button = Self::action_button("edit-copy-symbolic", "Copy path");
+
+        let report = {
+            let error_label = error_label.clone();
+            move |result: Result<(), ClipboardError>| report_result(&error_label, result)
+        };
+        let report_copy = report.clone();
+
+        let path = file_path.to_string();
+        open_button.connect_clicked(move |_| report(open_in_external_app(&path)));
+        let path = file_path.to_string();
+        copy_button.connect_clicked(move |_| report_copy(copy_text_to_clipboard(&path)));
+
+        let line = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        line.pack_start(&Self::file_icon(file_path, kind), false, false, 0);
+        line.pack_start(&name_label, true, true, 0);
+        line.pack_start(&size_label, false, false, 0);
+        line.pack_start(&open_button, false, false, 0);
+        line.pack_start(&copy_button, false, false, 0);
+
+        let container = GTKBox::new(Orientation::Vertical, 2);
+        container.pack_start(&line, false, false, 0);
+        container.pack_start(&error_label, false, false, 0);
+        container
+    }
+
+    fn create_file_list(&self) -> Widget {
+        let list = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+        list.set_margin(Self::MARGIN);
+        list.style_context().add_class("clipboard-file-list");
+
+        let summary_label = Label::new(Some(&self.summary()));
+        summary_label.set_xalign(0.0);
+        summary_label.style_context().add_class("dim-label");
+        list.pack_start(&summary_label, false, false, 0);
+
+        for (file_path, kind) in self.file_paths.iter().zip(&self.path_kinds) {
+            list.pack_start(&Self::create_file_line(file_path, *kind), false, false, 0);
         }
-        
-        let query_lower = query.to_lowercase();
-        let text_lower = text.to_lowercase();
-        
-        let mut result = String::new();
-        let mut last_end = 0;
-        let mut found_match = false; 
-        
-        let mut search_start = 0;
-        while let Some(match_start) = text_lower[search_start..].find(&query_lower) {
-            found_match = true; 
-            let absolute_start = search_start + match_start;
-            let absolute_end = absolute_start + query.len();
-            
-            if absolute_start > last_end {
-                result.push_str(&markup_escape_text(&text[last_end..absolute_start]));
+
+        list.upcast::<Widget>()
+    }
+
+    /// Fills the details grid once the file has been looked up.
+    fn show_file_details(lookup: FileLookup, grid: &Grid, value_labels: &[Label; 5], status_label: &Label) {
+        let details = match lookup {
+            Ok(Some(details)) => details,
+            Ok(None) => {
+                grid.hide();
+                status_label.set_text("The file no longer exists.");
+                status_label.show();
+                return;
             }
-            
-            let matched_text = &text[absolute_start..absolute_end];
-            let highlighted = format.replace("%s", &markup_escape_text(matched_text));
-            result.push_str(&highlighted);
-            
-            last_end = absolute_end;
-            search_start = absolute_end;
-        }
-        
-        if !found_match {
-            return None; 
+            Err(reason) => {
+                grid.hide();
+                status_label.set_text(&format!("Error reading file details: {}", reason));
+                status_label.show();
+                return;
+            }
+        };
+
+        let owner = match (details.owner, details.group) {
+            (Some(owner), Some(group)) => format!("{}:{}", owner, group),
+            (Some(owner), None) => owner,
+            _ => "Unknown".to_string(),
+        };
+        let values = [
+            format!("{} ({})", details.description, details.content_type),
+            format_size(details.size),
+            format!("{} ({:o})", format_permissions(details.mode), details.mode),
+            owner,
+            details.modified.map(format_timestamp).unwrap_or_else(|| "Unknown".to_string()),
+        ];
+        for (label, value) in value_labels.iter().zip(values) {
+            label.set_text(&value);
         }
-        
-        if last_end < text.len() {
-            result.push_str(&markup_escape_text(&text[last_end..]));
+    }
+
+    /// Header of a single file's detail view: type, size, permissions, owner and modification
+    /// time, followed by buttons to show the file in its folder and to copy its text, if it is
+    /// no larger than `max_bytes`.
+    fn create_details_header(file_path: &str, max_bytes: u64) -> GTKBox {
+        const NAMES: [&str; 5] = ["Type", "Size", "Permissions", "Owner", "Modified"];
+
+        let grid = Grid::new();
+        grid.set_column_spacing(Self::MARGIN as u32);
+        grid.set_row_spacing(2);
+
+        let value_labels = NAMES.map(|_| {
+            let label = Label::new(Some("…"));
+            label.set_xalign(0.0);
+            label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+            label.set_selectable(true);
+            label
+        });
+        for (row, (name, value_label)) in NAMES.iter().zip(&value_labels).enumerate() {
+            let name_label = Label::new(Some(name));
+            name_label.set_xalign(0.0);
+            name_label.style_context().add_class("dim-label");
+            grid.attach(&name_label, 0, row as i32, 1, 1);
+            grid.attach(value_label, 1, row as i32, 1, 1);
         }
-        
-        Some(result)
+
+        let missing_label = Label::new(None);
+        missing_label.set_xalign(0.0);
+        missing_label.style_context().add_class("clipboard-error");
+        missing_label.set_no_show_all(true);
+
+        let folder_button = Button::with_label("Open folder");
+        folder_button.set_tooltip_text(Some("Show the file in its folder"));
+        folder_button.set_focus_on_click(false);
+        let contents_button = Button::with_label("Copy contents");
+        contents_button.set_tooltip_text(Some("Copy the text of the file"));
+        contents_button.set_focus_on_click(false);
+
+        let error_label = create_error_label();
+
+        let path = file_path.to_string();
+        let folder_error_label = error_label.clone();
+        folder_button.connect_clicked(move |_| report_result(&folder_error_label, open_containing_folder(&path)));
+        let path = file_path.to_string();
+        let contents_error_label = error_label.clone();
+        contents_button.connect_clicked(move |button| {
+            button.set_sensitive(false);
+            let button = button.clone();
+            let contents_error_label = contents_error_label.clone();
+            copy_file_contents_async(&path, max_bytes, move |result| {
+                button.set_sensitive(true);
+                report_result(&contents_error_label, result);
+            });
+        });
+
+        let button_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        button_box.pack_start(&folder_button, false, false, 0);
+        button_box.pack_start(&contents_button, false, false, 0);
+
+        let header = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+        header.style_context().add_class("clipboard-file-details");
+        header.pack_start(&grid, false, false, 0);
+        header.pack_start(&missing_label, false, false, 0);
+        header.pack_start(&button_box, false, false, 0);
+        header.pack_start(&error_label, false, false, 0);
+
+        query_file_details_async(file_path, move |lookup| {
+            Self::show_file_details(lookup, &grid, &value_labels, &missing_label);
+        });
+
+        header
     }
 
+    fn row_label(&self) -> Label {
+        self.render_row();
+        self.row_label.borrow().clone().expect("render_row creates the row label")
+    }
 
-    fn read_file_content(file_path: &String) -> Result<String, String> {
-        match fs::read_to_string(file_path) {
-            Ok(content) => Ok(content),
-            Err(err) => Err(format!("Error reading file: {}", err)),
+    /// Appends one part of the streamed preview to the detail view.
+    fn show_preview_event(event: PreviewEvent, code_view: &CodeView, status_label: &Label) {
+        match event {
+            PreviewEvent::Text { text, styles } => {
+                code_view.append(&text, &styles);
+                return;
+            }
+            PreviewEvent::Truncated { shown, total } => {
+                status_label.set_text(&format!(
+                    "Showing the first {} of {}.",
+                    format_size(shown),
+                    format_size(total)
+                ));
+            }
+            PreviewEvent::Binary { size } => {
+                code_view.hide();
+                status_label.set_text(&format!("Binary file ({}), no preview available.", format_size(size)));
+            }
+            PreviewEvent::Unavailable(reason) => {
+                code_view.hide();
+                status_label.set_text(&reason);
+                status_label.style_context().add_class("error");
+                status_label.style_context().add_class("clipboard-error");
+            }
         }
+        status_label.show();
     }
 }
 
@@ -108,57 +360,105 @@ impl ClipboardEntry for ClipboardFileEntry {
         return self.row.clone();
     }
 
-    fn create_more_info_widget(&self, _width: i32, _height: i32, _search_query: Option<String>) -> gtk::Widget {
-        let (content, is_error) = match &self.file_content {
-            Ok(text) => (text.clone(), false),
-            Err(err_msg) => (err_msg.clone(), true),
+    fn uuid(&self) -> &str {
+        return &self.uuid;
+    }
+
+    fn render_row(&self) {
+        if self.row_label.borrow().is_some() {
+            return;
+        }
+
+        let (row_box, label) = self.create_row_box(self.row_height);
+        self.row.add(&row_box);
+        self.row.show_all();
+        self.row_label.replace(Some(label));
+    }
+
+    /// A single file is described and read on demand each time the detail view opens, its
+    /// text streamed in as it is read. Several files are listed instead.
+    fn create_more_info_widget(&self, _width: i32, _height: i32, search_query: Option<String>) -> gtk::Widget {
+        let [file_path] = self.file_paths.as_slice() else {
+            return self.create_file_list();
         };
 
-        let label = Label::new(Some(&content));
-        label.set_xalign(0.0);
-        label.set_margin(Self::MARGIN);
-        if is_error {
-            label.style_context().add_class("error");
-        } 
+        let container = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+        container.set_margin(Self::MARGIN);
+
+        let status_label = Label::new(None);
+        status_label.set_xalign(0.0);
+        status_label.set_line_wrap(true);
+        status_label.style_context().add_class("dim-label");
+        status_label.set_no_show_all(true);
 
-        label.upcast::<Widget>()
+        // Code files are highlighted by their extension, on the reading thread.
+        let language = Language::for_path(file_path);
+        let code_view = Rc::new(CodeView::new(language, search_query));
+        let highlight = language.map(|language| (language, code_view.theme_variant()));
+
+        container.pack_start(&Self::create_details_header(file_path, self.preview_max_bytes), false, false, 0);
+        container.pack_start(&status_label, false, false, 0);
+        container.pack_start(&code_view.widget(), true, true, 0);
+
+        // Dropping the receiver once the view is gone stops the reading thread.
+        let status_label = status_label.downgrade();
+        stream_file_preview(file_path, self.preview_max_bytes, highlight).attach(None, move |event| {
+            let Some(status_label) = status_label.upgrade() else {
+                return Continue(false);
+            };
+            Self::show_preview_event(event, &code_view, &status_label);
+            Continue(true)
+        });
+
+        container.upcast::<Widget>()
     }
 
     fn contains_text(&self, search_text: &String) -> bool {
-        return self.file_path.to_lowercase().contains(&search_text.to_lowercase());
+        let search_text = search_text.to_lowercase();
+        return self.file_paths.iter().any(|path| path.to_lowercase().contains(&search_text));
     }
 
     fn set_highlight_in_row(&self, search_query: Option<String>) {
+        let row_label = self.row_label();
         match search_query.filter(|query| !query.is_empty()) {
             Some(query) => {
-                let format = if self.row.is_selected() {
-                    Self::HIGHLIGHT_FORMAT_SELECTED
-                } else {
-                    Self::HIGHLIGHT_FORMAT
-                };
+                let format = highlight_format(&row_label, self.row.is_selected());
                 
-                if let Some(highlighted) = ClipboardFileEntry::highlight_in_text(&self.file_path, &query, format) {
-                    self.row_label.set_markup(&highlighted);
+                if let Some(highlighted) = highlight_in_text(&self.row_text, &query, &format) {
+                    row_label.set_markup(&highlighted);
                 } else {
-                    self.row_label.set_text(&self.file_path);
+                    row_label.set_text(&self.row_text);
                 }
             }
             _ => {
-                self.row_label.set_text(&self.file_path);
+                row_label.set_text(&self.row_text);
             }
         }
     }
 
-    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), io::Error> {
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError> {
         if copy_path {
-            copy_text_to_clipboard(&self.file_path)
+            copy_text_to_clipboard(&self.file_paths.join("\n"))
         } else {
             copy_to_clipboard_by_gpaste_uuid(&self.uuid)
         }
     }
 
-    fn open_in_external_app(&self) -> Result<(), io::Error> {
-        open_in_external_app(&self.file_path)
+    /// Opens every file, reporting the first failure after trying all of them.
+    fn open_in_external_app(&self) -> Result<(), ClipboardError> {
+        let mut result = Ok(());
+        for file_path in &self.file_paths {
+            if let Err(e) = open_in_external_app(file_path) {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError> {
+        open_files_in_editor(editor_command, &self.file_paths)
     }
 
 }
@@ -1,18 +1,36 @@
 use gtk::{
-    gdk_pixbuf::Pixbuf, prelude::*, Box as GTKBox, Image, Label, ListBoxRow, Orientation,
+    prelude::*, Box as GTKBox, Image, Label, ListBoxRow, Orientation,
+};
+use std::{
+    cell::{Cell, RefCell},
+    sync::Arc,
 };
-use std::{fs, io};
 
-use crate::{copy_text_to_clipboard, copy_to_clipboard_by_gpaste_uuid, open_in_external_app};
+use crate::{
+    copy_text_to_clipboard, copy_to_clipboard_by_gpaste_uuid,
+    file_info::format_size,
+    image_loader::{load_full_image_async, load_image_async, load_thumbnail_async, LoadedImage},
+    open_in_editor, open_in_external_app,
+    thumbnail_cache::ThumbnailCache,
+    ClipboardError,
+};
 
-use super::clipboard_entry::ClipboardEntry;
+use super::{
+    clipboard_entry::ClipboardEntry,
+    image_view::{ImageCommand, ImageView},
+};
 
 #[derive(Debug, Clone)]
 pub struct ClipboardImageEntry {
     image_path: String,
     uuid: String,
     row: ListBoxRow,
-    pixbuf: Option<Pixbuf>
+    row_width: i32,
+    row_max_height: i32,
+    rendered: Cell<bool>,
+    thumbnail_cache: Option<Arc<ThumbnailCache>>,
+    /// The zoomable image of the current detail view.
+    image_view: RefCell<Option<ImageView>>,
 }
 
 impl ClipboardImageEntry {
@@ -23,86 +41,48 @@ impl ClipboardImageEntry {
         uuid: String,
         row_width: i32,
         row_max_height: i32,
+        thumbnail_cache: Option<Arc<ThumbnailCache>>,
     ) -> Self {
-        let pixbuf = Pixbuf::from_file(&image_path).ok();
-        let row = Self::create_entry_row(pixbuf.as_ref(), &image_path, row_width, row_max_height, Self::MARGIN);
-        Self {image_path, uuid, row, pixbuf} 
-    }
-
-    fn scale_pixbuf(pixbuf: &Pixbuf, max_width: i32, max_height: i32) -> Option<Pixbuf> {
-        let orig_width = pixbuf.width() as f64;
-        let orig_height = pixbuf.height() as f64;
-        let image_scale = orig_width / orig_height;
-        let canvas_height = max_height as f64;
-        let canvas_width = max_width as f64;
-        let canvas_scale = canvas_width / canvas_height;
-        
-        let (new_width, new_height) = if image_scale > canvas_scale {
-            (canvas_width, canvas_width / image_scale)
-        } else {
-            (canvas_height * image_scale, canvas_height)
-        };
-
-        let scaled_pixbuf = pixbuf.scale_simple(
-            new_width as i32,
-            new_height as i32,
-            gtk::gdk_pixbuf::InterpType::Bilinear
-        );
-
-        return scaled_pixbuf;
+        let row = ListBoxRow::new();
+        row.style_context().add_class("clipboard-row");
+        row.set_size_request(row_width, row_max_height);
+        Self {
+            image_path,
+            uuid,
+            row,
+            row_width,
+            row_max_height,
+            rendered: Cell::new(false),
+            thumbnail_cache,
+            image_view: RefCell::new(None),
+        }
     }
 
-    fn create_entry_row(pixbuf: Option<&Pixbuf>, image_path: &String, width: i32, max_height: i32, margin: i32) -> ListBoxRow {
-        let row = ListBoxRow::new();
-        
+    /// Builds the row with a placeholder icon and "Loading…" labels. The thumbnail and the
+    /// image details are filled in once decoding on a worker thread has finished.
+    fn create_row_content(
+        image_path: &String,
+        width: i32,
+        max_height: i32,
+        margin: i32,
+        thumbnail_cache: Option<Arc<ThumbnailCache>>,
+    ) -> GTKBox {
         let hbox = GTKBox::new(Orientation::Horizontal, margin);
         hbox.set_margin_start(margin);
         hbox.set_margin_end(margin);
         hbox.set_margin_top(margin);
         hbox.set_margin_bottom(margin);
 
-        let (image, dimensions) = pixbuf
-            .and_then(|pixbuf| {
-                let scaled_pixbuf = Self::scale_pixbuf(
-                    &pixbuf,
-                    width - (2 * margin),
-                    max_height - (2 * margin),
-                )?;
-                
-                let img = Image::from_pixbuf(Some(&scaled_pixbuf));
-                let dimensions = (pixbuf.width() as u32, pixbuf.height() as u32);
-                
-                Some((img, Some(dimensions)))
-            })
-            .unwrap_or_else(|| {
-                (Image::from_icon_name(Some("image-missing"), gtk::IconSize::Dialog), None)
-            });
+        let image = Image::from_icon_name(Some("image-loading"), gtk::IconSize::Dialog);
 
         let info_vbox = GTKBox::new(Orientation::Vertical, 2);
         info_vbox.set_halign(gtk::Align::Start);
-        
-        let size_str = match fs::metadata(image_path) {
-            Ok(metadata) => {
-                let size = metadata.len();
-                if size < 1024 {
-                    format!("{} B", size)
-                } else if size < 1024 * 1024 {
-                    format!("{:.1} KB", size as f64 / 1024.0)
-                } else {
-                    format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
-                }
-            }
-            Err(_) => "Unknown size".to_string()
-        };
-        
-        let size_label = Label::new(Some(&size_str));
+
+        let size_label = Label::new(Some("Loading…"));
         size_label.set_xalign(0.0);
         size_label.style_context().add_class("dim-label");
-        
-        let dimensions_label = Label::new(Some(&match dimensions {
-            Some((w, h)) => format!("{}×{} px", w, h),
-            _ => "Unknown dimensions".to_string()
-        }));
+
+        let dimensions_label = Label::new(None);
         dimensions_label.set_xalign(0.0);
         dimensions_label.style_context().add_class("dim-label");
 
@@ -119,10 +99,29 @@ impl ClipboardImageEntry {
         
         hbox.pack_start(&image, false, false, 0);
         hbox.pack_start(&info_vbox, true, true, 0);
+
+        let on_loaded = move |loaded: LoadedImage| {
+            match loaded.pixbuf {
+                Some(thumbnail) => image.set_from_pixbuf(Some(&thumbnail)),
+                None => image.set_from_icon_name(Some("image-missing"), gtk::IconSize::Dialog),
+            }
+            size_label.set_text(&match loaded.file_size {
+                Some(size) => format_size(size),
+                None => "Unknown size".to_string(),
+            });
+            dimensions_label.set_text(&match loaded.dimensions {
+                Some((w, h)) => format!("{}×{} px", w, h),
+                None => "Unknown dimensions".to_string(),
+            });
+        };
+
+        let (thumbnail_width, thumbnail_height) = (width - (2 * margin), max_height - (2 * margin));
+        match thumbnail_cache {
+            Some(cache) => load_thumbnail_async(image_path, thumbnail_width, thumbnail_height, cache, on_loaded),
+            None => load_image_async(image_path, thumbnail_width, thumbnail_height, on_loaded),
+        }
         
-        row.add(&hbox);
-        row.show_all();
-        return row;
+        return hbox;
     }
 }
 
@@ -131,29 +130,42 @@ impl ClipboardEntry for ClipboardImageEntry {
         self.row.clone()
     }
 
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    fn render_row(&self) {
+        if self.rendered.replace(true) {
+            return;
+        }
+
+        let content = Self::create_row_content(
+            &self.image_path,
+            self.row_width,
+            self.row_max_height,
+            Self::MARGIN,
+            self.thumbnail_cache.clone(),
+        );
+        self.row.add(&content);
+        self.row.show_all();
+    }
+
     fn create_more_info_widget(&self, width: i32, height: i32, _search_query: Option<String>) -> gtk::Widget {
         let more_info_box = GTKBox::new(Orientation::Vertical, Self::MARGIN);
         more_info_box.set_margin(Self::MARGIN);
+        more_info_box.style_context().add_class("clipboard-detail-image");
 
-        let Some(pixbuf) = self.pixbuf.as_ref() else {
-            return more_info_box.upcast::<gtk::Widget>();
-        };
-
-        let Some(scaled_pixbuf) = Self::scale_pixbuf(
-            &pixbuf,
-            width - (Self::MARGIN * 2),
-            height - (Self::MARGIN * 2) 
-        ) else {
-            return more_info_box.upcast::<gtk::Widget>();
-        };
+        let image_view = ImageView::new(width - (Self::MARGIN * 2), height - (Self::MARGIN * 2));
+        more_info_box.pack_start(&image_view.widget(), true, true, 0);
 
-        let image = Image::from_pixbuf(Some(&scaled_pixbuf));
+        let loaded_view = image_view.clone();
+        load_full_image_async(&self.image_path, move |loaded| loaded_view.set_image(loaded.pixbuf));
+        self.image_view.replace(Some(image_view));
 
-        more_info_box.pack_start(&image, true, true, 0);
         return more_info_box.upcast::<gtk::Widget>();
     }
 
-    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), io::Error> {
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError> {
         if copy_path {
             return copy_text_to_clipboard(&self.image_path);
         } else {
@@ -162,7 +174,18 @@ impl ClipboardEntry for ClipboardImageEntry {
     }
 
 
-    fn open_in_external_app(&self) -> Result<(), io::Error> {
+    fn open_in_external_app(&self) -> Result<(), ClipboardError> {
         open_in_external_app(&self.image_path)
     }
+
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError> {
+        open_in_editor(editor_command, &self.image_path)
+    }
+
+    fn handle_image_command(&self, command: ImageCommand) -> bool {
+        match self.image_view.borrow().as_ref() {
+            Some(image_view) => image_view.run(command),
+            None => false,
+        }
+    }
 }
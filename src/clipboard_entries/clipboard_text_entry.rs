@@ -1,9 +1,22 @@
-use gtk::{glib::markup_escape_text, prelude::*, Label, ListBoxRow, Widget};
-use std::io;
+use gtk::{
+    glib::{markup_escape_text, Continue},
+    prelude::*,
+    Align, Box as GTKBox, Label, ListBoxRow, Orientation, Spinner, Widget,
+};
+use std::cell::{Cell, RefCell};
 
-use crate::{copy_to_clipboard_by_gpaste_uuid, open_in_external_app, save_to_tmp_file};
+use crate::{
+    copy_to_clipboard_by_gpaste_uuid, open_in_editor, open_in_external_app,
+    secret_detection::SecretKind,
+    session_files::{text_file_extension, write_session_file},
+    syntax_highlight::highlight_text_async,
+    ClipboardError,
+};
 
-use super::clipboard_entry::ClipboardEntry;
+use super::{
+    clipboard_entry::{highlight_format, highlight_in_text, ClipboardEntry},
+    code_view::{theme_variant, CodeView},
+};
 
 #[derive(Debug, Clone)]
 pub struct ClipboardTextEntry {
@@ -11,36 +24,120 @@ pub struct ClipboardTextEntry {
     shorten_content: Option<String>,
     uuid: String,
     row: ListBoxRow,
-    row_label: Label,
+    row_width: i32,
+    row_label: RefCell<Option<Label>>,
+    /// Set for text that looks like a password, key or token, which is masked until revealed.
+    secret: Option<SecretKind>,
+    revealed: Cell<bool>,
 }
 
 impl ClipboardTextEntry {
     const MARGIN: i32 = 10;
-    const HIGHLIGHT_FORMAT: &'static str = "<span background='#E95420' foreground='white' weight='bold'>%s</span>";
-    const HIGHLIGHT_FORMAT_SELECTED: &'static str = "<span background='#333' foreground='white' weight='bold'>%s</span>";
+    const ESTIMATED_LINE_HEIGHT: i32 = 18;
+    /// Larger text is shown plain, as highlighting it would stall the detail view.
+    const MAX_HIGHLIGHTED_LEN: usize = 256 * 1024;
+    const MASK: &'static str = "••••••••••••";
 
     pub fn new(full_content: String, uuid: String, row_width: i32, row_max_lines: i32) -> Self {
         let shorten_content = Self::create_shorten_content(&full_content, row_max_lines as usize);
-        let entry_row_content = match &shorten_content {
-            Some(shortened) => format!("{}\n...", shortened),
-            _ => full_content.clone(),
+        let row_lines = match &shorten_content {
+            Some(shortened) => shortened.lines().count() + 1,
+            _ => full_content.lines().count().max(1),
         };
-        let (row, row_label) = Self::create_entry_row(&entry_row_content, row_width);
-        return Self { full_content, shorten_content, uuid, row, row_label }
+        let row = Self::create_placeholder_row(row_width, row_lines as i32);
+        return Self {
+            full_content,
+            shorten_content,
+            uuid,
+            row,
+            row_width,
+            row_label: RefCell::new(None),
+            secret: None,
+            revealed: Cell::new(false),
+        }
     }
 
-    fn create_entry_row(text: &String, width: i32) -> (ListBoxRow, Label) {
+    /// Text that is not code, with search matches highlighted.
+    fn create_plain_label(text: &str, search_query: Option<String>) -> Widget {
+        let label = Label::new(None);
+        label.set_xalign(0.0);
+        label.set_valign(Align::Start);
+        label.style_context().add_class("clipboard-detail-text");
+
+        let format = highlight_format(&label, false);
+        match search_query.and_then(|query| highlight_in_text(text, &query, &format)) {
+            Some(markup) => label.set_markup(&markup),
+            _ => label.set_text(text),
+        }
+
+        label.upcast::<Widget>()
+    }
+
+    /// Text detected as a secret. It is masked in the row and detail view until revealed, and
+    /// never matches a search.
+    pub fn new_sensitive(
+        full_content: String,
+        secret: SecretKind,
+        uuid: String,
+        row_width: i32,
+        row_max_lines: i32,
+    ) -> Self {
+        let row = Self::create_placeholder_row(row_width, 1);
+        let shorten_content = Self::create_shorten_content(&full_content, row_max_lines as usize);
+        return Self {
+            full_content,
+            shorten_content,
+            uuid,
+            row,
+            row_width,
+            row_label: RefCell::new(None),
+            secret: Some(secret),
+            revealed: Cell::new(false),
+        }
+    }
+
+    fn is_masked(&self) -> bool {
+        return self.secret.is_some() && !self.revealed.get();
+    }
+
+    fn masked_text(&self) -> String {
+        match self.secret {
+            Some(secret) => format!("{} {}", Self::MASK, secret),
+            None => String::new(),
+        }
+    }
+
+    /// Shows the masked text in the row, or the text itself once revealed.
+    fn update_masked_row(&self, row_label: &Label) {
+        let style_context = row_label.style_context();
+        if self.is_masked() {
+            row_label.set_text(&self.masked_text());
+            style_context.add_class("clipboard-sensitive");
+        } else {
+            style_context.remove_class("clipboard-sensitive");
+        }
+    }
+
+    fn create_placeholder_row(width: i32, lines: i32) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        row.style_context().add_class("clipboard-row");
+        row.set_size_request(width, lines * Self::ESTIMATED_LINE_HEIGHT + (2 * Self::MARGIN));
+        return row;
+    }
+
+    fn create_row_label(text: &String, width: i32) -> Label {
         let label = Label::new(Some(text));
         label.set_xalign(0.0);
         label.set_margin(Self::MARGIN);
         label.set_ellipsize(gtk::pango::EllipsizeMode::End);
         label.set_size_request(width - (2 * Self::MARGIN), -1);
+        label.style_context().add_class("clipboard-row-label");
+        return label;
+    }
 
-        let row = ListBoxRow::new();
-        row.add(&label);
-        row.set_size_request(width, -1);
-
-        return (row, label);
+    fn row_label(&self) -> Label {
+        self.render_row();
+        self.row_label.borrow().clone().expect("render_row creates the row label")
     }
 
     fn create_shorten_content(content: &str, max_lines: usize) -> Option<String> {
@@ -56,47 +153,6 @@ impl ClipboardTextEntry {
             Some(shortened.join("\n"))
         }
     }
-
-    fn highlight_in_text(text: &str, query: &str, format: &str) -> Option<String> {
-        if query.is_empty() {
-            return None;
-        }
-        
-        let query_lower = query.to_lowercase();
-        let text_lower = text.to_lowercase();
-        
-        let mut result = String::new();
-        let mut last_end = 0;
-        let mut found_match = false; 
-        
-        let mut search_start = 0;
-        while let Some(match_start) = text_lower[search_start..].find(&query_lower) {
-            found_match = true; 
-            let absolute_start = search_start + match_start;
-            let absolute_end = absolute_start + query.len();
-            
-            if absolute_start > last_end {
-                result.push_str(&markup_escape_text(&text[last_end..absolute_start]));
-            }
-            
-            let matched_text = &text[absolute_start..absolute_end];
-            let highlighted = format.replace("%s", &markup_escape_text(matched_text));
-            result.push_str(&highlighted);
-            
-            last_end = absolute_end;
-            search_start = absolute_end;
-        }
-        
-        if !found_match {
-            return None; 
-        }
-        
-        if last_end < text.len() {
-            result.push_str(&markup_escape_text(&text[last_end..]));
-        }
-        
-        Some(result)
-    }
 }
 
 impl ClipboardEntry for ClipboardTextEntry {
@@ -104,24 +160,112 @@ impl ClipboardEntry for ClipboardTextEntry {
         return self.row.clone();
     }
 
+    fn uuid(&self) -> &str {
+        return &self.uuid;
+    }
+
+    fn render_row(&self) {
+        if self.row_label.borrow().is_some() {
+            return;
+        }
+
+        let entry_row_content = match &self.shorten_content {
+            Some(shortened) => format!("{}\n...", shortened),
+            _ => self.full_content.clone(),
+        };
+        let label = Self::create_row_label(&entry_row_content, self.row_width);
+        self.update_masked_row(&label);
+
+        self.row.set_size_request(self.row_width, -1);
+        self.row.add(&label);
+        self.row.show_all();
+        self.row_label.replace(Some(label));
+    }
+
+    /// Text that looks like code is shown highlighted, with line numbers. Secrets are only
+    /// described until revealed.
     fn create_more_info_widget(&self, _width: i32, _height: i32, search_query: Option<String>) -> gtk::Widget {
-        let label = Label::new(None);
-        label.set_margin(Self::MARGIN);
-        label.set_xalign(0.0);
+        if self.is_masked() {
+            let label = Label::new(Some(&format!(
+                "{}\n\nThis looks like a {} and is hidden. Use the reveal key to show it.",
+                Self::MASK,
+                self.secret.map(|secret| secret.to_string()).unwrap_or_default(),
+            )));
+            label.set_margin(Self::MARGIN);
+            label.set_xalign(0.0);
+            label.set_line_wrap(true);
+            label.style_context().add_class("clipboard-detail-text");
+            label.style_context().add_class("clipboard-sensitive");
+            return label.upcast::<Widget>();
+        }
 
-        match search_query.and_then(|query| ClipboardTextEntry::highlight_in_text(&self.full_content, &query, Self::HIGHLIGHT_FORMAT)) {
-            Some(markup) => label.set_markup(&markup),
-            _ => label.set_text(&self.full_content),
-        }        
+        let container = GTKBox::new(Orientation::Vertical, 0);
+        container.set_margin(Self::MARGIN);
+        if self.full_content.len() > Self::MAX_HIGHLIGHTED_LEN {
+            container.pack_start(&Self::create_plain_label(&self.full_content, search_query), true, true, 0);
+            return container.upcast::<Widget>();
+        }
 
-        label.upcast::<Widget>()
+        // Text that turns out to be code is highlighted on a worker thread, with a spinner
+        // until it is done.
+        let spinner = Spinner::new();
+        spinner.start();
+        container.pack_start(&spinner, true, true, 0);
+
+        let content = self.full_content.clone();
+        let weak_container = container.downgrade();
+        highlight_text_async(&self.full_content, theme_variant(&container)).attach(None, move |highlighted| {
+            let Some(container) = weak_container.upgrade() else {
+                return Continue(false);
+            };
+            container.remove(&spinner);
+            let widget = match highlighted {
+                Some((language, styles)) => {
+                    let code_view = CodeView::new(Some(language), search_query.clone());
+                    code_view.append(&content, &styles);
+                    code_view.widget()
+                }
+                None => Self::create_plain_label(&content, search_query.clone()),
+            };
+            container.pack_start(&widget, true, true, 0);
+            container.show_all();
+            Continue(false)
+        });
+
+        container.upcast::<Widget>()
     }
 
+    /// Secrets never match, so searching cannot reveal them or keep them in the search cache.
     fn contains_text(&self, search_text: &String) -> bool {
+        if self.secret.is_some() {
+            return false;
+        }
         return self.full_content.to_lowercase().contains(&search_text.to_lowercase());
     }
 
+    fn is_sensitive(&self) -> bool {
+        return self.secret.is_some();
+    }
+
+    fn secret_text(&self) -> Option<&str> {
+        return self.secret.map(|_| self.full_content.as_str());
+    }
+
+    fn toggle_revealed(&self) -> bool {
+        if self.secret.is_none() {
+            return false;
+        }
+        self.revealed.set(!self.revealed.get());
+        self.set_highlight_in_row(None);
+        return self.revealed.get();
+    }
+
     fn set_highlight_in_row(&self, search_query: Option<String>) {
+        let row_label = self.row_label();
+        self.update_masked_row(&row_label);
+        if self.is_masked() {
+            return;
+        }
         let (content, is_shortened) = match &self.shorten_content {
             Some(shortened) => (shortened, true),
             _ => (&self.full_content, false),
@@ -129,13 +273,9 @@ impl ClipboardEntry for ClipboardTextEntry {
         
         match search_query.filter(|query| !query.is_empty()) {
             Some(query) => {
-                let format = if self.row.is_selected() {
-                    Self::HIGHLIGHT_FORMAT_SELECTED
-                } else {
-                    Self::HIGHLIGHT_FORMAT
-                };
+                let format = highlight_format(&row_label, self.row.is_selected());
                 
-                let markup = if let Some(highlighted) = ClipboardTextEntry::highlight_in_text(content, &query, format) {
+                let markup = if let Some(highlighted) = highlight_in_text(content, &query, &format) {
                     if is_shortened {
                         format!("{}\n...", highlighted)
                     } else {
@@ -150,7 +290,7 @@ impl ClipboardEntry for ClipboardTextEntry {
                     )
                 };
                 
-                self.row_label.set_markup(&markup);
+                row_label.set_markup(&markup);
             }
             _ => {
                 let display_text = if is_shortened {
@@ -158,23 +298,28 @@ impl ClipboardEntry for ClipboardTextEntry {
                 } else {
                     content.to_string()
                 };
-                self.row_label.set_text(&display_text);
+                row_label.set_text(&display_text);
             }
         }
     }
 
-    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), io::Error> {
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError> {
         if copy_path {
             return Ok(());
         }
         copy_to_clipboard_by_gpaste_uuid(&self.uuid)
     }
 
-    fn open_in_external_app(&self) -> Result<(), io::Error> {
-        let file_path = save_to_tmp_file(&self.full_content)?;
+    fn open_in_external_app(&self) -> Result<(), ClipboardError> {
+        let file_path = write_session_file(&self.uuid, &self.full_content, text_file_extension(&self.full_content))?;
         open_in_external_app(&file_path)
     }
 
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError> {
+        let file_path = write_session_file(&self.uuid, &self.full_content, text_file_extension(&self.full_content))?;
+        open_in_editor(editor_command, &file_path)
+    }
+
 }
 
 
@@ -0,0 +1,301 @@
+use gtk::{
+    glib::markup_escape_text, prelude::*, Box as GTKBox, Button, Grid, Image, Label, ListBoxRow, Orientation, Widget,
+};
+use std::cell::RefCell;
+use url::Url;
+
+use crate::{
+    copy_text_to_clipboard, copy_to_clipboard_by_gpaste_uuid, open_in_editor, open_in_external_app,
+    session_files::write_session_file, ClipboardError,
+};
+
+use super::clipboard_entry::{
+    create_error_label, highlight_format, highlight_in_text, report_result, ClipboardEntry,
+};
+
+/// A copied web address. The row emphasises its domain; the detail view breaks it into parts.
+#[derive(Debug, Clone)]
+pub struct ClipboardUrlEntry {
+    url: Url,
+    /// The address as copied.
+    text: String,
+    uuid: String,
+    row: ListBoxRow,
+    row_width: i32,
+    row_label: RefCell<Option<Label>>,
+}
+
+impl ClipboardUrlEntry {
+    const MARGIN: i32 = 10;
+    const ICON_SIZE: i32 = 24;
+
+    const SCHEMES: &'static [&'static str] = &["http", "https", "ftp", "ftps", "sftp", "ws", "wss"];
+
+    /// Query parameters that only serve to track where a visitor came from.
+    const TRACKING_PARAMETERS: &'static [&'static str] = &[
+        "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "mc_cid", "mc_eid", "igshid",
+        "_hsenc", "_hsmi", "mkt_tok", "ref_src", "spm",
+    ];
+
+    /// Parses `text` if it is nothing but a single web address.
+    pub fn parse(text: &str) -> Option<Url> {
+        let text = text.trim();
+        if text.is_empty() || text.contains(char::is_whitespace) {
+            return None;
+        }
+        let url = Url::parse(text).ok()?;
+        (Self::SCHEMES.contains(&url.scheme()) && url.host_str().is_some()).then_some(url)
+    }
+
+    pub fn new(url: Url, text: String, uuid: String, row_width: i32) -> Self {
+        let row = ListBoxRow::new();
+        row.style_context().add_class("clipboard-row");
+        row.set_size_request(row_width, Self::ICON_SIZE + (2 * Self::MARGIN));
+        Self {
+            url,
+            text: text.trim().to_string(),
+            uuid,
+            row,
+            row_width,
+            row_label: RefCell::new(None),
+        }
+    }
+
+    fn row_label(&self) -> Label {
+        self.render_row();
+        self.row_label.borrow().clone().expect("render_row creates the row label")
+    }
+
+    /// The address with its host in bold.
+    fn domain_markup(&self) -> String {
+        let Some(host) = self.url.host_str() else {
+            return markup_escape_text(&self.text).to_string();
+        };
+        match self.text.find(host) {
+            Some(start) => format!(
+                "{}<b>{}</b>{}",
+                markup_escape_text(&self.text[..start]),
+                markup_escape_text(host),
+                markup_escape_text(&self.text[start + host.len()..]),
+            ),
+            None => markup_escape_text(&self.text).to_string(),
+        }
+    }
+
+    fn is_tracking_parameter(name: &str) -> bool {
+        name.starts_with("utm_") || Self::TRACKING_PARAMETERS.contains(&name)
+    }
+
+    /// The address without tracking parameters such as `utm_source` or `fbclid`.
+    fn without_tracking(&self) -> String {
+        let mut url = self.url.clone();
+        let kept: Vec<(String, String)> = self
+            .url
+            .query_pairs()
+            .filter(|(name, _)| !Self::is_tracking_parameter(name))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+
+        if kept.is_empty() {
+            url.set_query(None);
+        } else if kept.len() != self.url.query_pairs().count() {
+            url.query_pairs_mut().clear().extend_pairs(kept);
+        }
+        url.to_string()
+    }
+
+    /// `[host/path](address)`, with the characters Markdown treats specially escaped.
+    fn markdown_link(&self) -> String {
+        let host = self.url.host_str().unwrap_or_default();
+        let title = format!("{}{}", host, self.url.path().trim_end_matches('/'));
+        let title = title.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]");
+        let target = self.text.replace('(', "%28").replace(')', "%29");
+        format!("[{}]({})", title, target)
+    }
+
+    fn create_parts_grid(&self) -> Grid {
+        let grid = Grid::new();
+        grid.set_column_spacing(Self::MARGIN as u32);
+        grid.set_row_spacing(2);
+
+        let host = match self.url.port() {
+            Some(port) => format!("{}:{}", self.url.host_str().unwrap_or_default(), port),
+            None => self.url.host_str().unwrap_or_default().to_string(),
+        };
+        let mut parts = vec![
+            ("Scheme".to_string(), self.url.scheme().to_string()),
+            ("Host".to_string(), host),
+            ("Path".to_string(), self.url.path().to_string()),
+        ];
+        if let Some(fragment) = self.url.fragment() {
+            parts.push(("Fragment".to_string(), fragment.to_string()));
+        }
+        for (name, value) in self.url.query_pairs() {
+            parts.push((format!("?{}", name), value.into_owned()));
+        }
+
+        for (row, (name, value)) in parts.iter().enumerate() {
+            let name_label = Label::new(Some(name));
+            name_label.set_xalign(0.0);
+            name_label.set_yalign(0.0);
+            name_label.style_context().add_class("dim-label");
+
+            let value_label = Label::new(Some(value));
+            value_label.set_xalign(0.0);
+            value_label.set_line_wrap(true);
+            value_label.set_line_wrap_mode(gtk::pango::WrapMode::WordChar);
+            value_label.set_selectable(true);
+
+            grid.attach(&name_label, 0, row as i32, 1, 1);
+            grid.attach(&value_label, 1, row as i32, 1, 1);
+        }
+
+        grid
+    }
+}
+
+impl ClipboardEntry for ClipboardUrlEntry {
+    fn get_row(&self) -> ListBoxRow {
+        self.row.clone()
+    }
+
+    fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    fn render_row(&self) {
+        if self.row_label.borrow().is_some() {
+            return;
+        }
+
+        let icon = Image::from_icon_name(Some("insert-link"), gtk::IconSize::LargeToolbar);
+        icon.set_pixel_size(Self::ICON_SIZE);
+
+        let label = Label::new(None);
+        label.set_markup(&self.domain_markup());
+        label.set_xalign(0.0);
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        label.set_tooltip_text(Some(&self.text));
+        label.style_context().add_class("clipboard-row-label");
+
+        let row_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        row_box.set_margin(Self::MARGIN);
+        row_box.set_size_request(self.row_width - (2 * Self::MARGIN), -1);
+        row_box.pack_start(&icon, false, false, 0);
+        row_box.pack_start(&label, true, true, 0);
+
+        self.row.add(&row_box);
+        self.row.show_all();
+        self.row_label.replace(Some(label));
+    }
+
+    /// Breaks the address into scheme, host, path, fragment and decoded query parameters, with
+    /// buttons to open it and to copy it cleaned up or as a Markdown link.
+    fn create_more_info_widget(&self, _width: i32, _height: i32, _search_query: Option<String>) -> gtk::Widget {
+        let container = GTKBox::new(Orientation::Vertical, Self::MARGIN);
+        container.set_margin(Self::MARGIN);
+        container.style_context().add_class("clipboard-url-details");
+
+        let address_label = Label::new(Some(&self.text));
+        address_label.set_xalign(0.0);
+        address_label.set_line_wrap(true);
+        address_label.set_line_wrap_mode(gtk::pango::WrapMode::Char);
+        address_label.set_selectable(true);
+
+        let error_label = create_error_label();
+
+        let report = {
+            let error_label = error_label.clone();
+            move |result: Result<(), ClipboardError>| report_result(&error_label, result)
+        };
+
+        let open_button = Button::with_label("Open in browser");
+        let clean_button = Button::with_label("Copy without tracking");
+        clean_button.set_tooltip_text(Some("Copy the address without parameters such as utm_source"));
+        let markdown_button = Button::with_label("Copy as Markdown link");
+
+        let button_box = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        for button in [&open_button, &clean_button, &markdown_button] {
+            button.set_focus_on_click(false);
+            button_box.pack_start(button, false, false, 0);
+        }
+
+        let address = self.text.clone();
+        let report_open = report.clone();
+        open_button.connect_clicked(move |_| report_open(open_in_external_app(&address)));
+        let cleaned = self.without_tracking();
+        let report_clean = report.clone();
+        clean_button.set_sensitive(cleaned != self.url.as_str());
+        clean_button.connect_clicked(move |_| report_clean(copy_text_to_clipboard(&cleaned)));
+        let markdown = self.markdown_link();
+        markdown_button.connect_clicked(move |_| report(copy_text_to_clipboard(&markdown)));
+
+        container.pack_start(&address_label, false, false, 0);
+        container.pack_start(&self.create_parts_grid(), false, false, 0);
+        container.pack_start(&button_box, false, false, 0);
+        container.pack_start(&error_label, false, false, 0);
+
+        container.upcast::<Widget>()
+    }
+
+    fn contains_text(&self, search_text: &String) -> bool {
+        self.text.to_lowercase().contains(&search_text.to_lowercase())
+    }
+
+    fn set_highlight_in_row(&self, search_query: Option<String>) {
+        let row_label = self.row_label();
+        let format = highlight_format(&row_label, self.row.is_selected());
+        let highlighted = search_query
+            .filter(|query| !query.is_empty())
+            .and_then(|query| highlight_in_text(&self.text, &query, &format));
+        match highlighted {
+            Some(highlighted) => row_label.set_markup(&highlighted),
+            None => row_label.set_markup(&self.domain_markup()),
+        }
+    }
+
+    /// With `copy_path`, copies the address as text rather than selecting the history item.
+    fn copy_to_clipboard(&self, copy_path: bool) -> Result<(), ClipboardError> {
+        if copy_path {
+            copy_text_to_clipboard(&self.text)
+        } else {
+            copy_to_clipboard_by_gpaste_uuid(&self.uuid)
+        }
+    }
+
+    fn open_in_external_app(&self) -> Result<(), ClipboardError> {
+        open_in_external_app(&self.text)
+    }
+
+    fn open_in_editor(&self, editor_command: &str) -> Result<(), ClipboardError> {
+        let file_path = write_session_file(&self.uuid, &self.text, "txt")?;
+        open_in_editor(editor_command, &file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_web_addresses() {
+        let url = ClipboardUrlEntry::parse("  https://example.com/path?q=1 ").unwrap();
+        assert_eq!(url.host_str(), Some("example.com"));
+        assert_eq!(url.path(), "/path");
+        assert!(ClipboardUrlEntry::parse("ftp://files.example.com").is_some());
+    }
+
+    #[test]
+    fn rejects_other_text() {
+        for text in ["", "example.com", "see https://example.com", "mailto:me@example.com", "file:///etc/hosts"] {
+            assert!(ClipboardUrlEntry::parse(text).is_none(), "{} should not be a web address", text);
+        }
+    }
+
+    #[test]
+    fn recognises_tracking_parameters() {
+        assert!(ClipboardUrlEntry::is_tracking_parameter("utm_source"));
+        assert!(ClipboardUrlEntry::is_tracking_parameter("fbclid"));
+        assert!(!ClipboardUrlEntry::is_tracking_parameter("q"));
+    }
+}
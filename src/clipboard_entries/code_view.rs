@@ -0,0 +1,226 @@
+use gtk::{
+    glib::translate::IntoGlib, pango, prelude::*, Box as GTKBox, Justification, Orientation, TextBuffer, TextTag,
+    TextTagTable, TextView, Widget, WrapMode,
+};
+use std::cell::Cell;
+
+use crate::syntax_highlight::{Language, StyledRange, TextStyle, ThemeVariant};
+
+use super::clipboard_entry::highlight_colors;
+
+/// Read-only view of text in the detail view. Code is shown in a monospace font with line
+/// numbers and syntax highlighting; search matches are highlighted on top of either.
+pub struct CodeView {
+    container: GTKBox,
+    text_view: TextView,
+    buffer: TextBuffer,
+    tag_table: TextTagTable,
+    line_numbers: Option<TextBuffer>,
+    numbered_lines: Cell<usize>,
+    search_query: Option<String>,
+    search_tag: TextTag,
+}
+
+impl CodeView {
+    const LINE_NUMBER_MARGIN: i32 = 6;
+
+    /// `language` is `None` for plain text, which is wrapped instead of numbered.
+    pub fn new(language: Option<Language>, search_query: Option<String>) -> Self {
+        let tag_table = TextTagTable::new();
+        let buffer = TextBuffer::new(Some(&tag_table));
+        let text_view = TextView::with_buffer(&buffer);
+        text_view.set_editable(false);
+        text_view.set_cursor_visible(false);
+        text_view.style_context().add_class("clipboard-detail-text");
+
+        let container = GTKBox::new(Orientation::Horizontal, 0);
+
+        let line_numbers = language.map(|_| {
+            let line_numbers = TextView::new();
+            line_numbers.set_editable(false);
+            line_numbers.set_cursor_visible(false);
+            line_numbers.set_can_focus(false);
+            line_numbers.set_monospace(true);
+            line_numbers.set_justification(Justification::Right);
+            line_numbers.set_right_margin(Self::LINE_NUMBER_MARGIN);
+            line_numbers.style_context().add_class("clipboard-line-numbers");
+            line_numbers.style_context().add_class("dim-label");
+            container.pack_start(&line_numbers, false, false, 0);
+            line_numbers.buffer().expect("text views have a buffer")
+        });
+
+        if language.is_some() {
+            text_view.set_monospace(true);
+            text_view.set_wrap_mode(WrapMode::None);
+            text_view.set_left_margin(Self::LINE_NUMBER_MARGIN);
+        } else {
+            text_view.set_wrap_mode(WrapMode::WordChar);
+        }
+        container.pack_start(&text_view, true, true, 0);
+
+        let (background, foreground) = highlight_colors(&text_view, false);
+        let search_tag = TextTag::new(Some("search-match"));
+        search_tag.set_background(Some(&background));
+        search_tag.set_foreground(Some(&foreground));
+        search_tag.set_weight(pango::Weight::Bold.into_glib());
+        tag_table.add(&search_tag);
+
+        Self {
+            container,
+            text_view,
+            buffer,
+            tag_table,
+            line_numbers,
+            numbered_lines: Cell::new(0),
+            search_query: search_query.filter(|query| !query.is_empty()).map(|query| query.to_lowercase()),
+            search_tag,
+        }
+    }
+
+    pub fn widget(&self) -> Widget {
+        self.container.clone().upcast::<Widget>()
+    }
+
+    pub fn hide(&self) {
+        self.container.hide();
+    }
+
+    /// Syntax colours suited to the detail view's background.
+    pub fn theme_variant(&self) -> ThemeVariant {
+        theme_variant(&self.text_view)
+    }
+
+    /// Adds `text` to the end of the view. Offsets of `styles` are relative to `text`.
+    pub fn append(&self, text: &str, styles: &[StyledRange]) {
+        let buffer = &self.buffer;
+        let start_offset = buffer.end_iter().offset();
+        buffer.insert(&mut buffer.end_iter(), text);
+
+        let mut char_offsets = CharOffsets::new(text);
+        for range in styles {
+            if range.start == range.end || !text.is_char_boundary(range.start) || !text.is_char_boundary(range.end) {
+                continue;
+            }
+            let tag = self.style_tag(range.style);
+            buffer.apply_tag(
+                &tag,
+                &buffer.iter_at_offset(start_offset + char_offsets.at(range.start)),
+                &buffer.iter_at_offset(start_offset + char_offsets.at(range.end)),
+            );
+        }
+
+        if let Some(query) = &self.search_query {
+            let lowercase_text = text.to_lowercase();
+            // Lowercasing can change byte lengths, which would shift the matches.
+            if lowercase_text.len() == text.len() {
+                let mut char_offsets = CharOffsets::new(text);
+                for (start, matched) in lowercase_text.match_indices(query.as_str()) {
+                    let end = start + matched.len();
+                    if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+                        continue;
+                    }
+                    buffer.apply_tag(
+                        &self.search_tag,
+                        &buffer.iter_at_offset(start_offset + char_offsets.at(start)),
+                        &buffer.iter_at_offset(start_offset + char_offsets.at(end)),
+                    );
+                }
+            }
+        }
+
+        self.number_lines();
+    }
+
+    /// Tag drawing `style`, shared by all text with that style. Search matches stay on top.
+    fn style_tag(&self, style: TextStyle) -> TextTag {
+        let (red, green, blue) = style.foreground;
+        let name = format!(
+            "syntax-{:02x}{:02x}{:02x}{}{}",
+            red,
+            green,
+            blue,
+            if style.bold { "-bold" } else { "" },
+            if style.italic { "-italic" } else { "" },
+        );
+
+        let tag_table = &self.tag_table;
+        if let Some(tag) = tag_table.lookup(&name) {
+            return tag;
+        }
+
+        let tag = TextTag::new(Some(&name));
+        tag.set_foreground(Some(&format!("#{:02x}{:02x}{:02x}", red, green, blue)));
+        if style.bold {
+            tag.set_weight(pango::Weight::Bold.into_glib());
+        }
+        if style.italic {
+            tag.set_style(pango::Style::Italic);
+        }
+        tag_table.add(&tag);
+        self.search_tag.set_priority(tag_table.size() - 1);
+        tag
+    }
+
+    /// Numbers the lines added since the last call. A trailing newline does not start a
+    /// numbered line until text follows it.
+    fn number_lines(&self) {
+        let Some(numbers_buffer) = &self.line_numbers else {
+            return;
+        };
+
+        let end = self.buffer.end_iter();
+        let mut lines = end.line() as usize + 1;
+        if end.starts_line() {
+            lines -= 1;
+        }
+
+        let numbered = self.numbered_lines.get();
+        if lines <= numbered {
+            return;
+        }
+        let numbers: String = (numbered + 1..=lines)
+            .map(|line| if line == 1 { line.to_string() } else { format!("\n{}", line) })
+            .collect();
+        numbers_buffer.insert(&mut numbers_buffer.end_iter(), &numbers);
+        self.numbered_lines.set(lines);
+    }
+}
+
+/// Syntax colours suited to the background `widget` is shown on in the detail view.
+pub fn theme_variant(widget: &impl IsA<Widget>) -> ThemeVariant {
+    let is_dark = widget
+        .style_context()
+        .lookup_color("clipboard_detail_bg")
+        .map(|background| 0.299 * background.red() + 0.587 * background.green() + 0.114 * background.blue() < 0.5)
+        .unwrap_or(false);
+    if is_dark {
+        ThemeVariant::Dark
+    } else {
+        ThemeVariant::Light
+    }
+}
+
+/// Converts byte offsets of a text to character offsets, counting on from the previous one, so
+/// offsets taken in increasing order cost a single pass over the text. Offsets must lie on
+/// character boundaries.
+struct CharOffsets<'a> {
+    text: &'a str,
+    byte: usize,
+    char: i32,
+}
+
+impl<'a> CharOffsets<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, byte: 0, char: 0 }
+    }
+
+    fn at(&mut self, byte: usize) -> i32 {
+        if byte < self.byte {
+            self.byte = 0;
+            self.char = 0;
+        }
+        self.char += self.text[self.byte..byte].chars().count() as i32;
+        self.byte = byte;
+        self.char
+    }
+}
@@ -0,0 +1,307 @@
+use gtk::{
+    gdk::EventMask,
+    gdk_pixbuf::{InterpType, Pixbuf},
+    glib::{self, Bytes},
+    prelude::*,
+    Adjustment, Align, Box as GTKBox, EventBox, Image, Inhibit, Label, Orientation, PolicyType, ScrolledWindow,
+    Spinner, Widget,
+};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::color_value::ColorValue;
+
+/// What the keys of the image view do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageCommand {
+    ZoomIn,
+    ZoomOut,
+    /// Scales the image down to fit the view.
+    ZoomFit,
+    /// One image pixel per screen pixel.
+    ZoomOriginal,
+    /// Scrolls one step horizontally and vertically, each -1, 0 or 1.
+    Pan(i32, i32),
+}
+
+/// The decoded image, with its pixel data for reading the color under the pointer.
+#[derive(Debug)]
+struct SourceImage {
+    pixbuf: Pixbuf,
+    pixels: Bytes,
+}
+
+impl SourceImage {
+    fn color_at(&self, x: i32, y: i32) -> Option<ColorValue> {
+        let pixbuf = &self.pixbuf;
+        if pixbuf.bits_per_sample() != 8 || !(0..pixbuf.width()).contains(&x) || !(0..pixbuf.height()).contains(&y) {
+            return None;
+        }
+        let offset = (y * pixbuf.rowstride() + x * pixbuf.n_channels()) as usize;
+        let pixel = self.pixels.get(offset..offset + pixbuf.n_channels() as usize)?;
+        Some(ColorValue {
+            red: pixel[0],
+            green: pixel[1],
+            blue: pixel[2],
+            alpha: if pixbuf.has_alpha() { pixel[3] as f64 / 255.0 } else { 1.0 },
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ImageViewState {
+    source: Option<SourceImage>,
+    zoom: f64,
+    /// Whether the zoom follows the size of the view.
+    fit: bool,
+    /// Image pixel under the pointer.
+    pointer: Option<(i32, i32)>,
+}
+
+/// Image in the detail view that can be zoomed and scrolled, above a status line with the zoom
+/// level and the position and color of the pixel under the pointer.
+#[derive(Debug, Clone)]
+pub struct ImageView {
+    container: GTKBox,
+    spinner: Spinner,
+    scrolled_window: ScrolledWindow,
+    image: Image,
+    status_label: Label,
+    view_size: (i32, i32),
+    state: Rc<RefCell<ImageViewState>>,
+}
+
+impl ImageView {
+    const STATUS_LINE_HEIGHT: i32 = 24;
+    const ZOOM_STEP: f64 = 1.25;
+    const MIN_ZOOM: f64 = 0.02;
+    const MAX_ZOOM: f64 = 32.0;
+    /// Zoom levels are limited so the scaled image stays within this many pixels per side,
+    /// which GDK can draw, and within this many pixels in total, about 128 MB of pixel data.
+    const MAX_SCALED_SIDE: f64 = 16384.0;
+    const MAX_SCALED_PIXELS: f64 = 32_000_000.0;
+    /// From this zoom level on, pixels are shown as sharp squares instead of being smoothed.
+    const PIXELATED_ZOOM: f64 = 2.0;
+    /// Share of the visible area one pan step scrolls.
+    const PAN_FRACTION: f64 = 0.15;
+    const MIN_PAN_STEP: f64 = 16.0;
+
+    /// A view filling `width`×`height`, status line included, that shows a spinner until
+    /// `set_image` is called.
+    pub fn new(width: i32, height: i32) -> Self {
+        let container = GTKBox::new(Orientation::Vertical, 0);
+        container.style_context().add_class("clipboard-image-view");
+
+        let view_size = (width.max(1), (height - Self::STATUS_LINE_HEIGHT).max(1));
+        let scrolled_window = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Automatic)
+            .vscrollbar_policy(PolicyType::Automatic)
+            .build();
+        scrolled_window.set_size_request(view_size.0, view_size.1);
+        scrolled_window.set_no_show_all(true);
+
+        let image = Image::new();
+        let event_box = EventBox::new();
+        event_box.set_halign(Align::Center);
+        event_box.set_valign(Align::Center);
+        event_box.add_events(EventMask::POINTER_MOTION_MASK | EventMask::LEAVE_NOTIFY_MASK);
+        event_box.add(&image);
+        scrolled_window.add(&event_box);
+        image.show();
+        event_box.show();
+
+        let spinner = Spinner::new();
+        spinner.start();
+        spinner.set_size_request(view_size.0, view_size.1);
+
+        let status_label = Label::new(None);
+        status_label.set_xalign(0.0);
+        status_label.set_size_request(-1, Self::STATUS_LINE_HEIGHT);
+        status_label.style_context().add_class("clipboard-image-status");
+        status_label.style_context().add_class("dim-label");
+
+        container.pack_start(&spinner, true, true, 0);
+        container.pack_start(&scrolled_window, true, true, 0);
+        container.pack_start(&status_label, false, false, 0);
+
+        let view = Self {
+            container,
+            spinner,
+            scrolled_window,
+            image,
+            status_label,
+            view_size,
+            state: Rc::new(RefCell::new(ImageViewState {
+                source: None,
+                zoom: 1.0,
+                fit: true,
+                pointer: None,
+            })),
+        };
+
+        let motion_state = view.state.clone();
+        let motion_label = view.status_label.clone();
+        event_box.connect_motion_notify_event(move |_, event| {
+            let (x, y) = event.position();
+            let mut state = motion_state.borrow_mut();
+            let zoom = state.zoom;
+            state.pointer = Some(((x / zoom).floor() as i32, (y / zoom).floor() as i32));
+            update_status(&state, &motion_label);
+            Inhibit(false)
+        });
+        let leave_state = view.state.clone();
+        let leave_label = view.status_label.clone();
+        event_box.connect_leave_notify_event(move |_, _| {
+            let mut state = leave_state.borrow_mut();
+            state.pointer = None;
+            update_status(&state, &leave_label);
+            Inhibit(false)
+        });
+
+        view
+    }
+
+    pub fn widget(&self) -> Widget {
+        self.container.clone().upcast::<Widget>()
+    }
+
+    /// Shows the decoded image, scaled to fit, or a notice if it could not be decoded.
+    pub fn set_image(&self, pixbuf: Option<Pixbuf>) {
+        self.container.remove(&self.spinner);
+        let source = pixbuf.and_then(|pixbuf| {
+            let pixels = pixbuf.read_pixel_bytes()?;
+            Some(SourceImage { pixbuf, pixels })
+        });
+        if source.is_none() {
+            self.status_label.set_text("Could not decode the image");
+            self.status_label.show();
+            return;
+        }
+
+        self.state.borrow_mut().source = source;
+        self.scrolled_window.set_no_show_all(false);
+        self.scrolled_window.show_all();
+        self.zoom_to_fit();
+    }
+
+    /// Runs `command`. Returns whether it had an effect; panning an image that fits the view
+    /// does not, so the key can keep its usual meaning.
+    pub fn run(&self, command: ImageCommand) -> bool {
+        let zoom = {
+            let state = self.state.borrow();
+            if state.source.is_none() {
+                return false;
+            }
+            state.zoom
+        };
+        match command {
+            ImageCommand::ZoomIn => self.set_zoom(zoom * Self::ZOOM_STEP, false),
+            ImageCommand::ZoomOut => self.set_zoom(zoom / Self::ZOOM_STEP, false),
+            ImageCommand::ZoomFit => self.zoom_to_fit(),
+            ImageCommand::ZoomOriginal => self.set_zoom(1.0, false),
+            ImageCommand::Pan(dx, dy) => {
+                let panned_x = dx != 0 && Self::pan(&self.scrolled_window.hadjustment(), dx);
+                let panned_y = dy != 0 && Self::pan(&self.scrolled_window.vadjustment(), dy);
+                return panned_x || panned_y;
+            }
+        }
+        true
+    }
+
+    /// Scrolls one step, returning whether the image extends beyond the view on this axis.
+    fn pan(adjustment: &Adjustment, direction: i32) -> bool {
+        let max_value = adjustment.upper() - adjustment.page_size();
+        if max_value <= adjustment.lower() {
+            return false;
+        }
+        let step = (adjustment.page_size() * Self::PAN_FRACTION).max(Self::MIN_PAN_STEP);
+        let value = adjustment.value() + direction as f64 * step;
+        adjustment.set_value(value.clamp(adjustment.lower(), max_value));
+        true
+    }
+
+    fn zoom_to_fit(&self) {
+        let fit_zoom = {
+            let state = self.state.borrow();
+            let Some(source) = &state.source else {
+                return;
+            };
+            let (view_width, view_height) = self.view_size;
+            let horizontal = view_width as f64 / source.pixbuf.width() as f64;
+            let vertical = view_height as f64 / source.pixbuf.height() as f64;
+            horizontal.min(vertical).min(1.0)
+        };
+        self.set_zoom(fit_zoom, true);
+    }
+
+    /// Rescales the image, keeping the centre of the visible area in place.
+    fn set_zoom(&self, zoom: f64, fit: bool) {
+        let scaled = {
+            let mut state = self.state.borrow_mut();
+            let Some(source) = &state.source else {
+                return;
+            };
+            let (width, height) = (source.pixbuf.width() as f64, source.pixbuf.height() as f64);
+            let max_zoom = Self::MAX_ZOOM
+                .min(Self::MAX_SCALED_SIDE / width.max(height))
+                .min((Self::MAX_SCALED_PIXELS / (width * height)).sqrt());
+            let zoom = zoom.clamp(Self::MIN_ZOOM.min(max_zoom), max_zoom);
+            let scaled_width = ((width * zoom).round() as i32).max(1);
+            let scaled_height = ((height * zoom).round() as i32).max(1);
+            let scaled = if scaled_width == source.pixbuf.width() && scaled_height == source.pixbuf.height() {
+                Some(source.pixbuf.clone())
+            } else {
+                let interpolation = if zoom >= Self::PIXELATED_ZOOM {
+                    InterpType::Nearest
+                } else {
+                    InterpType::Bilinear
+                };
+                source.pixbuf.scale_simple(scaled_width, scaled_height, interpolation)
+            };
+            state.zoom = zoom;
+            state.fit = fit;
+            state.pointer = None;
+            scaled
+        };
+
+        let centre = |adjustment: &Adjustment| {
+            let upper = adjustment.upper();
+            if upper > 0.0 {
+                (adjustment.value() + adjustment.page_size() / 2.0) / upper
+            } else {
+                0.5
+            }
+        };
+        let (hadjustment, vadjustment) = (self.scrolled_window.hadjustment(), self.scrolled_window.vadjustment());
+        let (centre_x, centre_y) = (centre(&hadjustment), centre(&vadjustment));
+
+        self.image.set_from_pixbuf(scaled.as_ref());
+        update_status(&self.state.borrow(), &self.status_label);
+
+        // The adjustments only take on the new size once the view was laid out again.
+        glib::idle_add_local_once(move || {
+            for (adjustment, centre) in [(hadjustment, centre_x), (vadjustment, centre_y)] {
+                let max_value = (adjustment.upper() - adjustment.page_size()).max(adjustment.lower());
+                let value = centre * adjustment.upper() - adjustment.page_size() / 2.0;
+                adjustment.set_value(value.clamp(adjustment.lower(), max_value));
+            }
+        });
+    }
+}
+
+/// `50% (fit) · 1920×1080 px · 412, 96 · #3d8fd1 rgb(61, 143, 209)`
+fn update_status(state: &ImageViewState, status_label: &Label) {
+    let Some(source) = &state.source else {
+        return;
+    };
+    let mut parts = vec![
+        format!("{:.0}%{}", state.zoom * 100.0, if state.fit { " (fit)" } else { "" }),
+        format!("{}×{} px", source.pixbuf.width(), source.pixbuf.height()),
+    ];
+    if let Some((x, y)) = state.pointer {
+        if let Some(color) = source.color_at(x, y) {
+            parts.push(format!("{}, {}", x, y));
+            parts.push(format!("{} {}", color.to_hex(), color.to_rgb()));
+        }
+    }
+    status_label.set_text(&parts.join(" · "));
+}
@@ -2,8 +2,20 @@ pub mod clipboard_entry;
 pub mod clipboard_image_entry;
 pub mod clipboard_text_entry;
 pub mod clipboard_file_entry;
+pub mod clipboard_directory_entry;
+pub mod clipboard_url_entry;
+pub mod clipboard_color_entry;
+pub mod clipboard_data_entry;
+pub mod clipboard_encrypted_entry;
+pub mod code_view;
+pub mod image_view;
 
-pub use clipboard_entry::ClipboardEntry;
+pub use clipboard_entry::{highlight_format, ClipboardEntry};
 pub use clipboard_image_entry::ClipboardImageEntry;
 pub use clipboard_text_entry::ClipboardTextEntry;
 pub use clipboard_file_entry::ClipboardFileEntry;
+pub use clipboard_directory_entry::ClipboardDirectoryEntry;
+pub use clipboard_url_entry::ClipboardUrlEntry;
+pub use clipboard_color_entry::ClipboardColorEntry;
+pub use clipboard_data_entry::ClipboardDataEntry;
+pub use clipboard_encrypted_entry::ClipboardEncryptedEntry;
@@ -0,0 +1,373 @@
+/// A colour copied as a CSS literal such as `#ff8800`, `rgb(255 136 0 / 50%)` or
+/// `hsl(32, 100%, 50%)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorValue {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    /// Opacity from 0 to 1.
+    pub alpha: f64,
+}
+
+/// CSS named colours, used to name a copied colour or the one closest to it.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff), ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd), ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00), ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed), ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c),
+    ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b), ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400), ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc), ("darkred", 0x8b0000), ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b), ("darkslategray", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1), ("darkviolet", 0x9400d3), ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969), ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff), ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000), ("greenyellow", 0xadff2f),
+    ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c), ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa), ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6), ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90), ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32), ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff), ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa), ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585), ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5), ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000), ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500),
+    ("orchid", 0xda70d6), ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093), ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f),
+    ("pink", 0xffc0cb), ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6), ("purple", 0x800080),
+    ("rebeccapurple", 0x663399), ("red", 0xff0000), ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513), ("salmon", 0xfa8072), ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee), ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd), ("slategray", 0x708090), ("snow", 0xfffafa), ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4), ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee), ("wheat", 0xf5deb3),
+    ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
+fn unpack(rgb: u32) -> (u8, u8, u8) {
+    ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+/// Splits the arguments of `rgb(…)` or `hsl(…)`, which may be separated by commas or, in the
+/// modern syntax, by spaces with the alpha after a slash.
+fn split_arguments(arguments: &str) -> Option<Vec<&str>> {
+    let arguments: Vec<&str> = if arguments.contains(',') {
+        arguments.split(',').map(str::trim).collect()
+    } else {
+        let (channels, alpha) = match arguments.split_once('/') {
+            Some((channels, alpha)) => (channels, Some(alpha.trim())),
+            None => (arguments, None),
+        };
+        channels.split_whitespace().chain(alpha).collect()
+    };
+    (arguments.len() == 3 || arguments.len() == 4).then_some(arguments)
+}
+
+/// A number, or a percentage of `percent_of`.
+fn parse_number(value: &str, percent_of: f64) -> Option<f64> {
+    let number = match value.strip_suffix('%') {
+        Some(percentage) => percentage.trim().parse::<f64>().ok()? * percent_of / 100.0,
+        None => value.parse::<f64>().ok()?,
+    };
+    number.is_finite().then_some(number)
+}
+
+fn parse_alpha(value: Option<&&str>) -> Option<f64> {
+    match value {
+        Some(value) => Some(parse_number(value, 1.0)?.clamp(0.0, 1.0)),
+        None => Some(1.0),
+    }
+}
+
+fn channel(value: f64) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+impl ColorValue {
+    pub const BLACK: ColorValue = ColorValue { red: 0, green: 0, blue: 0, alpha: 1.0 };
+    pub const WHITE: ColorValue = ColorValue { red: 255, green: 255, blue: 255, alpha: 1.0 };
+
+    /// Parses `text` if it is nothing but a hex, `rgb()`/`rgba()` or `hsl()`/`hsla()` colour.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        if let Some(hex) = text.strip_prefix('#') {
+            return Self::parse_hex(hex);
+        }
+
+        let (function, arguments) = text.strip_suffix(')')?.split_once('(')?;
+        let arguments = split_arguments(arguments)?;
+        match function.trim() {
+            "rgb" | "rgba" => Some(Self {
+                red: channel(parse_number(arguments[0], 255.0)?),
+                green: channel(parse_number(arguments[1], 255.0)?),
+                blue: channel(parse_number(arguments[2], 255.0)?),
+                alpha: parse_alpha(arguments.get(3))?,
+            }),
+            "hsl" | "hsla" => {
+                let hue = parse_number(arguments[0].trim_end_matches("deg"), 360.0)?;
+                let saturation = parse_number(arguments[1], 1.0)?;
+                let lightness = parse_number(arguments[2], 1.0)?;
+                // Saturation and lightness must be percentages.
+                if !arguments[1].ends_with('%') || !arguments[2].ends_with('%') {
+                    return None;
+                }
+                let (red, green, blue) = hsl_to_rgb(hue, saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+                Some(Self { red, green, blue, alpha: parse_alpha(arguments.get(3))? })
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_hex(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        // `#123` and `#1234` are far more often issue numbers than colours.
+        if hex.len() <= 4 && hex.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let digits: Vec<u8> = hex.chars().map(|c| c.to_digit(16).unwrap_or(0) as u8).collect();
+        let (red, green, blue, alpha) = match digits.as_slice() {
+            [r, g, b] => (r * 17, g * 17, b * 17, 255),
+            [r, g, b, a] => (r * 17, g * 17, b * 17, a * 17),
+            [r1, r2, g1, g2, b1, b2] => (r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2, 255),
+            [r1, r2, g1, g2, b1, b2, a1, a2] => (r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2, a1 * 16 + a2),
+            _ => return None,
+        };
+        Some(Self { red, green, blue, alpha: alpha as f64 / 255.0 })
+    }
+
+    fn is_opaque(&self) -> bool {
+        self.alpha >= 1.0
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` for a translucent colour.
+    pub fn to_hex(&self) -> String {
+        let hex = format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue);
+        if self.is_opaque() {
+            hex
+        } else {
+            format!("{}{:02x}", hex, channel(self.alpha * 255.0))
+        }
+    }
+
+    /// `rgb(…)`, or `rgba(…)` for a translucent colour.
+    pub fn to_rgb(&self) -> String {
+        if self.is_opaque() {
+            format!("rgb({}, {}, {})", self.red, self.green, self.blue)
+        } else {
+            format!("rgba({}, {}, {}, {})", self.red, self.green, self.blue, format_alpha(self.alpha))
+        }
+    }
+
+    /// `hsl(…)`, or `hsla(…)` for a translucent colour.
+    pub fn to_hsl(&self) -> String {
+        let (hue, saturation, lightness) = rgb_to_hsl(self.red, self.green, self.blue);
+        let hsl = format!(
+            "{}, {}%, {}%",
+            hue.round(),
+            (saturation * 100.0).round(),
+            (lightness * 100.0).round()
+        );
+        if self.is_opaque() {
+            format!("hsl({})", hsl)
+        } else {
+            format!("hsla({}, {})", hsl, format_alpha(self.alpha))
+        }
+    }
+
+    /// The CSS name of the colour, and whether it matches exactly rather than being the
+    /// closest named colour.
+    pub fn name(&self) -> (&'static str, bool) {
+        let distance = |rgb: u32| {
+            let (red, green, blue) = unpack(rgb);
+            let difference = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+            difference(red, self.red) + difference(green, self.green) + difference(blue, self.blue)
+        };
+        let (name, rgb) = NAMED_COLORS
+            .iter()
+            .min_by_key(|(_, rgb)| distance(*rgb))
+            .copied()
+            .unwrap_or(("black", 0));
+        (name, distance(rgb) == 0)
+    }
+
+    /// Relative luminance as defined by WCAG 2, ignoring opacity.
+    pub fn relative_luminance(&self) -> f64 {
+        let linear = |value: u8| {
+            let value = value as f64 / 255.0;
+            if value <= 0.03928 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.red) + 0.7152 * linear(self.green) + 0.0722 * linear(self.blue)
+    }
+
+    /// WCAG contrast ratio against `other`, from 1 to 21.
+    pub fn contrast_ratio(&self, other: &ColorValue) -> f64 {
+        let (lighter, darker) = {
+            let (a, b) = (self.relative_luminance(), other.relative_luminance());
+            if a > b { (a, b) } else { (b, a) }
+        };
+        (lighter + 0.05) / (darker + 0.05)
+    }
+
+    /// The colour packed as `0xrrggbbaa`, as pixbufs are filled.
+    pub fn to_rgba_u32(&self) -> u32 {
+        u32::from_be_bytes([self.red, self.green, self.blue, channel(self.alpha * 255.0)])
+    }
+}
+
+/// WCAG level a contrast ratio reaches for normal text.
+pub fn contrast_level(ratio: f64) -> &'static str {
+    if ratio >= 7.0 {
+        "AAA"
+    } else if ratio >= 4.5 {
+        "AA"
+    } else if ratio >= 3.0 {
+        "AA large text only"
+    } else {
+        "fails"
+    }
+}
+
+fn format_alpha(alpha: f64) -> String {
+    let rounded = (alpha * 100.0).round() / 100.0;
+    rounded.to_string()
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (u8, u8, u8) {
+    let hue = hue.rem_euclid(360.0) / 360.0;
+    if saturation == 0.0 {
+        let gray = channel(lightness * 255.0);
+        return (gray, gray, gray);
+    }
+
+    let q = if lightness < 0.5 {
+        lightness * (1.0 + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2.0 * lightness - q;
+    let component = |t: f64| {
+        let t = t.rem_euclid(1.0);
+        let value = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        channel(value * 255.0)
+    };
+    (component(hue + 1.0 / 3.0), component(hue), component(hue - 1.0 / 3.0))
+}
+
+fn rgb_to_hsl(red: u8, green: u8, blue: u8) -> (f64, f64, f64) {
+    let (red, green, blue) = (red as f64 / 255.0, green as f64 / 255.0, blue as f64 / 255.0);
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let lightness = (max + min) / 2.0;
+    if max == min {
+        return (0.0, 0.0, lightness);
+    }
+
+    let delta = max - min;
+    let saturation = if lightness > 0.5 {
+        delta / (2.0 - max - min)
+    } else {
+        delta / (max + min)
+    };
+    let hue = if max == red {
+        (green - blue) / delta + if green < blue { 6.0 } else { 0.0 }
+    } else if max == green {
+        (blue - red) / delta + 2.0
+    } else {
+        (red - green) / delta + 4.0
+    };
+    (hue * 60.0, saturation, lightness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(red: u8, green: u8, blue: u8) -> ColorValue {
+        ColorValue { red, green, blue, alpha: 1.0 }
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(ColorValue::parse("#ff8800"), Some(rgb(255, 136, 0)));
+        assert_eq!(ColorValue::parse("  #FF8800 "), Some(rgb(255, 136, 0)));
+        assert_eq!(ColorValue::parse("#f80"), Some(rgb(255, 136, 0)));
+        assert_eq!(ColorValue::parse("#ff880080").map(|color| color.to_hex()), Some("#ff880080".to_string()));
+        assert_eq!(ColorValue::parse("#ff88"), Some(ColorValue { alpha: 136.0 / 255.0, ..rgb(255, 255, 136) }));
+    }
+
+    #[test]
+    fn rejects_malformed_hex() {
+        assert_eq!(ColorValue::parse("#ff88zz"), None);
+        assert_eq!(ColorValue::parse("#ff88000"), None);
+        assert_eq!(ColorValue::parse("ff8800"), None);
+    }
+
+    #[test]
+    fn short_hex_needs_a_hex_letter() {
+        assert_eq!(ColorValue::parse("#123"), None);
+        assert_eq!(ColorValue::parse("#1234"), None);
+        assert_eq!(ColorValue::parse("#12a"), Some(rgb(0x11, 0x22, 0xaa)));
+        assert_eq!(ColorValue::parse("#123456"), Some(rgb(0x12, 0x34, 0x56)));
+    }
+
+    #[test]
+    fn parses_rgb() {
+        assert_eq!(ColorValue::parse("rgb(255, 136, 0)"), Some(rgb(255, 136, 0)));
+        assert_eq!(ColorValue::parse("rgb(100% 0% 0%)"), Some(rgb(255, 0, 0)));
+        assert_eq!(ColorValue::parse("rgba(255, 136, 0, 0.5)"), Some(ColorValue { alpha: 0.5, ..rgb(255, 136, 0) }));
+        assert_eq!(ColorValue::parse("rgb(255 136 0 / 50%)"), Some(ColorValue { alpha: 0.5, ..rgb(255, 136, 0) }));
+        assert_eq!(ColorValue::parse("rgb(255, 136)"), None);
+        assert_eq!(ColorValue::parse("rgb(a, b, c)"), None);
+    }
+
+    #[test]
+    fn parses_hsl() {
+        assert_eq!(ColorValue::parse("hsl(0, 100%, 50%)"), Some(rgb(255, 0, 0)));
+        assert_eq!(ColorValue::parse("hsl(120deg 100% 25%)"), Some(rgb(0, 128, 0)));
+        assert_eq!(ColorValue::parse("hsl(0, 100, 50)"), None);
+    }
+
+    #[test]
+    fn formats_in_every_notation() {
+        let color = rgb(255, 0, 0);
+        assert_eq!(color.to_hex(), "#ff0000");
+        assert_eq!(color.to_rgb(), "rgb(255, 0, 0)");
+        assert_eq!(color.to_hsl(), "hsl(0, 100%, 50%)");
+        assert_eq!(color.to_rgba_u32(), 0xff0000ff);
+    }
+
+    #[test]
+    fn names_exact_and_closest_colors() {
+        assert_eq!(rgb(255, 0, 0).name(), ("red", true));
+        assert_eq!(rgb(254, 1, 0).name(), ("red", false));
+    }
+
+    #[test]
+    fn contrast_between_black_and_white() {
+        let ratio = ColorValue::BLACK.contrast_ratio(&ColorValue::WHITE);
+        assert!((ratio - 21.0).abs() < 1e-9);
+        assert_eq!(contrast_level(ratio), "AAA");
+        assert_eq!(contrast_level(4.5), "AA");
+        assert_eq!(contrast_level(3.0), "AA large text only");
+        assert_eq!(contrast_level(1.0), "fails");
+    }
+}
@@ -0,0 +1,439 @@
+use gtk::gdk::RGBA;
+use serde::Deserialize;
+use std::{fs, path::PathBuf, sync::Arc};
+
+use clipboard_manager::{
+    backend::{ClipboardBackend, GpasteBackend},
+    directory_listing::ListingLimits,
+    encrypted_history::{EncryptedBackend, EncryptedHistory},
+    thumbnail_cache::ThumbnailCache,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub height: i32,
+    pub list_width: i32,
+    pub detail_width: i32,
+    pub detail_big_width: i32,
+    pub detail_big_height: i32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            height: 400,
+            list_width: 500,
+            detail_width: 500,
+            detail_big_width: 1000,
+            detail_big_height: 800,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// Number of history items fetched per page while scrolling.
+    #[serde(alias = "initial_entries")]
+    pub page_size: usize,
+    /// Optional cap on how far back the history can be scrolled; unlimited when unset.
+    pub max_entries: Option<usize>,
+    /// Move every text and image item but the newest out of GPaste into the encrypted history.
+    pub encrypt: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            page_size: 25,
+            max_entries: None,
+            encrypt: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RowsConfig {
+    pub image_max_height: i32,
+    pub text_max_lines: i32,
+}
+
+impl Default for RowsConfig {
+    fn default() -> Self {
+        Self {
+            image_max_height: 100,
+            text_max_lines: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreviewConfig {
+    /// How much of a copied file the detail view reads, and the largest file whose contents
+    /// can be copied, in kilobytes.
+    pub max_file_size_kb: u64,
+    /// Levels of subfolders shown in the listing of a copied folder.
+    pub max_directory_depth: usize,
+    /// Files and folders shown in the listing of a copied folder.
+    pub max_directory_items: usize,
+    /// Walk the whole of a copied folder to show its item count and size. Otherwise only
+    /// the listed levels are walked and counted.
+    pub count_directory_totals: bool,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size_kb: 1024,
+            max_directory_depth: 3,
+            max_directory_items: 500,
+            count_directory_totals: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThumbnailsConfig {
+    /// Keep row thumbnails in `~/.cache/clipboard_manager/thumbnails` between launches.
+    pub cache: bool,
+    /// Size limit of the thumbnail cache in megabytes.
+    pub max_cache_size_mb: u64,
+    /// Encrypt cached thumbnails with the key of the encrypted history, kept in the session
+    /// keyring.
+    pub encrypt: bool,
+}
+
+impl Default for ThumbnailsConfig {
+    fn default() -> Self {
+        Self {
+            cache: true,
+            max_cache_size_mb: 100,
+            encrypt: false,
+        }
+    }
+}
+
+/// What happens after a sensitive entry is copied from the picker. Both are off when unset.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretsConfig {
+    /// Seconds after which the clipboard is cleared, if it still holds the secret.
+    pub clear_clipboard_after_secs: Option<u64>,
+    /// Minutes after which the entry is deleted from the history.
+    pub delete_after_minutes: Option<u64>,
+}
+
+/// Applications whose copies are removed from the history, by `WM_CLASS` name or application
+/// id, ignoring case. Applied by the resident instance on X11, after GPaste recorded the copy.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExclusionsConfig {
+    pub deny: Vec<String>,
+    /// When set, copies from any application not listed are removed as well.
+    pub allow: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    Gpaste,
+}
+
+impl BackendKind {
+    pub fn create(&self) -> Arc<dyn ClipboardBackend> {
+        match self {
+            BackendKind::Gpaste => Arc::new(GpasteBackend),
+        }
+    }
+}
+
+/// Overrides for the search highlight colours of the active stylesheet.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighlightConfig {
+    pub background: Option<String>,
+    pub foreground: Option<String>,
+    pub selected_background: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub backend: BackendKind,
+    /// Command used for the open-editor action, e.g. `"gnome-text-editor"` or `"code --wait"`.
+    /// Falls back to `xdg-open` when unset.
+    pub editor: Option<String>,
+    pub window: WindowConfig,
+    pub history: HistoryConfig,
+    pub rows: RowsConfig,
+    pub thumbnails: ThumbnailsConfig,
+    pub preview: PreviewConfig,
+    pub highlight: HighlightConfig,
+    pub secrets: SecretsConfig,
+    pub exclusions: ExclusionsConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            backend: BackendKind::Gpaste,
+            editor: None,
+            window: WindowConfig::default(),
+            history: HistoryConfig::default(),
+            rows: RowsConfig::default(),
+            thumbnails: ThumbnailsConfig::default(),
+            preview: PreviewConfig::default(),
+            highlight: HighlightConfig::default(),
+            secrets: SecretsConfig::default(),
+            exclusions: ExclusionsConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    const FILE_NAME: &'static str = "config.toml";
+    const BYTES_PER_KB: u64 = 1024;
+    const BYTES_PER_MB: u64 = 1024 * 1024;
+
+    pub fn config_path() -> PathBuf {
+        gtk::glib::user_config_dir()
+            .join("clipboard_manager")
+            .join(Self::FILE_NAME)
+    }
+
+    /// Reads `config.toml` from the user config directory. A missing file yields the defaults.
+    pub fn load() -> Result<Self, String> {
+        let path = Self::config_path();
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(format!("Error reading {}: {}", path.display(), err)),
+        };
+
+        Self::parse(&content).map_err(|err| format!("Invalid configuration in {}:\n{}", path.display(), err))
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let config: Config = toml::from_str(content).map_err(|err| err.to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let positive = [
+            ("window.height", self.window.height),
+            ("window.list_width", self.window.list_width),
+            ("window.detail_width", self.window.detail_width),
+            ("window.detail_big_width", self.window.detail_big_width),
+            ("window.detail_big_height", self.window.detail_big_height),
+            ("rows.image_max_height", self.rows.image_max_height),
+            ("rows.text_max_lines", self.rows.text_max_lines),
+        ];
+        for (name, value) in positive {
+            if value <= 0 {
+                return Err(format!("`{}` must be greater than 0, got {}", name, value));
+            }
+        }
+
+        if self.history.page_size == 0 {
+            return Err("`history.page_size` must be greater than 0".to_string());
+        }
+        if self.history.max_entries == Some(0) {
+            return Err("`history.max_entries` must be greater than 0".to_string());
+        }
+        if self.preview.max_file_size_kb == 0 {
+            return Err("`preview.max_file_size_kb` must be greater than 0".to_string());
+        }
+        if self.preview.max_directory_items == 0 {
+            return Err("`preview.max_directory_items` must be greater than 0".to_string());
+        }
+        if self.secrets.clear_clipboard_after_secs == Some(0) {
+            return Err("`secrets.clear_clipboard_after_secs` must be greater than 0".to_string());
+        }
+        if self.secrets.delete_after_minutes == Some(0) {
+            return Err("`secrets.delete_after_minutes` must be greater than 0".to_string());
+        }
+        let exclusions = [("exclusions.deny", &self.exclusions.deny), ("exclusions.allow", &self.exclusions.allow)];
+        for (name, applications) in exclusions {
+            if applications.iter().any(|application| application.trim().is_empty()) {
+                return Err(format!("`{}` must not contain empty application names", name));
+            }
+        }
+        if self.thumbnails.cache && self.thumbnails.max_cache_size_mb == 0 {
+            return Err("`thumbnails.max_cache_size_mb` must be greater than 0".to_string());
+        }
+
+        if let Some(editor) = &self.editor {
+            match gtk::glib::shell_parse_argv(editor) {
+                Ok(argv) if !argv.is_empty() => {}
+                Ok(_) => return Err("`editor` must not be empty".to_string()),
+                Err(err) => return Err(format!("`editor` is not a valid command line: {}", err)),
+            }
+        }
+
+        for (name, _, value) in self.highlight_colors() {
+            if RGBA::parse(value).is_err() {
+                return Err(format!("`{}` is not a valid colour: '{}'", name, value));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn highlight_colors(&self) -> impl Iterator<Item = (&'static str, &'static str, &String)> {
+        [
+            ("highlight.background", "clipboard_highlight_bg", &self.highlight.background),
+            ("highlight.foreground", "clipboard_highlight_fg", &self.highlight.foreground),
+            ("highlight.selected_background", "clipboard_highlight_selected_bg", &self.highlight.selected_background),
+        ]
+        .into_iter()
+        .filter_map(|(name, css_name, value)| Some((name, css_name, value.as_ref()?)))
+    }
+
+    pub fn preview_max_bytes(&self) -> u64 {
+        self.preview.max_file_size_kb.saturating_mul(Self::BYTES_PER_KB)
+    }
+
+    /// How much of a copied folder the detail view lists.
+    pub fn directory_listing_limits(&self) -> ListingLimits {
+        ListingLimits {
+            max_depth: self.preview.max_directory_depth,
+            max_items: self.preview.max_directory_items,
+            count_totals: self.preview.count_directory_totals,
+        }
+    }
+
+    /// Where encrypted history items are kept. Used even without `history.encrypt`, so items
+    /// encrypted before it was turned off are still listed.
+    pub fn encrypted_history(&self) -> Arc<EncryptedHistory> {
+        Arc::new(EncryptedHistory::new(EncryptedHistory::default_dir()))
+    }
+
+    /// The configured backend, followed by the items of `encrypted_history`.
+    pub fn create_backend(&self, encrypted_history: Arc<EncryptedHistory>) -> Arc<dyn ClipboardBackend> {
+        Arc::new(EncryptedBackend::new(self.backend.create(), encrypted_history, self.history.encrypt))
+    }
+
+    /// The on-disk thumbnail cache, unless it is disabled.
+    pub fn thumbnail_cache(&self) -> Option<Arc<ThumbnailCache>> {
+        if !self.thumbnails.cache {
+            return None;
+        }
+        let max_bytes = self.thumbnails.max_cache_size_mb.saturating_mul(Self::BYTES_PER_MB);
+        Some(Arc::new(ThumbnailCache::new(
+            ThumbnailCache::default_dir(),
+            max_bytes,
+            self.thumbnails.encrypt,
+        )))
+    }
+
+    /// `@define-color` rules for the configured highlight colours, layered above the bundled
+    /// stylesheet and below the user's `style.css`.
+    pub fn highlight_css(&self) -> String {
+        self.highlight_colors()
+            .map(|(_, css_name, value)| format!("@define-color {} {};\n", css_name, value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_valid_file() {
+        let config = Config::parse(
+            r##"
+            editor = "gnome-text-editor --new-window"
+
+            [window]
+            height = 600
+
+            [history]
+            initial_entries = 50
+            max_entries = 1000
+            encrypt = true
+
+            [preview]
+            count_directory_totals = false
+
+            [secrets]
+            delete_after_minutes = 10
+
+            [exclusions]
+            deny = ["KeePassXC"]
+
+            [highlight]
+            background = "#3584E4"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(config.window.height, 600);
+        assert_eq!(config.window.list_width, 500);
+        assert_eq!(config.history.page_size, 50);
+        assert_eq!(config.history.max_entries, Some(1000));
+        assert!(config.history.encrypt);
+        assert!(!config.directory_listing_limits().count_totals);
+        assert_eq!(config.secrets.delete_after_minutes, Some(10));
+        assert_eq!(config.exclusions.deny, ["KeePassXC"]);
+        assert_eq!(config.highlight_css(), "@define-color clipboard_highlight_bg #3584E4;\n");
+    }
+
+    #[test]
+    fn an_empty_file_yields_the_defaults() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.history.page_size, 25);
+        assert_eq!(config.preview_max_bytes(), 1024 * 1024);
+        assert!(config.thumbnails.cache);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(Config::parse("colour = \"red\"").is_err());
+        assert!(Config::parse("[window]\nwidth = 300").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let invalid = [
+            ("[window]\nheight = 0", "`window.height` must be greater than 0, got 0"),
+            ("[window]\nlist_width = -1", "`window.list_width` must be greater than 0, got -1"),
+            ("[window]\ndetail_width = 0", "`window.detail_width` must be greater than 0, got 0"),
+            ("[window]\ndetail_big_width = 0", "`window.detail_big_width` must be greater than 0, got 0"),
+            ("[window]\ndetail_big_height = 0", "`window.detail_big_height` must be greater than 0, got 0"),
+            ("[rows]\nimage_max_height = 0", "`rows.image_max_height` must be greater than 0, got 0"),
+            ("[rows]\ntext_max_lines = 0", "`rows.text_max_lines` must be greater than 0, got 0"),
+            ("[history]\npage_size = 0", "`history.page_size` must be greater than 0"),
+            ("[history]\nmax_entries = 0", "`history.max_entries` must be greater than 0"),
+            ("[preview]\nmax_file_size_kb = 0", "`preview.max_file_size_kb` must be greater than 0"),
+            ("[preview]\nmax_directory_items = 0", "`preview.max_directory_items` must be greater than 0"),
+            (
+                "[secrets]\nclear_clipboard_after_secs = 0",
+                "`secrets.clear_clipboard_after_secs` must be greater than 0",
+            ),
+            ("[secrets]\ndelete_after_minutes = 0", "`secrets.delete_after_minutes` must be greater than 0"),
+            ("[exclusions]\ndeny = [\" \"]", "`exclusions.deny` must not contain empty application names"),
+            ("[exclusions]\nallow = [\"\"]", "`exclusions.allow` must not contain empty application names"),
+            ("[thumbnails]\nmax_cache_size_mb = 0", "`thumbnails.max_cache_size_mb` must be greater than 0"),
+            (
+                "[highlight]\nforeground = \"not a colour\"",
+                "`highlight.foreground` is not a valid colour: 'not a colour'",
+            ),
+        ];
+        for (content, error) in invalid {
+            assert_eq!(Config::parse(content).unwrap_err(), error, "{}", content);
+        }
+
+        for editor in ["editor = \"vim 'unterminated\"", "editor = \"  \""] {
+            let error = Config::parse(editor).unwrap_err();
+            assert!(error.starts_with("`editor`"), "{}", error);
+        }
+        assert!(Config::parse("[thumbnails]\ncache = false\nmax_cache_size_mb = 0").is_ok());
+    }
+}
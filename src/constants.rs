@@ -1,9 +0,0 @@
-pub const APP_HEIGHT: i32 = 400;
-pub const ENTRIES_WIDTH: i32 = 500;
-pub const INFO_BOX_WIDTH: i32 = 500;
-pub const INFO_BOX_BIG_WIDTH: i32 = 1000;
-pub const INFO_BOX_BIG_HEIGHT: i32 = 800;
-pub const INITIAL_ENTRIES: usize = 10;
-pub const MAX_ENTRIES: usize = 100;
-pub const ROW_IMAGE_MAX_HEIGHT: i32 = 100;
-pub const ROW_TEXT_MAX_LINES: i32 = 2;
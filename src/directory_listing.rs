@@ -0,0 +1,170 @@
+use gtk::glib;
+use std::{fs, path::Path, thread};
+
+/// A file or folder in a directory listing.
+pub struct ListingNode {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    /// Size of a file; `None` for folders.
+    pub size: Option<u64>,
+    pub children: Vec<ListingNode>,
+}
+
+/// Contents of a directory, limited in depth and number of listed items.
+pub struct DirectoryListing {
+    pub nodes: Vec<ListingNode>,
+    /// Files and folders below the directory, counted up to `MAX_COUNTED_ITEMS`.
+    pub item_count: u64,
+    /// Total size of the counted files.
+    pub total_size: u64,
+    /// Whether some items were left out of `nodes` because of the depth or item limits.
+    pub truncated: bool,
+    /// Whether counting stopped at `MAX_COUNTED_ITEMS`, or at the listed levels when
+    /// `ListingLimits::count_totals` is off.
+    pub count_incomplete: bool,
+}
+
+/// Progress of a listing, sent from the walking thread to the main loop.
+pub enum ListingEvent {
+    /// Items counted so far, sent every `PROGRESS_INTERVAL` items.
+    Progress { item_count: u64 },
+    Finished(DirectoryListing),
+    /// The walking thread could not be started.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ListingLimits {
+    /// Levels of subfolders shown; 0 shows only the contents of the directory itself.
+    pub max_depth: usize,
+    /// Items shown in the listing.
+    pub max_items: usize,
+    /// Walk below `max_depth` too, so that the item count and size cover the whole folder.
+    pub count_totals: bool,
+}
+
+/// Items visited to compute the totals, so that copying e.g. the home folder does not walk
+/// the whole disk.
+const MAX_COUNTED_ITEMS: u64 = 100_000;
+
+/// Items counted between progress reports. A report that cannot be delivered stops the walk.
+const PROGRESS_INTERVAL: u64 = 1_000;
+
+struct Scan {
+    limits: ListingLimits,
+    listed: usize,
+    listing: DirectoryListing,
+    sender: glib::Sender<ListingEvent>,
+    /// Set once the receiver is gone, i.e. nobody waits for the listing any more.
+    cancelled: bool,
+}
+
+impl Scan {
+    /// Lists `dir`, returning the nodes to show at `depth`. Symbolic links are listed but not
+    /// followed, so loops cannot occur.
+    fn visit(&mut self, dir: &Path, depth: usize) -> Vec<ListingNode> {
+        let Ok(read_dir) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        let mut dir_entries: Vec<fs::DirEntry> = read_dir.filter_map(|entry| entry.ok()).collect();
+        dir_entries.sort_by_key(|entry| entry.file_name());
+
+        let mut nodes = Vec::new();
+        for entry in dir_entries {
+            if self.cancelled {
+                break;
+            }
+            if self.listing.item_count >= MAX_COUNTED_ITEMS {
+                self.listing.count_incomplete = true;
+                break;
+            }
+            self.listing.item_count += 1;
+            // `u64::is_multiple_of` needs Rust 1.87.
+            #[allow(unknown_lints, clippy::manual_is_multiple_of)]
+            let report_progress = self.listing.item_count % PROGRESS_INTERVAL == 0;
+            if report_progress {
+                let progress = ListingEvent::Progress { item_count: self.listing.item_count };
+                self.cancelled = self.sender.send(progress).is_err();
+            }
+
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let is_dir = file_type.is_dir();
+            let size = if is_dir {
+                None
+            } else {
+                entry.metadata().ok().map(|metadata| metadata.len())
+            };
+            self.listing.total_size += size.unwrap_or(0);
+
+            let show = depth <= self.limits.max_depth && self.listed < self.limits.max_items;
+            if show {
+                self.listed += 1;
+            } else {
+                self.listing.truncated = true;
+            }
+
+            // Folders are walked even when not shown if the totals are to be complete.
+            let children = if !is_dir {
+                Vec::new()
+            } else if depth < self.limits.max_depth || self.limits.count_totals {
+                self.visit(&entry.path(), depth + 1)
+            } else {
+                self.listing.truncated = true;
+                self.listing.count_incomplete = true;
+                Vec::new()
+            };
+
+            if show {
+                nodes.push(ListingNode {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    path: entry.path().to_string_lossy().to_string(),
+                    is_dir,
+                    size,
+                    children,
+                });
+            }
+        }
+
+        nodes
+    }
+}
+
+/// Lists the directory at `path` on a worker thread, reporting progress to the main loop
+/// while it walks. Dropping the receiver stops the walk.
+pub fn list_directory_async(path: &str, limits: ListingLimits) -> glib::Receiver<ListingEvent> {
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT_IDLE);
+
+    let path = path.to_string();
+    let worker_sender = sender.clone();
+    let spawned = thread::Builder::new()
+        .name("directory-listing".to_string())
+        .spawn(move || {
+            let mut scan = Scan {
+                limits,
+                listed: 0,
+                listing: DirectoryListing {
+                    nodes: Vec::new(),
+                    item_count: 0,
+                    total_size: 0,
+                    truncated: false,
+                    count_incomplete: false,
+                },
+                sender: worker_sender,
+                cancelled: false,
+            };
+            scan.listing.nodes = scan.visit(Path::new(&path), 0);
+            if !scan.cancelled {
+                let _ = scan.sender.send(ListingEvent::Finished(scan.listing));
+            }
+        });
+
+    if let Err(e) = spawned {
+        let _ = sender.send(ListingEvent::Failed(e.to_string()));
+    }
+
+    receiver
+}
@@ -0,0 +1,392 @@
+use gtk::glib;
+use std::{
+    collections::HashSet,
+    fs::{self, DirBuilder, OpenOptions, Permissions},
+    io::{self, Write},
+    os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    backend::{ChangeWatch, ClipboardBackend, HistoryItem, HistoryPage},
+    encryption::EncryptionKey,
+    file_info::format_size,
+    ClipboardError,
+};
+
+/// What an item of the encrypted history holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptedKind {
+    Text,
+    /// A PNG image.
+    Image,
+}
+
+impl EncryptedKind {
+    fn name(self) -> &'static str {
+        match self {
+            EncryptedKind::Text => "text",
+            EncryptedKind::Image => "image",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(EncryptedKind::Text),
+            "image" => Some(EncryptedKind::Image),
+            _ => None,
+        }
+    }
+}
+
+/// History items moved out of the backend and kept encrypted with the key from the session
+/// keyring, one file each in `~/.local/share/clipboard_manager/history`. Files are named
+/// `<time>_<backend uuid>_<kind>.enc`, so they sort by the time they were moved and can still
+/// be found by the uuid the backend knew them by. Their contents are only decrypted when an
+/// entry is shown or copied; listing them reads nothing but their names and sizes.
+#[derive(Debug)]
+pub struct EncryptedHistory {
+    dir: PathBuf,
+    key: OnceLock<Option<EncryptionKey>>,
+}
+
+impl EncryptedHistory {
+    const EXTENSION: &'static str = "enc";
+    const PARTIAL_EXTENSION: &'static str = "part";
+    /// Starts the uuids of encrypted items, which GPaste uuids never do.
+    const UUID_PREFIX: &'static str = "encrypted_";
+    const DIR_MODE: u32 = 0o700;
+    const FILE_MODE: u32 = 0o600;
+
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            key: OnceLock::new(),
+        }
+    }
+
+    pub fn default_dir() -> PathBuf {
+        glib::user_data_dir()
+            .join("clipboard_manager")
+            .join("history")
+    }
+
+    /// What the encrypted item `uuid` holds, or `None` if `uuid` is not an encrypted item.
+    pub fn kind_of(uuid: &str) -> Option<EncryptedKind> {
+        let (_, kind) = uuid.strip_prefix(Self::UUID_PREFIX)?.rsplit_once('_')?;
+        EncryptedKind::from_name(kind)
+    }
+
+    /// The key, fetched from the keyring once. `None` if it could not be fetched, which is
+    /// reported the first time.
+    fn encryption_key(&self) -> Option<&EncryptionKey> {
+        self.key
+            .get_or_init(|| {
+                EncryptionKey::from_keyring()
+                    .map_err(|e| eprintln!("Error getting the history encryption key: {}", e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    fn file(&self, uuid: &str) -> Option<PathBuf> {
+        Self::kind_of(uuid)?;
+        let stem = uuid.strip_prefix(Self::UUID_PREFIX)?;
+        // Only the characters of the names written here, so the file stays inside the directory.
+        if !stem.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return None;
+        }
+        Some(self.dir.join(format!("{}.{}", stem, Self::EXTENSION)))
+    }
+
+    /// Creates the directory readable only by the user, and tightens the permissions of an
+    /// existing one.
+    fn create_dir(&self) -> io::Result<()> {
+        DirBuilder::new().recursive(true).mode(Self::DIR_MODE).create(&self.dir)?;
+        let metadata = fs::metadata(&self.dir)?;
+        if metadata.permissions().mode() & 0o777 != Self::DIR_MODE {
+            fs::set_permissions(&self.dir, Permissions::from_mode(Self::DIR_MODE))?;
+        }
+        Ok(())
+    }
+
+    /// The encrypted items, newest first. Their content is a summary such as
+    /// ` [Encrypted text, 1.2 KB]`, as the text itself stays encrypted.
+    pub fn items(&self) -> Vec<HistoryItem> {
+        let Ok(dir_entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut items: Vec<(String, HistoryItem)> = dir_entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().map(|ext| ext != Self::EXTENSION).unwrap_or(true) {
+                    return None;
+                }
+                let stem = path.file_stem()?.to_str()?.to_string();
+                let uuid = format!("{}{}", Self::UUID_PREFIX, stem);
+                let kind = Self::kind_of(&uuid)?;
+                let size = EncryptionKey::plaintext_len(entry.metadata().ok()?.len());
+                let content = format!(" [Encrypted {}, {}]", kind.name(), format_size(size));
+                Some((stem, HistoryItem { uuid, content }))
+            })
+            .collect();
+
+        items.sort_by(|(a, _), (b, _)| b.cmp(a));
+        items.into_iter().map(|(_, item)| item).collect()
+    }
+
+    /// Encrypts `plaintext` into a new item for the backend item `origin_uuid`. `time` orders
+    /// it among the other items.
+    fn add(&self, origin_uuid: &str, kind: EncryptedKind, plaintext: &[u8], time: u128) -> Result<PathBuf, String> {
+        let key = self.encryption_key().ok_or_else(|| "no encryption key".to_string())?;
+        let sealed = key.encrypt(plaintext)?;
+        self.create_dir().map_err(|e| e.to_string())?;
+
+        let origin: String = origin_uuid.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect();
+        let file = self.dir.join(format!("{:020}_{}_{}.{}", time, origin, kind.name(), Self::EXTENSION));
+        // Written under a temporary name first so listings never see a partial file.
+        let partial_file = file.with_extension(Self::PARTIAL_EXTENSION);
+        let written = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(Self::FILE_MODE)
+            .open(&partial_file)
+            .and_then(|mut output| output.write_all(&sealed))
+            .and_then(|()| fs::rename(&partial_file, &file));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&partial_file);
+            return Err(e.to_string());
+        }
+        Ok(file)
+    }
+
+    /// Decrypts the encrypted item `uuid`.
+    pub fn read(&self, uuid: &str) -> Result<Vec<u8>, ClipboardError> {
+        let file = self.file(uuid).ok_or_else(|| ClipboardError::EntryNotFound { uuid: uuid.to_string() })?;
+        let sealed = match fs::read(&file) {
+            Ok(sealed) => sealed,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(ClipboardError::EntryNotFound { uuid: uuid.to_string() })
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let key = self.encryption_key().ok_or_else(|| ClipboardError::Decryption {
+            reason: "the key could not be read from the session keyring".to_string(),
+        })?;
+        key.decrypt(&sealed).map_err(|reason| ClipboardError::Decryption { reason })
+    }
+
+    /// Removes the encrypted item `uuid`.
+    pub fn remove(&self, uuid: &str) -> Result<(), ClipboardError> {
+        let file = self.file(uuid).ok_or_else(|| ClipboardError::EntryNotFound { uuid: uuid.to_string() })?;
+        match fs::remove_file(file) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(ClipboardError::EntryNotFound { uuid: uuid.to_string() })
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Removes the item that was moved out of the backend item `origin_uuid`. Returns whether
+    /// there was one.
+    fn remove_origin(&self, origin_uuid: &str) -> io::Result<bool> {
+        let Ok(dir_entries) = fs::read_dir(&self.dir) else {
+            return Ok(false);
+        };
+        let infix = format!("_{}_", origin_uuid);
+        let mut removed = false;
+        for path in dir_entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            let matches = path.file_name().and_then(|name| name.to_str()).map(|name| name.contains(&infix));
+            if matches == Some(true) {
+                fs::remove_file(&path)?;
+                removed = true;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// A backend whose history continues with the encrypted history. With `encrypt` set, every
+/// text and image item but the newest is moved into the encrypted history whenever the history
+/// is listed, and deleted from the wrapped backend. The newest item stays, as it is what the
+/// clipboard holds. File items stay too: they only hold paths, and the files stay where they
+/// are. Without `encrypt`, items already encrypted are still listed.
+pub struct EncryptedBackend {
+    inner: Arc<dyn ClipboardBackend>,
+    history: Arc<EncryptedHistory>,
+    encrypt: bool,
+    /// Held while moving items, so concurrent listings do not move the same item twice.
+    moving: Mutex<()>,
+}
+
+impl EncryptedBackend {
+    const IMAGE_MARKER: &'static str = " [Image,";
+    const FILES_MARKER: &'static str = " [Files] ";
+
+    pub fn new(inner: Arc<dyn ClipboardBackend>, history: Arc<EncryptedHistory>, encrypt: bool) -> Self {
+        Self {
+            inner,
+            history,
+            encrypt,
+            moving: Mutex::new(()),
+        }
+    }
+
+    /// What `item` is moved as, or `None` if it stays in the backend.
+    fn encrypted_kind(item: &HistoryItem) -> Option<EncryptedKind> {
+        if item.content.is_empty() || item.content.starts_with(Self::FILES_MARKER) {
+            None
+        } else if item.content.starts_with(Self::IMAGE_MARKER) {
+            Some(EncryptedKind::Image)
+        } else {
+            Some(EncryptedKind::Text)
+        }
+    }
+
+    fn move_item(&self, item: &HistoryItem, kind: EncryptedKind, time: u128) -> Result<(), String> {
+        let (plaintext, image_path) = match kind {
+            EncryptedKind::Text => (item.content.as_bytes().to_vec(), None),
+            EncryptedKind::Image => {
+                let path = self.inner.entry_path(&item.uuid).map_err(|e| e.to_string())?;
+                let image = fs::read(Path::new(&path)).map_err(|e| format!("could not read {}: {}", path, e))?;
+                (image, Some(path))
+            }
+        };
+        let file = self.history.add(&item.uuid, kind, &plaintext, time)?;
+        // Without the backend's copy gone, the item would be listed twice.
+        if let Err(e) = self.inner.delete(&item.uuid) {
+            let _ = fs::remove_file(&file);
+            return Err(e.to_string());
+        }
+        // The backend may keep the image file of a deleted item.
+        if let Some(path) = image_path {
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => eprintln!("Error removing {}: {}", path, e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the items of `items` but the newest into the encrypted history, oldest first so
+    /// they keep their order. Returns the uuids of the moved items.
+    fn move_older_items(&self, items: &[HistoryItem]) -> HashSet<String> {
+        let mut moved = HashSet::new();
+        if !self.encrypt || items.len() < 2 || self.history.encryption_key().is_none() {
+            return moved;
+        }
+        let _moving = self.moving.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let start = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_nanos());
+        for (index, item) in items[1..].iter().rev().enumerate() {
+            let Some(kind) = Self::encrypted_kind(item) else {
+                continue;
+            };
+            match self.move_item(item, kind, start + index as u128) {
+                Ok(()) => {
+                    moved.insert(item.uuid.clone());
+                }
+                Err(e) => eprintln!("Error encrypting history item {}: {}", item.uuid, e),
+            }
+        }
+        moved
+    }
+}
+
+impl ClipboardBackend for EncryptedBackend {
+    /// Lists the wrapped backend's items, after moving older ones into the encrypted history,
+    /// followed by the encrypted items.
+    fn history(&self, offset: usize, limit: Option<usize>) -> Result<HistoryPage, ClipboardError> {
+        let listed = self.inner.history(0, None)?;
+        let moved = self.move_older_items(&listed.items);
+
+        let items: Vec<HistoryItem> = listed
+            .items
+            .into_iter()
+            .filter(|item| !moved.contains(&item.uuid))
+            .chain(self.history.items())
+            .collect();
+        let end = match limit {
+            Some(limit) => items.len().min(offset.saturating_add(limit)),
+            None => items.len(),
+        };
+
+        Ok(HistoryPage {
+            next_offset: (end < items.len()).then_some(end),
+            items: items.get(offset..end).unwrap_or_default().to_vec(),
+            invalid_lines: listed.invalid_lines,
+        })
+    }
+
+    fn entry_path(&self, uuid: &str) -> Result<String, ClipboardError> {
+        self.inner.entry_path(uuid)
+    }
+
+    fn entry_files(&self, uuid: &str) -> Result<Vec<String>, ClipboardError> {
+        self.inner.entry_files(uuid)
+    }
+
+    /// An item that was moved after its uuid was handed out, e.g. to delete it once it
+    /// expires, is deleted from the encrypted history instead.
+    fn delete(&self, uuid: &str) -> Result<(), ClipboardError> {
+        if EncryptedHistory::kind_of(uuid).is_some() {
+            return self.history.remove(uuid);
+        }
+        match self.inner.delete(uuid) {
+            Err(ClipboardError::EntryNotFound { .. }) if self.history.remove_origin(uuid)? => Ok(()),
+            result => result,
+        }
+    }
+
+    fn is_recording(&self) -> Option<bool> {
+        self.inner.is_recording()
+    }
+
+    fn set_recording(&self, recording: bool) -> Result<(), ClipboardError> {
+        self.inner.set_recording(recording)
+    }
+
+    fn watch_changes(&self, on_change: Box<dyn Fn()>) -> Option<ChangeWatch> {
+        self.inner.watch_changes(on_change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_encrypted_uuids_apart() {
+        assert_eq!(EncryptedHistory::kind_of("encrypted_01_3f2a-77_text"), Some(EncryptedKind::Text));
+        assert_eq!(EncryptedHistory::kind_of("encrypted_01_3f2a-77_image"), Some(EncryptedKind::Image));
+        assert_eq!(EncryptedHistory::kind_of("encrypted_01_3f2a-77_audio"), None);
+        assert_eq!(EncryptedHistory::kind_of("3f2a-77"), None);
+    }
+
+    #[test]
+    fn lists_items_newest_first_without_decrypting_them() {
+        let dir = std::env::temp_dir().join(format!("clipboard_manager_history_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("00000000000000000001_a_text.enc"), [0; 34 + 1024]).unwrap();
+        fs::write(dir.join("00000000000000000002_b_image.enc"), [0; 34 + 10]).unwrap();
+        fs::write(dir.join("00000000000000000003_c_text.part"), [0; 34]).unwrap();
+
+        let items = EncryptedHistory::new(dir.clone()).items();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let listed: Vec<(&str, &str)> = items.iter().map(|item| (item.uuid.as_str(), item.content.as_str())).collect();
+        assert_eq!(
+            listed,
+            [
+                ("encrypted_00000000000000000002_b_image", " [Encrypted image, 10 B]"),
+                ("encrypted_00000000000000000001_a_text", " [Encrypted text, 1.0 KB]"),
+            ]
+        );
+    }
+}
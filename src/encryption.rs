@@ -0,0 +1,155 @@
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use std::{
+    fmt,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Attributes identifying the key in the session keyring.
+const KEYRING_ATTRIBUTES: [&str; 4] = ["application", "clipboard_manager", "purpose", "history-encryption"];
+const KEYRING_LABEL: &str = "Clipboard manager history encryption key";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+/// Length of the authentication tag that follows the ciphertext.
+const TAG_LEN: usize = 16;
+/// Starts every encrypted file, so data written by something else is not mistaken for it.
+const MAGIC: &[u8] = b"CMENC1";
+
+/// The key that the encrypted history and cached thumbnails are encrypted with. It is stored
+/// in the session keyring through the Secret Service, using `secret-tool`, and created there
+/// on first use. Any Secret Service works, including a stand-in for tests.
+pub struct EncryptionKey {
+    cipher: ChaCha20Poly1305,
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok().filter(|_| pair.len() == 2))
+        .collect()
+}
+
+/// The stored key, `Ok(None)` only if the keyring was searched and has none yet. A locked or
+/// unreachable keyring is an error, so it never leads to the key being replaced.
+fn lookup_key() -> Result<Option<Vec<u8>>, String> {
+    let output = Command::new("secret-tool")
+        .arg("lookup")
+        .args(KEYRING_ATTRIBUTES)
+        .output()
+        .map_err(|e| format!("could not run secret-tool: {}", e))?;
+    let stored = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+    // secret-tool exits with 1 without any output when no item matches, and reports failures
+    // such as a missing Secret Service on stderr, also with 1.
+    if !error.is_empty() {
+        return Err(format!("secret-tool lookup failed: {}", error));
+    }
+    if stored.is_empty() {
+        return match output.status.code() {
+            Some(0 | 1) => Ok(None),
+            _ => Err(format!("secret-tool lookup failed with {}", output.status)),
+        };
+    }
+    match from_hex(&stored) {
+        Some(key) if key.len() == KEY_LEN => Ok(Some(key)),
+        _ => Err("the encryption key in the keyring is malformed".to_string()),
+    }
+}
+
+fn store_key(key: &[u8]) -> Result<(), String> {
+    let mut child = Command::new("secret-tool")
+        .arg("store")
+        .arg(format!("--label={}", KEYRING_LABEL))
+        .args(KEYRING_ATTRIBUTES)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run secret-tool: {}", e))?;
+
+    // The secret is passed on stdin so it never shows up in the process list.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(to_hex(key).as_bytes())
+            .map_err(|e| format!("could not pass the key to secret-tool: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("could not run secret-tool: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "secret-tool store failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+impl EncryptionKey {
+    /// Reads the key from the session keyring, creating it there if there is none yet. Fails
+    /// if the keyring cannot be reached, in which case nothing must be written unencrypted
+    /// in its place.
+    pub fn from_keyring() -> Result<Self, String> {
+        let key = match lookup_key()? {
+            Some(key) => Key::clone_from_slice(&key),
+            None => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                store_key(&key)?;
+                key
+            }
+        };
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(&key),
+        })
+    }
+
+    /// Encrypts `plaintext` with a fresh nonce. The result starts with a marker and the nonce,
+    /// followed by the authenticated ciphertext.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| "encryption failed".to_string())?;
+
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypts what `encrypt` produced. Fails if the data was written with another key or
+    /// was tampered with.
+    pub fn decrypt(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        let rest = sealed
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| "not an encrypted file".to_string())?;
+        if rest.len() < NONCE_LEN {
+            return Err("encrypted data is truncated".to_string());
+        }
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "decryption failed, the data was damaged or encrypted with another key".to_string())
+    }
+
+    /// Size of the plaintext that `encrypt` turned into `sealed_len` bytes.
+    pub fn plaintext_len(sealed_len: u64) -> u64 {
+        sealed_len.saturating_sub((MAGIC.len() + NONCE_LEN + TAG_LEN) as u64)
+    }
+}
@@ -0,0 +1,69 @@
+use std::{fmt, io, process::ExitStatus};
+
+use crate::file_info::format_size;
+
+#[derive(Debug)]
+pub enum ClipboardError {
+    /// The clipboard backend's command-line client could not be found.
+    BackendMissing { program: String },
+    /// The backend ran but reported a failure.
+    BackendFailed { status: ExitStatus, stderr: String },
+    /// The requested history entry no longer exists in the backend.
+    EntryNotFound { uuid: String },
+    /// An external application could not open the given file or URL.
+    OpenFailed { target: String, reason: String },
+    /// A file was expected to hold UTF-8 text but does not.
+    NotText { path: String },
+    /// A file is too large to be copied as text.
+    TooLarge { path: String, size: u64, limit: u64 },
+    /// An entry of the encrypted history could not be decrypted.
+    Decryption { reason: String },
+    Io(io::Error),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::BackendMissing { program } => {
+                write!(f, "{} was not found", program)
+            }
+            ClipboardError::BackendFailed { status, stderr } if stderr.is_empty() => {
+                write!(f, "clipboard backend failed with {}", status)
+            }
+            ClipboardError::BackendFailed { status, stderr } => {
+                write!(f, "clipboard backend failed with {}: {}", status, stderr)
+            }
+            ClipboardError::EntryNotFound { uuid } => {
+                write!(f, "clipboard entry {} no longer exists", uuid)
+            }
+            ClipboardError::OpenFailed { target, reason } => {
+                write!(f, "could not open {}: {}", target, reason)
+            }
+            ClipboardError::NotText { path } => {
+                write!(f, "{} is not a text file", path)
+            }
+            ClipboardError::TooLarge { path, size, limit } => {
+                write!(f, "{} is {}, more than the {} limit", path, format_size(*size), format_size(*limit))
+            }
+            ClipboardError::Decryption { reason } => {
+                write!(f, "could not decrypt the entry: {}", reason)
+            }
+            ClipboardError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClipboardError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClipboardError {
+    fn from(err: io::Error) -> Self {
+        ClipboardError::Io(err)
+    }
+}
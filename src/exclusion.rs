@@ -0,0 +1,145 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use clipboard_manager::{
+    backend::{ChangeWatch, ClipboardBackend},
+    source_app::{focus_detection_unavailable, focused_application, SourceApp},
+};
+
+use crate::config::ExclusionsConfig;
+
+/// Which applications may add to the history, from `[exclusions]`.
+#[derive(Debug)]
+struct ExclusionRules {
+    deny: Vec<String>,
+    allow: Vec<String>,
+}
+
+impl ExclusionRules {
+    /// Denied applications are excluded; when an allow list is set, so is every application
+    /// not on it.
+    fn excludes(&self, app: &SourceApp) -> bool {
+        let denied = self.deny.iter().any(|name| app.matches(name));
+        let not_allowed = !self.allow.is_empty() && !self.allow.iter().any(|name| app.matches(name));
+        denied || not_allowed
+    }
+}
+
+/// The history as last seen, so only newly recorded items are checked.
+#[derive(Debug, Default)]
+struct SeenHistory {
+    uuids: HashSet<String>,
+    newest: Option<String>,
+    len: usize,
+}
+
+impl SeenHistory {
+    /// Records `uuids`, newest first, and returns whether the newest one was just copied: it is
+    /// new, or an older item that moved to the top because it was copied again, which GPaste
+    /// records under the existing uuid. An older item that became the newest because the one
+    /// above it was deleted leaves the history shorter, so it does not count.
+    fn update(&mut self, uuids: Vec<String>) -> Option<String> {
+        let newest = uuids.first().cloned();
+        let copied = newest.clone().filter(|uuid| {
+            !self.uuids.contains(uuid) || (self.newest.as_ref() != Some(uuid) && uuids.len() >= self.len)
+        });
+        self.len = uuids.len();
+        self.uuids = uuids.into_iter().collect();
+        self.newest = newest;
+        copied
+    }
+}
+
+/// `None` until the history was read once; reading it failed when the watch started.
+type KnownItems = Arc<Mutex<Option<SeenHistory>>>;
+
+fn read_history(backend: &dyn ClipboardBackend) -> Option<Vec<String>> {
+    match backend.history(0, None) {
+        Ok(page) => Some(page.items.into_iter().map(|item| item.uuid).collect()),
+        Err(e) => {
+            eprintln!("Error reading the clipboard history: {}", e);
+            None
+        }
+    }
+}
+
+fn read_known_items(backend: &dyn ClipboardBackend, known: &mut Option<SeenHistory>) {
+    if let Some(uuids) = read_history(backend) {
+        let mut seen = SeenHistory::default();
+        seen.update(uuids);
+        *known = Some(seen);
+    }
+}
+
+/// Deletes the newest history item if it was just copied and the focused application is
+/// excluded. The backend does not record where an item came from, so the application focused
+/// when the history changed is taken as its source.
+fn check_newest_item(backend: &dyn ClipboardBackend, rules: &ExclusionRules, known: &KnownItems) {
+    let source = focused_application();
+    let mut known = known.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(seen) = known.as_mut() else {
+        read_known_items(backend, &mut known);
+        return;
+    };
+
+    let Some(uuids) = read_history(backend) else {
+        return;
+    };
+    let Some(newest) = seen.update(uuids) else {
+        return;
+    };
+
+    if let Some(source) = source.filter(|source| rules.excludes(source)) {
+        if let Err(e) = backend.delete(&newest) {
+            eprintln!("Error removing an entry copied from {}: {}", source.names.join("/"), e);
+        }
+    }
+}
+
+/// Why the configured exclusions cannot be applied, if they are set and cannot be: they are
+/// only applied by a resident instance, and only where the focused application can be looked
+/// up.
+pub fn exclusions_unenforceable(config: &ExclusionsConfig, resident: bool) -> Option<String> {
+    if config.deny.is_empty() && config.allow.is_empty() {
+        return None;
+    }
+    if !resident {
+        return Some("[exclusions] are only applied while the picker runs with --resident".to_string());
+    }
+    focus_detection_unavailable()
+        .map(|reason| format!("[exclusions] cannot be applied, {}; copies are not checked", reason))
+}
+
+/// Removes what excluded applications copy from the history, for as long as the returned watch
+/// is kept. This is best effort: items are deleted only after GPaste recorded them, so they
+/// are briefly in the history and on disk, and the source is guessed from the focused window.
+/// Returns `None` if no rules are configured or the backend cannot report changes.
+pub fn watch_exclusions(backend: Arc<dyn ClipboardBackend>, config: &ExclusionsConfig) -> Option<ChangeWatch> {
+    if config.deny.is_empty() && config.allow.is_empty() {
+        return None;
+    }
+    let rules = Arc::new(ExclusionRules {
+        deny: config.deny.clone(),
+        allow: config.allow.clone(),
+    });
+    // Read before watching, so the first change is compared against the history it changed.
+    let mut initial = None;
+    read_known_items(backend.as_ref(), &mut initial);
+    let known: KnownItems = Arc::new(Mutex::new(initial));
+
+    let watched_backend = backend.clone();
+    backend.watch_changes(Box::new(move || {
+        let backend = watched_backend.clone();
+        let rules = rules.clone();
+        let known = known.clone();
+        let spawned = thread::Builder::new()
+            .name("clipboard-exclusions".to_string())
+            .spawn(move || check_newest_item(backend.as_ref(), &rules, &known));
+        if let Err(e) = spawned {
+            eprintln!("Error checking the clipboard source: {}", e);
+        }
+    }))
+}
@@ -0,0 +1,219 @@
+use gtk::{gdk, glib, prelude::*, Application, Clipboard};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::{self, DirBuilder, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::{DirBuilderExt, OpenOptionsExt},
+    path::PathBuf,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clipboard_manager::{backend::ClipboardBackend, session_files::remove_session_files};
+
+use crate::config::SecretsConfig;
+
+/// A scheduled timeout. While it is pending it holds the application, so it fires even after
+/// the picker was closed outside of resident mode.
+struct PendingTimer {
+    source: glib::SourceId,
+    application: Option<Application>,
+}
+
+impl PendingTimer {
+    fn start<F: FnOnce() + 'static>(application: Option<&Application>, delay: Duration, on_timeout: F) -> Self {
+        if let Some(application) = application {
+            application.hold();
+        }
+        Self {
+            source: glib::timeout_add_local_once(delay, on_timeout),
+            application: application.cloned(),
+        }
+    }
+
+    /// Lets the application exit again once the timer fired.
+    fn finish(self) {
+        if let Some(application) = self.application {
+            application.release();
+        }
+    }
+
+    fn cancel(self) {
+        self.source.remove();
+        if let Some(application) = self.application {
+            application.release();
+        }
+    }
+}
+
+/// Pending deletions, one `<deadline> <uuid>` line each with the deadline in seconds since the
+/// epoch, so they survive the picker being stopped before they are due.
+struct ExpiryFile {
+    path: PathBuf,
+}
+
+impl ExpiryFile {
+    const DIR_MODE: u32 = 0o700;
+    const FILE_MODE: u32 = 0o600;
+
+    fn new() -> Self {
+        Self {
+            path: glib::user_data_dir().join("clipboard_manager").join("expiries"),
+        }
+    }
+
+    fn load(&self) -> Vec<(String, u64)> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                eprintln!("Error reading {}: {}", self.path.display(), e);
+                return Vec::new();
+            }
+        };
+        contents
+            .lines()
+            .filter_map(|line| {
+                let (deadline, uuid) = line.split_once(' ')?;
+                Some((uuid.to_string(), deadline.parse().ok()?))
+            })
+            .collect()
+    }
+
+    fn save(&self, deadlines: &HashMap<String, u64>) {
+        if let Err(e) = self.write(deadlines) {
+            eprintln!("Error writing {}: {}", self.path.display(), e);
+        }
+    }
+
+    fn write(&self, deadlines: &HashMap<String, u64>) -> io::Result<()> {
+        if deadlines.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        if let Some(dir) = self.path.parent() {
+            DirBuilder::new().recursive(true).mode(Self::DIR_MODE).create(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(Self::FILE_MODE)
+            .open(&self.path)?;
+        for (uuid, deadline) in deadlines {
+            writeln!(file, "{} {}", deadline, uuid)?;
+        }
+        Ok(())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs())
+}
+
+/// Clears the clipboard and deletes sensitive entries from the history when their time is up,
+/// as configured in `[secrets]`. Both start when a sensitive entry is copied from the picker.
+/// Pending deletions are kept on disk and resumed on the next start; a pending clear is not.
+pub struct ExpiryScheduler {
+    clear_after: Option<Duration>,
+    delete_after: Option<Duration>,
+    backend: Arc<dyn ClipboardBackend>,
+    clear_timer: Rc<RefCell<Option<PendingTimer>>>,
+    /// Pending deletions by entry uuid.
+    expiries: Rc<RefCell<HashMap<String, PendingTimer>>>,
+    /// Deadlines of the pending deletions, as saved to `expiry_file`.
+    deadlines: Rc<RefCell<HashMap<String, u64>>>,
+    expiry_file: Rc<ExpiryFile>,
+}
+
+impl ExpiryScheduler {
+    const SECONDS_PER_MINUTE: u64 = 60;
+
+    /// Resumes the deletions a previous run left pending; overdue ones happen right away.
+    pub fn new(config: &SecretsConfig, backend: Arc<dyn ClipboardBackend>) -> Self {
+        let scheduler = Self {
+            clear_after: config.clear_clipboard_after_secs.map(Duration::from_secs),
+            delete_after: config
+                .delete_after_minutes
+                .map(|minutes| Duration::from_secs(minutes.saturating_mul(Self::SECONDS_PER_MINUTE))),
+            backend,
+            clear_timer: Rc::new(RefCell::new(None)),
+            expiries: Rc::new(RefCell::new(HashMap::new())),
+            deadlines: Rc::new(RefCell::new(HashMap::new())),
+            expiry_file: Rc::new(ExpiryFile::new()),
+        };
+        let now = now_secs();
+        for (uuid, deadline) in scheduler.expiry_file.load() {
+            scheduler.schedule_deletion(None, Duration::from_secs(deadline.saturating_sub(now)), uuid);
+        }
+        scheduler
+    }
+
+    /// Starts the timers for a sensitive entry that was just copied. `text` is the copied
+    /// text; the clipboard is only cleared if it still holds it by then.
+    pub fn schedule(&self, application: Option<&Application>, uuid: &str, text: Option<&str>) {
+        if let (Some(delay), Some(text)) = (self.clear_after, text) {
+            self.schedule_clear(application, delay, text.to_string());
+        }
+        if let Some(delay) = self.delete_after {
+            self.schedule_deletion(application, delay, uuid.to_string());
+        }
+    }
+
+    /// Only the most recently copied secret can still be on the clipboard, so a new copy
+    /// replaces the pending clear.
+    fn schedule_clear(&self, application: Option<&Application>, delay: Duration, text: String) {
+        if let Some(previous) = self.clear_timer.take() {
+            previous.cancel();
+        }
+
+        let clear_timer = self.clear_timer.clone();
+        let timer = PendingTimer::start(application, delay, move || {
+            let Some(timer) = clear_timer.take() else {
+                return;
+            };
+            let clipboard = Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+            clipboard.request_text(move |clipboard, current| {
+                if current == Some(text.as_str()) {
+                    // Only what this process owns can be cleared, so take the clipboard first.
+                    clipboard.set_text("");
+                    clipboard.clear();
+                }
+                timer.finish();
+            });
+        });
+        self.clear_timer.replace(Some(timer));
+    }
+
+    /// Copying the same entry again restarts its expiry.
+    fn schedule_deletion(&self, application: Option<&Application>, delay: Duration, uuid: String) {
+        if let Some(previous) = self.expiries.borrow_mut().remove(&uuid) {
+            previous.cancel();
+        }
+
+        let expiries = self.expiries.clone();
+        let deadlines = self.deadlines.clone();
+        let expiry_file = self.expiry_file.clone();
+        let backend = self.backend.clone();
+        let expired_uuid = uuid.clone();
+        let timer = PendingTimer::start(application, delay, move || {
+            let Some(timer) = expiries.borrow_mut().remove(&expired_uuid) else {
+                return;
+            };
+            deadlines.borrow_mut().remove(&expired_uuid);
+            expiry_file.save(&deadlines.borrow());
+            if let Err(e) = backend.delete(&expired_uuid) {
+                eprintln!("Error deleting expired entry {}: {}", expired_uuid, e);
+            }
+            remove_session_files(&expired_uuid);
+            timer.finish();
+        });
+        self.deadlines.borrow_mut().insert(uuid.clone(), now_secs().saturating_add(delay.as_secs()));
+        self.expiry_file.save(&self.deadlines.borrow());
+        self.expiries.borrow_mut().insert(uuid, timer);
+    }
+}
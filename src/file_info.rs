@@ -0,0 +1,150 @@
+use gtk::{gio, glib, prelude::*};
+use std::{fs, thread};
+
+/// What the file system knows about a copied file.
+#[derive(Debug, Clone)]
+pub struct FileDetails {
+    /// Content type sniffed from the file's first bytes, e.g. `text/x-python`.
+    pub content_type: String,
+    /// Readable name of the content type, e.g. `Python script`.
+    pub description: String,
+    pub size: u64,
+    /// Permission bits of the file mode.
+    pub mode: u32,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    /// Last modification as seconds since the Unix epoch.
+    pub modified: Option<i64>,
+}
+
+/// What a copied path pointed to when the history was loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    File { size: u64 },
+    Directory,
+    Missing,
+}
+
+impl PathKind {
+    /// Looks `path` up. This touches the file system, so it belongs on a worker thread.
+    pub fn of(path: &str) -> Self {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.is_dir() => PathKind::Directory,
+            Ok(metadata) => PathKind::File { size: metadata.len() },
+            Err(_) => PathKind::Missing,
+        }
+    }
+
+    /// Size of a file; folders and missing files count as empty.
+    pub fn size(&self) -> u64 {
+        match self {
+            PathKind::File { size } => *size,
+            PathKind::Directory | PathKind::Missing => 0,
+        }
+    }
+}
+
+/// Result of looking up a file: its details, `None` if it no longer exists, or the reason the
+/// lookup failed.
+pub type FileLookup = Result<Option<FileDetails>, String>;
+
+const ATTRIBUTES: &str = "standard::content-type,standard::size,unix::mode,owner::user,owner::group,time::modified";
+
+fn query_file_details(path: &str) -> FileLookup {
+    let info = match gio::File::for_path(path).query_info(
+        ATTRIBUTES,
+        gio::FileQueryInfoFlags::NONE,
+        gio::Cancellable::NONE,
+    ) {
+        Ok(info) => info,
+        Err(e) if e.matches(gio::IOErrorEnum::NotFound) => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let content_type = info
+        .content_type()
+        .map(|content_type| content_type.to_string())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    let description = gio::content_type_get_description(&content_type).to_string();
+
+    Ok(Some(FileDetails {
+        description,
+        content_type,
+        size: info.size().max(0) as u64,
+        mode: info.attribute_uint32("unix::mode") & 0o7777,
+        owner: info.attribute_string("owner::user").map(|owner| owner.to_string()),
+        group: info.attribute_string("owner::group").map(|group| group.to_string()),
+        modified: info
+            .has_attribute("time::modified")
+            .then(|| info.attribute_uint64("time::modified") as i64),
+    }))
+}
+
+/// Looks up the details of the file at `path` on a worker thread, since sniffing the content
+/// type reads from the file, and delivers them to the main loop.
+pub fn query_file_details_async<F>(path: &str, on_queried: F)
+where
+    F: FnOnce(FileLookup) + 'static,
+{
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT_IDLE);
+
+    let mut on_queried = Some(on_queried);
+    receiver.attach(None, move |lookup| {
+        if let Some(on_queried) = on_queried.take() {
+            on_queried(lookup);
+        }
+        glib::Continue(false)
+    });
+
+    let path = path.to_string();
+    let worker_sender = sender.clone();
+    let spawned = thread::Builder::new()
+        .name("file-info".to_string())
+        .spawn(move || {
+            let _ = worker_sender.send(query_file_details(&path));
+        });
+
+    if let Err(e) = spawned {
+        let _ = sender.send(Err(e.to_string()));
+    }
+}
+
+/// Human-readable file size, e.g. `512 B`, `3.4 KB` or `1.2 MB`.
+pub fn format_size(size: u64) -> String {
+    if size < 1024 {
+        format!("{} B", size)
+    } else if size < 1024 * 1024 {
+        format!("{:.1} KB", size as f64 / 1024.0)
+    } else if size < 1024 * 1024 * 1024 {
+        format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} GB", size as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+/// Permission bits in `ls` notation, e.g. `rw-r--r--`.
+pub fn format_permissions(mode: u32) -> String {
+    const FLAGS: [(u32, char); 9] = [
+        (0o400, 'r'),
+        (0o200, 'w'),
+        (0o100, 'x'),
+        (0o040, 'r'),
+        (0o020, 'w'),
+        (0o010, 'x'),
+        (0o004, 'r'),
+        (0o002, 'w'),
+        (0o001, 'x'),
+    ];
+    FLAGS
+        .iter()
+        .map(|&(bit, flag)| if mode & bit != 0 { flag } else { '-' })
+        .collect()
+}
+
+/// Local date and time of a Unix timestamp, e.g. `2024-03-01 14:05`.
+pub fn format_timestamp(seconds: i64) -> String {
+    glib::DateTime::from_unix_local(seconds)
+        .and_then(|time| time.format("%Y-%m-%d %H:%M"))
+        .map(|time| time.to_string())
+        .unwrap_or_else(|_| seconds.to_string())
+}
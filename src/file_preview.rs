@@ -0,0 +1,175 @@
+use gtk::glib;
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    os::unix::fs::FileTypeExt,
+    thread,
+};
+
+use crate::syntax_highlight::{Highlighter, Language, StyledRange, ThemeVariant};
+
+/// Part of a file preview, sent from the reading thread to the main loop.
+pub enum PreviewEvent {
+    /// The next piece of the file's text, with syntax highlighting if it was asked for.
+    /// Offsets of `styles` are relative to `text`.
+    Text { text: String, styles: Vec<StyledRange> },
+    /// The preview stopped at the size limit after `shown` of `total` bytes.
+    Truncated { shown: u64, total: u64 },
+    /// The file does not look like text.
+    Binary { size: u64 },
+    /// The file cannot be previewed, with the reason why.
+    Unavailable(String),
+}
+
+const CHUNK_SIZE: usize = 64 * 1024;
+/// A NUL byte this close to the start marks a file as binary.
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+fn file_kind(file_type: &fs::FileType) -> &'static str {
+    if file_type.is_dir() {
+        "a directory"
+    } else if file_type.is_fifo() {
+        "a named pipe"
+    } else if file_type.is_socket() {
+        "a socket"
+    } else if file_type.is_block_device() || file_type.is_char_device() {
+        "a device"
+    } else {
+        "not a regular file"
+    }
+}
+
+/// Streams UTF-8 text to the main loop, holding back a character split between chunks.
+struct TextStream<'a> {
+    sender: &'a glib::Sender<PreviewEvent>,
+    pending: Vec<u8>,
+    sent_any: bool,
+    highlighter: Option<Highlighter>,
+}
+
+impl TextStream<'_> {
+    /// Returns `false` once the file turned out to be binary or nobody listens any more.
+    fn push(&mut self, chunk: &[u8], size: u64) -> bool {
+        if !self.sent_any && chunk[..chunk.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+            let _ = self.sender.send(PreviewEvent::Binary { size });
+            return false;
+        }
+
+        self.pending.extend_from_slice(chunk);
+        let text = match std::str::from_utf8(&self.pending) {
+            Ok(text) => {
+                let text = text.to_string();
+                self.pending.clear();
+                text
+            }
+            Err(e) if e.error_len().is_none() => {
+                let valid_up_to = e.valid_up_to();
+                let text = String::from_utf8_lossy(&self.pending[..valid_up_to]).into_owned();
+                self.pending.drain(..valid_up_to);
+                text
+            }
+            Err(_) if !self.sent_any => {
+                let _ = self.sender.send(PreviewEvent::Binary { size });
+                return false;
+            }
+            Err(_) => String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned(),
+        };
+
+        self.sent_any = true;
+        self.send_text(&text)
+    }
+
+    fn send_text(&mut self, text: &str) -> bool {
+        let (text, styles) = match &mut self.highlighter {
+            Some(highlighter) => highlighter.push(text),
+            None => (text.to_string(), Vec::new()),
+        };
+        if text.is_empty() {
+            return true;
+        }
+        self.sender.send(PreviewEvent::Text { text, styles }).is_ok()
+    }
+
+    fn finish(mut self) {
+        if !self.pending.is_empty() {
+            let text = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned();
+            self.send_text(&text);
+        }
+        if let Some(highlighter) = self.highlighter.take() {
+            let (text, styles) = highlighter.finish();
+            if !text.is_empty() {
+                let _ = self.sender.send(PreviewEvent::Text { text, styles });
+            }
+        }
+    }
+}
+
+fn read_preview(
+    path: &str,
+    max_bytes: u64,
+    highlight: Option<(Language, ThemeVariant)>,
+    sender: &glib::Sender<PreviewEvent>,
+) -> io::Result<()> {
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_file() {
+        // Opening a FIFO or device could block forever.
+        let _ = sender.send(PreviewEvent::Unavailable(format!("{} is {}", path, file_kind(&metadata.file_type()))));
+        return Ok(());
+    }
+
+    let size = metadata.len();
+    let mut reader = File::open(path)?.take(max_bytes);
+    let mut stream = TextStream {
+        sender,
+        pending: Vec::new(),
+        sent_any: false,
+        highlighter: highlight.map(|(language, variant)| Highlighter::new(language, variant)),
+    };
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut shown: u64 = 0;
+
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        shown += read as u64;
+        if !stream.push(&buffer[..read], size) {
+            return Ok(());
+        }
+    }
+    stream.finish();
+
+    if size > shown {
+        let _ = sender.send(PreviewEvent::Truncated { shown, total: size });
+    }
+
+    Ok(())
+}
+
+/// Reads up to `max_bytes` of the file at `path` on a worker thread and delivers it to the
+/// main loop piece by piece, highlighted as `highlight` asks. Reading stops early once the
+/// receiver is dropped.
+pub fn stream_file_preview(
+    path: &str,
+    max_bytes: u64,
+    highlight: Option<(Language, ThemeVariant)>,
+) -> glib::Receiver<PreviewEvent> {
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT_IDLE);
+
+    let path = path.to_string();
+    let worker_sender = sender.clone();
+    let spawned = thread::Builder::new()
+        .name("file-preview".to_string())
+        .spawn(move || {
+            if let Err(e) = read_preview(&path, max_bytes, highlight, &worker_sender) {
+                let _ = worker_sender.send(PreviewEvent::Unavailable(format!("Error reading file: {}", e)));
+            }
+        });
+
+    if let Err(e) = spawned {
+        let _ = sender.send(PreviewEvent::Unavailable(format!("Error reading file: {}", e)));
+    }
+
+    receiver
+}
@@ -1,139 +1,355 @@
-use std::{io, process::Command};
-
-use clipboard_manager::clipboard_entries::{
-    ClipboardEntry,
-    ClipboardFileEntry,
-    ClipboardImageEntry,
-    ClipboardTextEntry,
+use clipboard_manager::{
+    backend::{ClipboardBackend, HistoryItem},
+    clipboard_entries::{
+        ClipboardColorEntry,
+        ClipboardDataEntry,
+        ClipboardDirectoryEntry,
+        ClipboardEncryptedEntry,
+        ClipboardEntry,
+        ClipboardFileEntry,
+        ClipboardImageEntry,
+        ClipboardTextEntry,
+        ClipboardUrlEntry,
+    },
+    color_value::ColorValue,
+    encrypted_history::{EncryptedHistory, EncryptedKind},
+    file_info::PathKind,
+    secret_detection::{detect_secret, SecretKind},
+    structured_data::StructuredData,
+    thumbnail_cache::ThumbnailCache,
+    ClipboardError,
 };
+use gtk::glib;
+use std::{collections::HashSet, path::Path, sync::Arc, thread};
+use url::Url;
 
-const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "tiff", "webp"];
-
-fn is_image(content: &str) -> bool {
-    if content.starts_with(" [Image,") {
-        return true;
-    }
+use crate::config::Config;
 
-    if !content.starts_with(" [Files] ") {
-        return false;
-    }
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "tiff", "webp"];
 
-    let path = content.trim_start_matches(" [Files] ").trim();
-    let path_obj = std::path::Path::new(path);
-    
-    path_obj
+fn is_image_file(path: &str) -> bool {
+    Path::new(path)
         .extension()
         .and_then(|ext| ext.to_str())
         .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
         .unwrap_or(false)
 }
 
-fn execute_gpaste_command(args: &[&str]) -> Result<String, io::Error> {
-    let output = Command::new("gpaste-client")
-        .args(args)
-        .output()?;
+/// A history item as fetched from the backend on a worker thread. Widgets can only be
+/// created on the main thread, so entries are built from it there.
+pub enum EntryData {
+    Text { uuid: String, content: String },
+    Secret { uuid: String, content: String, secret: SecretKind },
+    Url { uuid: String, content: String, url: Url },
+    Color { uuid: String, content: String, color: ColorValue },
+    Data { uuid: String, content: String, data: StructuredData },
+    Image { uuid: String, path: String },
+    /// `kinds` tells what each of `paths` is, looked up on the worker thread.
+    File { uuid: String, paths: Vec<String>, kinds: Vec<PathKind> },
+    Directory { uuid: String, path: String },
+    /// An item of the encrypted history, described without decrypting it.
+    Encrypted { uuid: String, kind: EncryptedKind, description: String },
+}
 
-    if !output.status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("gpaste-client command failed with status: {}", output.status)
-        ));
+fn fetch_entry_data(backend: &dyn ClipboardBackend, item: HistoryItem) -> Result<Option<EntryData>, String> {
+    let HistoryItem { uuid, content } = item;
+    if content.is_empty() {
+        return Ok(None);
     }
 
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    if let Some(kind) = EncryptedHistory::kind_of(&uuid) {
+        let description = content.trim().trim_start_matches('[').trim_end_matches(']').to_string();
+        Ok(Some(EntryData::Encrypted { uuid, kind, description }))
+    } else if content.starts_with(" [Image,") {
+        backend.entry_path(&uuid)
+            .map(|path| Some(EntryData::Image { uuid: uuid.clone(), path }))
+            .map_err(|e| format!("Error creating image entry for UUID {}: {}", uuid, e))
+    } else if content.starts_with(" [Files] ") {
+        let mut paths = backend
+            .entry_files(&uuid)
+            .map_err(|e| format!("Error creating file entry for UUID {}: {}", uuid, e))?;
+        // A single copied image file is shown like an image, a single folder as a listing.
+        if paths.len() == 1 && is_image_file(&paths[0]) {
+            return Ok(Some(EntryData::Image { uuid, path: paths.remove(0) }));
+        }
+        let kinds: Vec<PathKind> = paths.iter().map(|path| PathKind::of(path)).collect();
+        if kinds == [PathKind::Directory] {
+            return Ok(Some(EntryData::Directory { uuid, path: paths.remove(0) }));
+        }
+        Ok(Some(EntryData::File { uuid, paths, kinds }))
+    } else if let Some(secret) = detect_secret(&content) {
+        Ok(Some(EntryData::Secret { uuid, content, secret }))
+    } else if let Some(color) = ColorValue::parse(&content) {
+        Ok(Some(EntryData::Color { uuid, content, color }))
+    } else if let Some(url) = ClipboardUrlEntry::parse(&content) {
+        Ok(Some(EntryData::Url { uuid, content, url }))
+    } else if let Some(data) = StructuredData::detect(&content) {
+        Ok(Some(EntryData::Data { uuid, content, data }))
+    } else {
+        Ok(Some(EntryData::Text { uuid, content }))
+    }
 }
 
-fn get_entry_path(uuid: &str) -> Result<String, io::Error> {
-    execute_gpaste_command(&["--raw", "get", uuid])
-}
+pub fn create_clipboard_entry(
+    data: EntryData,
+    config: &Config,
+    thumbnail_cache: Option<&Arc<ThumbnailCache>>,
+    encrypted_history: &Arc<EncryptedHistory>,
+) -> Box<dyn ClipboardEntry> {
+    let row_width = config.window.list_width;
+    let row_image_max_height = config.rows.image_max_height;
+    let row_text_max_lines = config.rows.text_max_lines;
 
-fn create_image_entry(
-    uuid: String,
-    width: i32,
-    row_max_height: i32,
-) -> Result<Box<dyn ClipboardEntry>, io::Error> {
-    let path = get_entry_path(&uuid)?;
-    let image_entry = ClipboardImageEntry::new(path, uuid, width, row_max_height);
-    Ok(Box::new(image_entry))
+    match data {
+        EntryData::Text { uuid, content } => {
+            Box::new(ClipboardTextEntry::new(content, uuid, row_width, row_text_max_lines))
+        }
+        EntryData::Secret { uuid, content, secret } => {
+            Box::new(ClipboardTextEntry::new_sensitive(content, secret, uuid, row_width, row_text_max_lines))
+        }
+        EntryData::Url { uuid, content, url } => {
+            Box::new(ClipboardUrlEntry::new(url, content, uuid, row_width))
+        }
+        EntryData::Color { uuid, content, color } => {
+            Box::new(ClipboardColorEntry::new(color, content, uuid, row_width))
+        }
+        EntryData::Data { uuid, content, data } => {
+            Box::new(ClipboardDataEntry::new(data, content, uuid, row_width, row_text_max_lines))
+        }
+        EntryData::Image { uuid, path } => {
+            Box::new(ClipboardImageEntry::new(
+                path,
+                uuid,
+                row_width,
+                row_image_max_height,
+                thumbnail_cache.cloned(),
+            ))
+        }
+        EntryData::File { uuid, paths, kinds } => {
+            Box::new(ClipboardFileEntry::new(
+                paths,
+                kinds,
+                uuid,
+                row_width,
+                row_image_max_height,
+                config.preview_max_bytes(),
+            ))
+        }
+        EntryData::Directory { uuid, path } => {
+            Box::new(ClipboardDirectoryEntry::new(
+                path,
+                uuid,
+                row_width,
+                row_image_max_height,
+                config.directory_listing_limits(),
+            ))
+        }
+        EntryData::Encrypted { uuid, kind, description } => {
+            Box::new(ClipboardEncryptedEntry::new(
+                uuid,
+                kind,
+                description,
+                encrypted_history.clone(),
+                row_width,
+                row_image_max_height,
+            ))
+        }
+    }
 }
 
-fn create_file_entry(
-    uuid: String,
-    width: i32,
-    row_max_height: i32,
-) -> Result<Box<dyn ClipboardEntry>, io::Error> {
-    let path = get_entry_path(&uuid)?;
-    let file_entry = ClipboardFileEntry::new(path, uuid, width, row_max_height);
-    Ok(Box::new(file_entry))
+/// The history as listed by the backend at one point in time, newest items first. Pages are
+/// sliced from it, so their offsets stay consistent while items are copied or deleted in the
+/// meantime; such changes reach the list through a sync, which takes a new snapshot.
+pub type HistorySnapshot = Arc<Vec<HistoryItem>>;
+
+/// Progress of a background load, delivered to the main loop.
+pub enum LoadMessage {
+    /// The history was listed because no snapshot was passed in; later pages are sliced from
+    /// this one.
+    Snapshot(HistorySnapshot),
+    Batch {
+        entries: Vec<EntryData>,
+        /// Reasons why individual history items could not be turned into entries.
+        failures: Vec<String>,
+    },
+    /// All requested items were delivered. `next_offset` is where the following page starts,
+    /// or `None` at the end of the history.
+    Finished { next_offset: Option<usize> },
+    Failed(ClipboardError),
 }
 
-fn create_text_entry(
-    content: String,
-    uuid: String,
-    width: i32,
-    row_text_max_lines: i32,
-) -> Box<dyn ClipboardEntry> {
-    Box::new(ClipboardTextEntry::new(content, uuid, width, row_text_max_lines))
+const BATCH_SIZE: usize = 10;
+
+/// Lists the whole history, reporting lines that could not be parsed.
+fn fetch_snapshot(
+    backend: &dyn ClipboardBackend,
+    sender: &glib::Sender<LoadMessage>,
+) -> Result<HistorySnapshot, ClipboardError> {
+    let page = backend.history(0, None)?;
+
+    if !page.invalid_lines.is_empty() {
+        let failures = page
+            .invalid_lines
+            .into_iter()
+            .map(|line| format!("Invalid clipboard entry format: {}", line))
+            .collect();
+        let _ = sender.send(LoadMessage::Batch { entries: Vec::new(), failures });
+    }
+
+    let snapshot = Arc::new(page.items);
+    let _ = sender.send(LoadMessage::Snapshot(snapshot.clone()));
+    Ok(snapshot)
 }
 
-fn parse_clipboard_line(line: &str) -> Option<(String, String)> {
-    let colon_pos = line.find(':')?;
-    let uuid = line[..colon_pos].to_string();
-    let content = line[colon_pos + 1..].to_string();
-    Some((uuid, content))
+fn fetch_history(
+    backend: &dyn ClipboardBackend,
+    snapshot: Option<HistorySnapshot>,
+    offset: usize,
+    limit: Option<usize>,
+    sender: &glib::Sender<LoadMessage>,
+) -> Result<Option<usize>, ClipboardError> {
+    let snapshot = match snapshot {
+        Some(snapshot) => snapshot,
+        None => fetch_snapshot(backend, sender)?,
+    };
+
+    let end = match limit {
+        Some(limit) => snapshot.len().min(offset.saturating_add(limit)),
+        None => snapshot.len(),
+    };
+
+    let mut items = snapshot.get(offset..end).unwrap_or_default().iter().cloned().peekable();
+    while items.peek().is_some() {
+        let mut entries = Vec::new();
+        let mut failures = Vec::new();
+
+        for item in items.by_ref().take(BATCH_SIZE) {
+            match fetch_entry_data(backend, item) {
+                Ok(Some(entry)) => entries.push(entry),
+                Ok(None) => {}
+                Err(failure) => failures.push(failure),
+            }
+        }
+
+        if sender.send(LoadMessage::Batch { entries, failures }).is_err() {
+            // The window was closed; nobody is waiting for the rest.
+            break;
+        }
+    }
+
+    Ok((end < snapshot.len()).then_some(end))
 }
 
-fn create_clipboard_entry(
-    uuid: String,
-    content: String,
-    row_width: i32,
-    row_image_max_height: i32,
-    row_text_max_lines: i32,
-) -> Option<Box<dyn ClipboardEntry>> {
-    if content.is_empty() {
-        return None;
+/// Fetches up to `limit` history items starting at `offset` of `snapshot` on a worker thread;
+/// `None` fetches the rest of the history. Without a snapshot the history is listed first and
+/// sent back as `Snapshot`. The returned receiver gets the items in batches, followed by
+/// `Finished` or `Failed`.
+pub fn load_clipboard_entries(
+    backend: Arc<dyn ClipboardBackend>,
+    snapshot: Option<HistorySnapshot>,
+    offset: usize,
+    limit: Option<usize>,
+) -> glib::Receiver<LoadMessage> {
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+    let worker_sender = sender.clone();
+    let spawned = thread::Builder::new()
+        .name("clipboard-loader".to_string())
+        .spawn(move || {
+            let message = match fetch_history(backend.as_ref(), snapshot, offset, limit, &worker_sender) {
+                Ok(next_offset) => LoadMessage::Finished { next_offset },
+                Err(e) => LoadMessage::Failed(e),
+            };
+            let _ = worker_sender.send(message);
+        });
+
+    if let Err(e) = spawned {
+        let _ = sender.send(LoadMessage::Failed(e.into()));
     }
 
-    if is_image(&content) {
-        create_image_entry(uuid.clone(), row_width, row_image_max_height)
-            .map_err(|e| eprintln!("Error creating image entry for UUID {}: {}", uuid, e))
-            .ok()
-    } else if content.starts_with(" [Files] ") {
-        create_file_entry(uuid.clone(), row_width, row_image_max_height)
-            .map_err(|e| eprintln!("Error creating file entry for UUID {}: {}", uuid, e))
-            .ok()
+    receiver
+}
+
+/// How the backend's history differs from the loaded entries.
+pub struct HistorySync {
+    /// Uuids of the loaded part of the history, newest first.
+    pub order: Vec<String>,
+    /// Items in that part that were not loaded yet.
+    pub new_entries: Vec<EntryData>,
+    pub failures: Vec<String>,
+    /// Offset of the first item after the loaded part, or `None` at the end of the history.
+    pub next_offset: Option<usize>,
+    /// The history the offset refers to, for loading the following pages.
+    pub snapshot: HistorySnapshot,
+}
+
+fn fetch_history_sync(
+    backend: &dyn ClipboardBackend,
+    known_uuids: &HashSet<String>,
+    all_loaded: bool,
+    min_count: usize,
+) -> Result<HistorySync, ClipboardError> {
+    let page = backend.history(0, None)?;
+    let snapshot: HistorySnapshot = Arc::new(page.items);
+    let total = snapshot.len();
+
+    // The loaded part ends at the oldest item that is already loaded, so scrolling further
+    // continues right after it.
+    let loaded_end = if all_loaded {
+        total
     } else {
-        Some(create_text_entry(content, uuid, row_width, row_text_max_lines))
+        snapshot
+            .iter()
+            .rposition(|item| known_uuids.contains(&item.uuid))
+            .map_or(0, |index| index + 1)
+            .max(min_count.min(total))
+    };
+
+    let mut sync = HistorySync {
+        order: Vec::new(),
+        new_entries: Vec::new(),
+        failures: Vec::new(),
+        next_offset: (loaded_end < total).then_some(loaded_end),
+        snapshot: snapshot.clone(),
+    };
+
+    for item in snapshot.iter().take(loaded_end).cloned() {
+        sync.order.push(item.uuid.clone());
+        if known_uuids.contains(&item.uuid) {
+            continue;
+        }
+        match fetch_entry_data(backend, item) {
+            Ok(Some(entry)) => sync.new_entries.push(entry),
+            Ok(None) => {}
+            Err(failure) => sync.failures.push(failure),
+        }
     }
+
+    Ok(sync)
 }
 
-pub fn get_clipboard_entries(
-    limit: usize,
-    row_width: i32,
-    row_image_max_height: i32,
-    row_text_max_lines: i32,
-) -> Result<Vec<Box<dyn ClipboardEntry>>, io::Error> {
-    let history = execute_gpaste_command(&["history", "--zero"])?;
-    
-    Ok(history
-        .split('\0')
-        .filter(|s| !s.is_empty())
-        .take(limit)
-        .filter_map(|line| {
-            match parse_clipboard_line(line) {
-                Some((uuid, content)) => create_clipboard_entry(
-                    uuid,
-                    content,
-                    row_width,
-                    row_image_max_height,
-                    row_text_max_lines,
-                ),
-                _ => {
-                    eprintln!("Invalid clipboard entry format: {}", line);
-                    None
-                }
-            }
-        })
-        .collect()
-    )
+/// Compares the history with the entries whose uuids are `known_uuids` on a worker thread.
+/// `all_loaded` tells whether the whole history was loaded; otherwise at least `min_count`
+/// items are taken as loaded.
+pub fn sync_clipboard_entries(
+    backend: Arc<dyn ClipboardBackend>,
+    known_uuids: HashSet<String>,
+    all_loaded: bool,
+    min_count: usize,
+) -> glib::Receiver<Result<HistorySync, ClipboardError>> {
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+
+    let worker_sender = sender.clone();
+    let spawned = thread::Builder::new()
+        .name("clipboard-sync".to_string())
+        .spawn(move || {
+            let result = fetch_history_sync(backend.as_ref(), &known_uuids, all_loaded, min_count);
+            let _ = worker_sender.send(result);
+        });
+
+    if let Err(e) = spawned {
+        let _ = sender.send(Err(e.into()));
+    }
+
+    receiver
 }
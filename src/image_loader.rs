@@ -0,0 +1,189 @@
+use gtk::{
+    gdk_pixbuf::{prelude::*, Colorspace, Pixbuf, PixbufLoader},
+    glib::{self, Bytes, Continue, MainContext, ThreadPool},
+};
+use std::{fs, sync::Arc};
+
+use crate::thumbnail_cache::ThumbnailCache;
+
+/// Pixels decoded on a worker thread. `Pixbuf` cannot be sent between threads, so the raw
+/// pixel data is sent instead and wrapped in a new `Pixbuf` on the main thread.
+struct DecodedPixels {
+    bytes: Bytes,
+    has_alpha: bool,
+    bits_per_sample: i32,
+    width: i32,
+    height: i32,
+    rowstride: i32,
+}
+
+impl DecodedPixels {
+    fn decode(path: &str, max_width: i32, max_height: i32, cache: Option<&ThumbnailCache>) -> Option<Self> {
+        let cached = cache.and_then(|cache| cache.lookup(path, max_width, max_height));
+        let pixbuf = match cached {
+            Some(pixbuf) => pixbuf,
+            None => {
+                let pixbuf = Pixbuf::from_file_at_scale(path, max_width, max_height, true).ok()?;
+                if let Some(cache) = cache {
+                    cache.store(path, max_width, max_height, &pixbuf);
+                }
+                pixbuf
+            }
+        };
+        Self::from_pixbuf(&pixbuf)
+    }
+
+    fn from_pixbuf(pixbuf: &Pixbuf) -> Option<Self> {
+        Some(Self {
+            bytes: pixbuf.read_pixel_bytes()?,
+            has_alpha: pixbuf.has_alpha(),
+            bits_per_sample: pixbuf.bits_per_sample(),
+            width: pixbuf.width(),
+            height: pixbuf.height(),
+            rowstride: pixbuf.rowstride(),
+        })
+    }
+
+    fn into_pixbuf(self) -> Pixbuf {
+        Pixbuf::from_bytes(
+            &self.bytes,
+            Colorspace::Rgb,
+            self.has_alpha,
+            self.bits_per_sample,
+            self.width,
+            self.height,
+            self.rowstride,
+        )
+    }
+}
+
+struct DecodedImage {
+    pixels: Option<DecodedPixels>,
+    dimensions: Option<(i32, i32)>,
+    file_size: Option<u64>,
+}
+
+/// An image loaded by `load_image_async`, back on the main thread.
+pub struct LoadedImage {
+    /// The image scaled to fit the requested size, or `None` if it could not be decoded.
+    pub pixbuf: Option<Pixbuf>,
+    /// Full size of the image in pixels.
+    pub dimensions: Option<(i32, i32)>,
+    pub file_size: Option<u64>,
+}
+
+const MAX_DECODE_THREADS: u32 = 4;
+
+thread_local! {
+    static DECODE_POOL: Option<ThreadPool> = ThreadPool::exclusive(MAX_DECODE_THREADS)
+        .map_err(|e| eprintln!("Error creating image decoding threads: {}", e))
+        .ok();
+}
+
+/// Decodes the image at `path` scaled to fit `max_width`×`max_height` on a worker thread and
+/// calls `on_loaded` with the result on the main loop. Must be called from the main thread.
+pub fn load_image_async<F>(path: &str, max_width: i32, max_height: i32, on_loaded: F)
+where
+    F: FnOnce(LoadedImage) + 'static,
+{
+    load_async(path, max_width, max_height, None, on_loaded);
+}
+
+/// Like `load_image_async`, but decodes the image at its full size.
+pub fn load_full_image_async<F>(path: &str, on_loaded: F)
+where
+    F: FnOnce(LoadedImage) + 'static,
+{
+    // Negative sizes leave the image unscaled.
+    load_async(path, -1, -1, None, on_loaded);
+}
+
+/// Like `load_image_async`, but reuses and fills `cache` for the scaled image.
+pub fn load_thumbnail_async<F>(
+    path: &str,
+    max_width: i32,
+    max_height: i32,
+    cache: Arc<ThumbnailCache>,
+    on_loaded: F,
+) where
+    F: FnOnce(LoadedImage) + 'static,
+{
+    load_async(path, max_width, max_height, Some(cache), on_loaded);
+}
+
+/// Like `load_full_image_async`, but decodes the image `read` returns on the worker thread,
+/// e.g. after decrypting it, rather than a file. `read` returning `None` counts as an image
+/// that could not be decoded.
+pub fn load_image_data_async<R, F>(read: R, on_loaded: F)
+where
+    R: FnOnce() -> Option<Vec<u8>> + Send + 'static,
+    F: FnOnce(LoadedImage) + 'static,
+{
+    let (sender, receiver) = MainContext::channel::<DecodedImage>(glib::PRIORITY_DEFAULT_IDLE);
+    attach_decoded(receiver, on_loaded);
+
+    let decode = move || {
+        let data = read();
+        let pixbuf = data.as_deref().and_then(|data| {
+            let loader = PixbufLoader::new();
+            loader.write(data).ok()?;
+            loader.close().ok()?;
+            loader.pixbuf()
+        });
+        let decoded = DecodedImage {
+            dimensions: pixbuf.as_ref().map(|pixbuf| (pixbuf.width(), pixbuf.height())),
+            file_size: data.map(|data| data.len() as u64),
+            pixels: pixbuf.as_ref().and_then(DecodedPixels::from_pixbuf),
+        };
+        let _ = sender.send(decoded);
+    };
+    push_decode(decode, "an image held in memory");
+}
+
+fn attach_decoded<F>(receiver: glib::Receiver<DecodedImage>, on_loaded: F)
+where
+    F: FnOnce(LoadedImage) + 'static,
+{
+    let mut on_loaded = Some(on_loaded);
+    receiver.attach(None, move |decoded| {
+        if let Some(on_loaded) = on_loaded.take() {
+            on_loaded(LoadedImage {
+                pixbuf: decoded.pixels.map(DecodedPixels::into_pixbuf),
+                dimensions: decoded.dimensions,
+                file_size: decoded.file_size,
+            });
+        }
+        Continue(false)
+    });
+}
+
+fn push_decode<D: FnOnce() + Send + 'static>(decode: D, source: &str) {
+    let pushed = DECODE_POOL.with(|pool| match pool {
+        Some(pool) => pool.push(decode).map_err(|e| e.to_string()),
+        None => Err("no decoding threads".to_string()),
+    });
+    if let Err(e) = pushed {
+        eprintln!("Error queueing image decoding for {}: {}", source, e);
+    }
+}
+
+fn load_async<F>(path: &str, max_width: i32, max_height: i32, cache: Option<Arc<ThumbnailCache>>, on_loaded: F)
+where
+    F: FnOnce(LoadedImage) + 'static,
+{
+    let (sender, receiver) = MainContext::channel::<DecodedImage>(glib::PRIORITY_DEFAULT_IDLE);
+    attach_decoded(receiver, on_loaded);
+
+    let path_for_worker = path.to_string();
+    let decode = move || {
+        let path = path_for_worker;
+        let decoded = DecodedImage {
+            dimensions: Pixbuf::file_info(&path).map(|(_, width, height)| (width, height)),
+            file_size: fs::metadata(&path).map(|metadata| metadata.len()).ok(),
+            pixels: DecodedPixels::decode(&path, max_width, max_height, cache.as_deref()),
+        };
+        let _ = sender.send(decoded);
+    };
+
+    push_decode(decode, path);
+}
@@ -1,9 +1,8 @@
-use gtk::{prelude::*, ApplicationWindow, Box as GTKBox, Inhibit, ListBox, ScrolledWindow};
+use gtk::{gdk::EventKey, prelude::*, ApplicationWindow, Box as GTKBox, Inhibit, ListBox, ScrolledWindow};
 
-use crate::{
-    constants::*,
-    ui::{AppState, DetailsVisibility},
-};
+use crate::ui::{AppState, DetailsVisibility};
+
+use super::keymap::Mode;
 
 fn get_current_entry<'a>(
     list_box: &ListBox,
@@ -26,7 +25,7 @@ pub fn hide_detail(
     app_state: &AppState,
 ) -> Inhibit {
     main_box.remove(detail_scrolled_window);
-    window.resize(ENTRIES_WIDTH, APP_HEIGHT);
+    window.resize(app_state.config.window.list_width, app_state.config.window.height);
     *app_state.details_visibility.borrow_mut() = DetailsVisibility::Hidden;
     Inhibit(true)
 }
@@ -39,12 +38,13 @@ pub fn show_normal_detail(
     app_state: &AppState,
 ) -> Inhibit {
     *app_state.details_visibility.borrow_mut() = DetailsVisibility::Normal;
+    let (width, height) = (app_state.config.window.detail_width, app_state.config.window.height);
     
     if detail_scrolled_window.parent().is_none() {
-        detail_scrolled_window.set_size_request(INFO_BOX_WIDTH, APP_HEIGHT);
+        detail_scrolled_window.set_size_request(width, height);
         main_box.pack_start(detail_scrolled_window, true, true, 0);
     } else {
-        detail_scrolled_window.set_size_request(INFO_BOX_WIDTH, APP_HEIGHT);
+        detail_scrolled_window.set_size_request(width, height);
     }
     
     if let Some(entry) = get_current_entry(list_box, app_state) {
@@ -52,8 +52,8 @@ pub fn show_normal_detail(
             detail_container.remove(&child);
         }
         let detail_widget = entry.create_more_info_widget(
-            INFO_BOX_WIDTH,
-            APP_HEIGHT,
+            width,
+            height,
             app_state.search_query.borrow().clone()
         );
         detail_container.add(&detail_widget);
@@ -72,12 +72,13 @@ pub fn show_big_detail(
     app_state: &AppState,
 ) -> Inhibit {
     *app_state.details_visibility.borrow_mut() = DetailsVisibility::Big;
+    let (width, height) = (app_state.config.window.detail_big_width, app_state.config.window.detail_big_height);
     
     if detail_scrolled_window.parent().is_none() {
-        detail_scrolled_window.set_size_request(INFO_BOX_BIG_WIDTH, INFO_BOX_BIG_HEIGHT);
+        detail_scrolled_window.set_size_request(width, height);
         main_box.pack_start(detail_scrolled_window, true, true, 0);
     } else {
-        detail_scrolled_window.set_size_request(INFO_BOX_BIG_WIDTH, INFO_BOX_BIG_HEIGHT);
+        detail_scrolled_window.set_size_request(width, height);
     }
     
     if let Some(entry) = get_current_entry(list_box, app_state) {
@@ -85,8 +86,8 @@ pub fn show_big_detail(
             detail_container.remove(&child);
         }
         let detail_widget = entry.create_more_info_widget(
-            INFO_BOX_BIG_WIDTH,
-            INFO_BOX_BIG_HEIGHT,
+            width,
+            height,
             app_state.search_query.borrow().clone()
         );
         detail_container.add(&detail_widget);
@@ -127,4 +128,24 @@ pub fn toggle_detail(
     }
     
     Inhibit(true)
-}
\ No newline at end of file
+}
+
+/// Runs an image mode key while the big detail view shows an image. Returns whether the image
+/// used it; otherwise the key keeps its normal meaning, so `j` and `k` move through the list
+/// while the image fits the view.
+pub fn handle_image_key(
+    list_box: &ListBox,
+    app_state: &AppState,
+    event: &EventKey,
+) -> bool {
+    if *app_state.details_visibility.borrow() != DetailsVisibility::Big {
+        return false;
+    }
+    let Some(command) = app_state.keymap.action_for(Mode::Image, event).and_then(|action| action.image_command()) else {
+        return false;
+    };
+    match get_current_entry(list_box, app_state) {
+        Some(entry) => entry.handle_image_command(command),
+        None => false,
+    }
+}
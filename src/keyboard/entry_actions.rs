@@ -1,6 +1,7 @@
 use gtk::{prelude::*, ApplicationWindow, Inhibit, ListBox};
+use std::sync::Arc;
 
-use clipboard_manager::clipboard_entries::clipboard_entry::ClipboardEntry;
+use clipboard_manager::{clipboard_entries::clipboard_entry::ClipboardEntry, session_files::remove_session_files};
 
 use crate::ui::AppState;
 
@@ -26,21 +27,111 @@ pub fn handle_copy_and_close(
 ) -> Inhibit {
     if let Some(entry) = get_current_entry(list_box, app_state) {
         if let Err(e) = entry.copy_to_clipboard(copy_path) {
-            eprintln!("Error copying to clipboard: {}", e);
+            app_state.status_bar.show_clipboard_error("Error copying to clipboard", &e, None);
+            return Inhibit(true);
+        }
+        if entry.is_sensitive() {
+            app_state.expiry.schedule(window.application().as_ref(), entry.uuid(), entry.secret_text());
         }
     }
     window.close();
     Inhibit(true)
 }
 
+pub fn handle_open_in_editor(
+    list_box: &ListBox,
+    app_state: &AppState,
+) -> Inhibit {
+    let Some(editor_command) = app_state.config.editor.as_deref() else {
+        return handle_open_in_external_app(list_box, app_state);
+    };
+
+    if let Some(entry) = get_current_entry(list_box, app_state) {
+        if let Err(e) = entry.open_in_editor(editor_command) {
+            app_state.status_bar.show_clipboard_error("Error opening in editor", &e, None);
+        }
+    }
+    Inhibit(true)
+}
+
 pub fn handle_open_in_external_app(
     list_box: &ListBox,
     app_state: &AppState,
 ) -> Inhibit {
     if let Some(entry) = get_current_entry(list_box, app_state) {
         if let Err(e) = entry.open_in_external_app() {
-            eprintln!("Error opening in external app: {}", e);
+            app_state.status_bar.show_clipboard_error("Error opening in external app", &e, None);
+        }
+    }
+    Inhibit(true)
+}
+
+/// Reveals the selected secret or masks it again, showing the change in the detail view too.
+pub fn handle_toggle_revealed(
+    list_box: &ListBox,
+    app_state: &AppState,
+) -> Inhibit {
+    let toggled = match get_current_entry(list_box, app_state) {
+        Some(entry) if entry.is_sensitive() => {
+            entry.toggle_revealed();
+            true
+        }
+        _ => false,
+    };
+
+    // Selecting the row again rebuilds the detail view.
+    if let Some(row) = list_box.selected_row().filter(|_| toggled) {
+        list_box.unselect_all();
+        list_box.select_row(Some(&row));
+    }
+    Inhibit(true)
+}
+
+/// Deletes the selected entry from the history and selects the one that took its place.
+pub fn handle_delete(
+    list_box: &ListBox,
+    app_state: &AppState,
+) -> Inhibit {
+    let Some(row) = list_box.selected_row() else {
+        return Inhibit(true);
+    };
+    let Some(uuid) = get_current_entry(list_box, app_state).map(|entry| entry.uuid().to_string()) else {
+        return Inhibit(true);
+    };
+
+    if let Err(e) = app_state.backend.delete(&uuid) {
+        app_state.status_bar.show_clipboard_error("Error deleting entry", &e, None);
+        return Inhibit(true);
+    }
+    remove_session_files(&uuid);
+
+    let index = row.index();
+    list_box.remove(&row);
+    app_state.rows.borrow_mut().retain(|r| r != &row);
+    app_state.row_to_entry_map.borrow_mut().remove(&row);
+    if let Some(filtered_rows) = app_state.filtered_rows.borrow_mut().as_mut() {
+        filtered_rows.retain(|r| r != &row);
+    }
+    app_state.search_cache.borrow_mut().clear();
+    if app_state.last_selected_row.borrow().as_ref() == Some(&row) {
+        app_state.last_selected_row.replace(None);
+    }
+    // Items after the deleted one move up by one in the snapshot pages are loaded from.
+    if let Some(snapshot) = app_state.history_snapshot.borrow_mut().as_mut() {
+        if let Some(index) = snapshot.iter().position(|item| item.uuid == uuid) {
+            Arc::make_mut(snapshot).remove(index);
+            if let Some(offset) = app_state.next_offset.borrow_mut().as_mut() {
+                if index < *offset {
+                    *offset -= 1;
+                }
+            }
         }
     }
+
+    let next_row = list_box.row_at_index(index).or_else(|| list_box.row_at_index(index - 1));
+    if let Some(next_row) = next_row {
+        list_box.select_row(Some(&next_row));
+        next_row.grab_focus();
+    }
     Inhibit(true)
-}
\ No newline at end of file
+}
@@ -1,68 +1,358 @@
-use gtk::{prelude::*, ApplicationWindow, ListBox};
+use gtk::{
+    glib::{self, Continue},
+    prelude::*,
+    Adjustment, ListBox, ListBoxRow, ScrolledWindow,
+};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    time::Duration,
+};
 
 use crate::{
-    constants::*,
-    get_clipboard_entries::get_clipboard_entries,
-    ui::{append_to_list_view, show_error, AppState},
+    get_clipboard_entries::{
+        create_clipboard_entry, load_clipboard_entries, sync_clipboard_entries, EntryData, HistorySync, LoadMessage,
+    },
+    ui::{append_to_list_view, AppState, RetryAction},
 };
 
-pub fn load_all_entries_if_reached_end(
+/// Builds entries for a batch delivered by the loader and appends their rows. While a search
+/// is active, only rows matching it are shown.
+fn append_loaded_entries(list_box: &ListBox, app_state: &AppState, batch: Vec<EntryData>) {
+    let entries = batch
+        .into_iter()
+        .map(|data| {
+            create_clipboard_entry(
+                data,
+                &app_state.config,
+                app_state.thumbnail_cache.as_ref(),
+                &app_state.encrypted_history,
+            )
+        })
+        .collect();
+
+    let (rows, row_to_entry_map) = append_to_list_view(list_box, entries);
+
+    if let Some(search_query) = app_state.search_query.borrow().clone() {
+        let lowercase_query = search_query.to_lowercase();
+        let mut filtered_rows = app_state.filtered_rows.borrow_mut();
+        for row in &rows {
+            match row_to_entry_map.get(row) {
+                Some(entry) if entry.contains_text(&lowercase_query) => {
+                    entry.set_highlight_in_row(Some(search_query.clone()));
+                    filtered_rows.get_or_insert_with(Vec::new).push(row.clone());
+                }
+                _ => list_box.remove(row),
+            }
+        }
+        app_state.search_cache.borrow_mut().clear();
+    }
+
+    app_state.rows.borrow_mut().extend(rows);
+    app_state.row_to_entry_map.borrow_mut().extend(row_to_entry_map);
+
+    list_box.show_all();
+}
+
+/// Loads up to `limit` history items from the next offset in the background, appending them
+/// as they arrive; `None` loads the rest of the history. `retry` is offered if the load fails.
+fn start_loading(
     list_box: &ListBox,
-    app_state: &AppState,
-    window: &ApplicationWindow,
+    app_state: &Rc<AppState>,
+    limit: Option<usize>,
+    retry: RetryAction,
 ) {
-    if *app_state.all_entries_loaded.borrow() {
+    if *app_state.entries_loading.borrow() {
         return;
     }
 
+    let Some(offset) = *app_state.next_offset.borrow() else {
+        return;
+    };
+
+    let remaining = app_state.config.history.max_entries.map(|max| max.saturating_sub(offset));
+    let limit = match (limit, remaining) {
+        (_, Some(0)) => {
+            app_state.next_offset.replace(None);
+            return;
+        }
+        (Some(limit), Some(remaining)) => Some(limit.min(remaining)),
+        (limit, remaining) => limit.or(remaining),
+    };
+
+    app_state.entries_loading.replace(true);
+    app_state.loading_indicator.start("Loading clipboard history…");
+
+    let snapshot = app_state.history_snapshot.borrow().clone();
+    let receiver = load_clipboard_entries(app_state.backend.clone(), snapshot, offset, limit);
+    let list_box = list_box.clone();
+    let app_state = Rc::downgrade(app_state);
+
+    receiver.attach(None, move |message| {
+        let Some(app_state) = app_state.upgrade() else {
+            return Continue(false);
+        };
+
+        let finished = match message {
+            LoadMessage::Snapshot(snapshot) => {
+                app_state.history_snapshot.replace(Some(snapshot));
+                return Continue(true);
+            }
+            LoadMessage::Batch { entries, failures } => {
+                app_state.status_bar.add_entry_failures(failures);
+                append_loaded_entries(&list_box, &app_state, entries);
+                return Continue(true);
+            }
+            LoadMessage::Finished { next_offset } => {
+                app_state.next_offset.replace(next_offset);
+                if app_state.rows.borrow().is_empty() && next_offset.is_none() {
+                    app_state.status_bar.show_info("No clipboard entries available.");
+                }
+                true
+            }
+            LoadMessage::Failed(e) => {
+                app_state.status_bar.show_clipboard_error(
+                    "Error fetching clipboard entries",
+                    &e,
+                    Some(retry),
+                );
+                false
+            }
+        };
+
+        app_state.entries_loading.replace(false);
+        app_state.loading_indicator.stop();
+
+        if app_state.sync_requested.replace(false) {
+            sync_entries(&list_box, &app_state);
+        } else if app_state.load_all_requested.replace(false) && finished {
+            load_all_entries(&list_box, &app_state);
+        } else if finished && app_state.rows.borrow().is_empty() {
+            // Every item of the page was empty or failed; keep going until something shows.
+            load_next_page(&list_box, &app_state);
+        }
+        Continue(false)
+    });
+}
+
+pub fn load_next_page(list_box: &ListBox, app_state: &Rc<AppState>) {
+    let page_size = app_state.config.history.page_size;
+    start_loading(list_box, app_state, Some(page_size), RetryAction::LoadNextPage);
+}
+
+pub fn load_next_page_if_reached_end(
+    list_box: &ListBox,
+    app_state: &Rc<AppState>,
+) {
     let Some(selected_row) = list_box.selected_row() else {
         return;
     };
 
-    let index = selected_row.index();
+    if selected_row.index() != list_box.children().len() as i32 - 1 {
+        return;
+    }
+
+    load_next_page(list_box, app_state);
+}
+
+/// Loads the next page when the list is scrolled to within a page of its end, or when the
+/// loaded rows do not fill the viewport yet.
+pub fn setup_scroll_paging(
+    scrolled_window: &ScrolledWindow,
+    list_box: &ListBox,
+    app_state: &Rc<AppState>,
+) {
+    let list_box = list_box.clone();
+    let app_state = Rc::downgrade(app_state);
+    let load_if_near_end = move |adjustment: &Adjustment| {
+        let Some(app_state) = app_state.upgrade() else {
+            return;
+        };
+        let distance_to_end = adjustment.upper() - (adjustment.value() + adjustment.page_size());
+        if adjustment.page_size() > 0.0 && distance_to_end <= adjustment.page_size() {
+            load_next_page(&list_box, &app_state);
+        }
+    };
+    let load_if_near_end = Rc::new(load_if_near_end);
+
+    let adjustment = scrolled_window.vadjustment();
+    let load_on_scroll = load_if_near_end.clone();
+    adjustment.connect_value_changed(move |adjustment| load_on_scroll(adjustment));
+    adjustment.connect_changed(move |adjustment| load_if_near_end(adjustment));
+}
+
+/// Loads the whole remaining history, e.g. so that search covers all of it. A load already in
+/// progress is finished first.
+pub fn load_all_entries(list_box: &ListBox, app_state: &Rc<AppState>) {
+    if app_state.next_offset.borrow().is_none() {
+        return;
+    }
 
-    if index != list_box.children().len() as i32 - 1 {
+    if *app_state.entries_loading.borrow() {
+        app_state.load_all_requested.replace(true);
         return;
     }
 
-    load_all_entries(list_box, app_state, window);
+    start_loading(list_box, app_state, None, RetryAction::LoadAllEntries);
+}
 
-    if let Some(row) = list_box.row_at_index(index) {
+/// Shows the loaded rows in history order, keeping only matches while a search is active.
+fn refill_list(list_box: &ListBox, app_state: &AppState) {
+    let selected_row = list_box.selected_row();
+    for row in list_box.children() {
+        list_box.remove(&row);
+    }
+
+    let search_query = app_state.search_query.borrow().clone();
+    let row_map = app_state.row_to_entry_map.borrow();
+    let mut filtered_rows = Vec::new();
+
+    for row in app_state.rows.borrow().iter() {
+        if let Some(search_query) = &search_query {
+            match row_map.get(row) {
+                Some(entry) if entry.contains_text(&search_query.to_lowercase()) => {
+                    entry.set_highlight_in_row(Some(search_query.clone()));
+                    filtered_rows.push(row.clone());
+                }
+                _ => continue,
+            }
+        }
+        list_box.add(row);
+    }
+
+    if search_query.is_some() {
+        *app_state.filtered_rows.borrow_mut() = (!filtered_rows.is_empty()).then_some(filtered_rows);
+    }
+    app_state.search_cache.borrow_mut().clear();
+
+    let row_to_select = selected_row
+        .filter(|row| row.parent().is_some())
+        .or_else(|| list_box.row_at_index(0));
+    if let Some(row) = row_to_select {
         list_box.select_row(Some(&row));
-        row.grab_focus();
     }
+    list_box.show_all();
+}
+
+fn apply_history_sync(list_box: &ListBox, app_state: &AppState, sync: HistorySync) {
+    app_state.status_bar.add_entry_failures(sync.failures);
+
+    {
+        let mut row_map = app_state.row_to_entry_map.borrow_mut();
+        let mut rows_by_uuid: HashMap<String, ListBoxRow> = row_map
+            .iter()
+            .map(|(row, entry)| (entry.uuid().to_string(), row.clone()))
+            .collect();
+
+        for data in sync.new_entries {
+            let entry = create_clipboard_entry(
+                data,
+                &app_state.config,
+                app_state.thumbnail_cache.as_ref(),
+                &app_state.encrypted_history,
+            );
+            let row = entry.get_row();
+            rows_by_uuid.insert(entry.uuid().to_string(), row.clone());
+            row_map.insert(row, entry);
+        }
+
+        let rows = sync
+            .order
+            .iter()
+            .filter_map(|uuid| rows_by_uuid.remove(uuid))
+            .collect();
+
+        // Whatever is left was removed from the history.
+        for row in rows_by_uuid.into_values() {
+            row_map.remove(&row);
+        }
+
+        *app_state.rows.borrow_mut() = rows;
+    }
+
+    app_state.history_snapshot.replace(Some(sync.snapshot));
+    app_state.next_offset.replace(sync.next_offset);
+    refill_list(list_box, app_state);
 }
 
-pub fn load_all_entries(list_box: &ListBox, app_state: &AppState, window: &ApplicationWindow) {
-    if *app_state.all_entries_loaded.borrow() {
+/// Brings the loaded entries in line with the backend's history: new items are added in
+/// place, removed ones dropped and the order updated, without reloading existing entries.
+pub fn sync_entries(list_box: &ListBox, app_state: &Rc<AppState>) {
+    if app_state.entries_loading.replace(true) {
+        app_state.sync_requested.replace(true);
         return;
     }
 
-    let entries = match get_clipboard_entries(
-        MAX_ENTRIES,
-        ENTRIES_WIDTH,
-        ROW_IMAGE_MAX_HEIGHT,
-        ROW_TEXT_MAX_LINES,
-    ) {
-        Ok(entries) if !entries.is_empty() => entries,
-        Ok(_) => {
-            show_error(window, "No more clipboard entries available.");
-            return;
+    let known_uuids: HashSet<String> = app_state
+        .row_to_entry_map
+        .borrow()
+        .values()
+        .map(|entry| entry.uuid().to_string())
+        .collect();
+    let all_loaded = app_state.next_offset.borrow().is_none();
+
+    let receiver = sync_clipboard_entries(
+        app_state.backend.clone(),
+        known_uuids,
+        all_loaded,
+        app_state.config.history.page_size,
+    );
+    let list_box = list_box.clone();
+    let app_state = Rc::downgrade(app_state);
+
+    receiver.attach(None, move |result| {
+        let Some(app_state) = app_state.upgrade() else {
+            return Continue(false);
+        };
+
+        app_state.entries_loading.replace(false);
+        match result {
+            Ok(sync) => apply_history_sync(&list_box, &app_state, sync),
+            Err(e) => eprintln!("Error updating clipboard entries: {}", e),
         }
-        Err(e) => {
-            show_error(window, &format!("Error fetching clipboard entries: {}", e));
-            return;
+
+        if app_state.sync_requested.replace(false) {
+            sync_entries(&list_box, &app_state);
+        } else if app_state.load_all_requested.replace(false) {
+            load_all_entries(&list_box, &app_state);
         }
-    };
+        Continue(false)
+    });
+}
 
-    let new_entries = entries.into_iter().skip(INITIAL_ENTRIES).collect();
+/// Keeps the list in sync with the backend while the app runs. Bursts of change
+/// notifications are coalesced into one update.
+pub fn watch_history_changes(list_box: &ListBox, app_state: &Rc<AppState>) {
+    const SYNC_DELAY: Duration = Duration::from_millis(200);
 
-    let (rows, row_to_entry_map) = append_to_list_view(list_box, new_entries);
+    let sync_scheduled = Rc::new(Cell::new(false));
+    let list_box = list_box.clone();
+    let weak_app_state = Rc::downgrade(app_state);
 
-    app_state.rows.borrow_mut().extend(rows);
-    app_state.row_to_entry_map.borrow_mut().extend(row_to_entry_map);
+    let watch = app_state.backend.watch_changes(Box::new(move || {
+        if sync_scheduled.replace(true) {
+            return;
+        }
+        let list_box = list_box.clone();
+        let weak_app_state = weak_app_state.clone();
+        let sync_scheduled = sync_scheduled.clone();
+        glib::timeout_add_local_once(SYNC_DELAY, move || {
+            sync_scheduled.set(false);
+            if let Some(app_state) = weak_app_state.upgrade() {
+                sync_entries(&list_box, &app_state);
+            }
+        });
+    }));
 
-    app_state.all_entries_loaded.replace(true);
+    app_state.change_watch.replace(watch);
+}
 
-    list_box.show_all();
-}
\ No newline at end of file
+/// Re-runs the load that last failed, if any.
+pub fn retry_failed_load(list_box: &ListBox, app_state: &Rc<AppState>) {
+    match app_state.status_bar.take_retry() {
+        Some(RetryAction::LoadNextPage) => load_next_page(list_box, app_state),
+        Some(RetryAction::LoadAllEntries) => load_all_entries(list_box, app_state),
+        None => {}
+    }
+}
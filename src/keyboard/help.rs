@@ -1,6 +1,8 @@
-use gtk::{prelude::*, Align, ApplicationWindow, Box, Grid, Label, Orientation, Window};
+use gtk::{glib::markup_escape_text, prelude::*, Align, ApplicationWindow, Box, Grid, Label, Orientation, Window};
 
-pub fn show_help_window(window: &ApplicationWindow) {
+use super::keymap::{Keymap, Mode};
+
+pub fn show_help_window(window: &ApplicationWindow, keymap: &Keymap) {
     let help_window = Window::builder()
         .transient_for(window)
         .modal(true)
@@ -17,45 +19,26 @@ pub fn show_help_window(window: &ApplicationWindow) {
     let title = Label::new(Some("Keyboard Shortcuts"));
     title.set_margin_bottom(12);
     
-    let normal_title = Label::new(Some("NORMAL MODE"));
-    normal_title.style_context().add_class("dim-label");
-    normal_title.set_halign(Align::Start);
-    normal_title.set_margin_top(8);
-    
-    let normal_shortcuts = create_shortcuts_box(vec![
-        ("j / k", "Navigate up/down"),
-        ("Enter / y", "Copy and exit"),
-        ("p", "Copy file path and exit"),
-        ("o", "Open in external app"),
-        ("e", "Open in editor"),
-        ("i", "Show more/less info"),
-        ("Ctrl+s", "Enter search mode"),
-        ("Ctrl+c / Esc / q", "Exit app"),
-    ]);
-    
-    let search_title = Label::new(Some("SEARCH MODE"));
-    search_title.style_context().add_class("dim-label");
-    search_title.set_halign(Align::Start);
-    search_title.set_margin_top(16);
-    
-    let search_shortcuts = create_shortcuts_box(vec![
-        ("Ctrl+j / Ctrl+k", "Navigate up/down"),
-        ("Enter / Ctrl+y", "Copy and exit"),
-        ("Ctrl+p", "Copy file path and exit"),
-        ("Ctrl+o", "Open in external app"),
-        ("Ctrl+e", "Open in editor"),
-        ("Ctrl+i", "Show more/less info"),
-        ("Ctrl+c / Esc", "Exit search mode"),
-    ]);
-    
+    main_box.add(&title);
+
+    for mode in Keymap::modes() {
+        let mode_title = Label::new(Some(mode.title()));
+        mode_title.style_context().add_class("dim-label");
+        mode_title.set_halign(Align::Start);
+        mode_title.set_margin_top(if mode == Mode::Normal { 8 } else { 16 });
+
+        let shortcuts: Vec<(String, &str)> = keymap
+            .bindings(mode)
+            .into_iter()
+            .map(|(action, keys)| (keys.join(" / "), action.description()))
+            .collect();
+
+        main_box.add(&mode_title);
+        main_box.add(&create_shortcuts_box(shortcuts));
+    }
+
     let close_label = Label::new(Some("Press Escape, Ctrl+c, or q to close"));
     close_label.set_margin_top(16);
-    
-    main_box.add(&title);
-    main_box.add(&normal_title);
-    main_box.add(&normal_shortcuts);
-    main_box.add(&search_title);
-    main_box.add(&search_shortcuts);
     main_box.add(&close_label);
     
     main_box.show_all();
@@ -81,7 +64,7 @@ pub fn show_help_window(window: &ApplicationWindow) {
 }
 
 
-fn create_shortcuts_box(shortcuts: Vec<(&str, &str)>) -> Box {
+fn create_shortcuts_box(shortcuts: Vec<(String, &str)>) -> Box {
     let shortcuts_box = Box::new(Orientation::Vertical, 4);
     
     let grid = Grid::new();
@@ -89,8 +72,8 @@ fn create_shortcuts_box(shortcuts: Vec<(&str, &str)>) -> Box {
     grid.set_row_spacing(4);
     
     for (row, (key, description)) in shortcuts.iter().enumerate() {
-        let key_label = Label::new(Some(key));
-        key_label.set_markup(&format!("<tt>{}</tt>", key));
+        let key_label = Label::new(None);
+        key_label.set_markup(&format!("<tt>{}</tt>", markup_escape_text(key)));
         key_label.set_xalign(0.0);
         key_label.set_size_request(120, -1);
         
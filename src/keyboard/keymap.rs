@@ -0,0 +1,451 @@
+use gtk::gdk::{keys::Key, EventKey, ModifierType};
+use std::{fs, path::PathBuf};
+
+use clipboard_manager::clipboard_entries::image_view::ImageCommand;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Search,
+    /// An image in the big detail view. Its keys take precedence over those of the normal
+    /// mode when the image uses them.
+    Image,
+}
+
+impl Mode {
+    const ALL: [Mode; 3] = [Mode::Normal, Mode::Search, Mode::Image];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Search => "search",
+            Mode::Image => "image",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Mode::Normal => "NORMAL MODE",
+            Mode::Search => "SEARCH MODE",
+            Mode::Image => "IMAGE DETAIL VIEW",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveDown,
+    MoveUp,
+    Copy,
+    CopyPath,
+    OpenExternal,
+    OpenEditor,
+    ToggleDetail,
+    Search,
+    Help,
+    Quit,
+    ExitSearch,
+    FocusList,
+    Retry,
+    Reveal,
+    Delete,
+    ZoomIn,
+    ZoomOut,
+    ZoomFit,
+    ZoomOriginal,
+    PanLeft,
+    PanDown,
+    PanUp,
+    PanRight,
+}
+
+impl Action {
+    const ALL: [Action; 23] = [
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::Copy,
+        Action::CopyPath,
+        Action::OpenExternal,
+        Action::OpenEditor,
+        Action::ToggleDetail,
+        Action::Search,
+        Action::Help,
+        Action::Quit,
+        Action::ExitSearch,
+        Action::FocusList,
+        Action::Retry,
+        Action::Reveal,
+        Action::Delete,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomFit,
+        Action::ZoomOriginal,
+        Action::PanLeft,
+        Action::PanDown,
+        Action::PanUp,
+        Action::PanRight,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveDown => "move-down",
+            Action::MoveUp => "move-up",
+            Action::Copy => "copy",
+            Action::CopyPath => "copy-path",
+            Action::OpenExternal => "open-external",
+            Action::OpenEditor => "open-editor",
+            Action::ToggleDetail => "toggle-detail",
+            Action::Search => "search",
+            Action::Help => "help",
+            Action::Quit => "quit",
+            Action::ExitSearch => "exit-search",
+            Action::FocusList => "focus-list",
+            Action::Retry => "retry",
+            Action::Reveal => "reveal",
+            Action::Delete => "delete",
+            Action::ZoomIn => "zoom-in",
+            Action::ZoomOut => "zoom-out",
+            Action::ZoomFit => "zoom-fit",
+            Action::ZoomOriginal => "zoom-original",
+            Action::PanLeft => "pan-left",
+            Action::PanDown => "pan-down",
+            Action::PanUp => "pan-up",
+            Action::PanRight => "pan-right",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::MoveDown => "Navigate down",
+            Action::MoveUp => "Navigate up",
+            Action::Copy => "Copy and exit",
+            Action::CopyPath => "Copy file path and exit",
+            Action::OpenExternal => "Open in external app",
+            Action::OpenEditor => "Open in editor",
+            Action::ToggleDetail => "Show more/less info",
+            Action::Search => "Enter search mode",
+            Action::Help => "Show this help",
+            Action::Quit => "Exit app",
+            Action::ExitSearch => "Clear search and return to list",
+            Action::FocusList => "Return to list",
+            Action::Retry => "Retry failed load",
+            Action::Reveal => "Reveal or hide a secret",
+            Action::Delete => "Delete from history",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ZoomFit => "Fit image to view",
+            Action::ZoomOriginal => "Show image at 1:1",
+            Action::PanLeft => "Scroll image left",
+            Action::PanDown => "Scroll image down",
+            Action::PanUp => "Scroll image up",
+            Action::PanRight => "Scroll image right",
+        }
+    }
+
+    /// What the action does to an image in the big detail view, for image mode actions.
+    pub fn image_command(&self) -> Option<ImageCommand> {
+        match self {
+            Action::ZoomIn => Some(ImageCommand::ZoomIn),
+            Action::ZoomOut => Some(ImageCommand::ZoomOut),
+            Action::ZoomFit => Some(ImageCommand::ZoomFit),
+            Action::ZoomOriginal => Some(ImageCommand::ZoomOriginal),
+            Action::PanLeft => Some(ImageCommand::Pan(-1, 0)),
+            Action::PanDown => Some(ImageCommand::Pan(0, 1)),
+            Action::PanUp => Some(ImageCommand::Pan(0, -1)),
+            Action::PanRight => Some(ImageCommand::Pan(1, 0)),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    ctrl: bool,
+    key: String,
+}
+
+impl KeyBinding {
+    /// Parses bindings such as `j`, `Return`, `F1` or `Ctrl+p`. Keys use GDK key names, so `+` is `plus`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let (ctrl, key) = match spec.split_once('+') {
+            Some((modifier, key)) if !key.is_empty() => {
+                if !matches!(modifier.to_lowercase().as_str(), "ctrl" | "control") {
+                    return Err(format!("unsupported modifier '{}' in '{}'", modifier, spec));
+                }
+                (true, key)
+            }
+            _ => (false, spec),
+        };
+
+        if key.is_empty() {
+            return Err("empty key binding".to_string());
+        }
+        if Key::from_name(key) == gtk::gdk::keys::constants::VoidSymbol {
+            return Err(format!("unknown key '{}' in '{}', keys use GDK names such as 'Return' or 'plus'", key, spec));
+        }
+
+        Ok(Self { ctrl, key: key.to_string() })
+    }
+
+    fn matches(&self, keyname: &str, ctrl: bool) -> bool {
+        self.ctrl == ctrl && self.key == keyname
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.key.as_str() {
+            "Return" => "Enter",
+            "Escape" => "Esc",
+            key => key,
+        };
+        if self.ctrl {
+            format!("Ctrl+{}", key)
+        } else {
+            key.to_string()
+        }
+    }
+}
+
+pub struct Keymap {
+    bindings: Vec<(Mode, KeyBinding, Action)>,
+}
+
+impl Keymap {
+    const FILE_NAME: &'static str = "keymap.toml";
+
+    const DEFAULT_BINDINGS: &'static [(Mode, &'static str, Action)] = &[
+        (Mode::Normal, "j", Action::MoveDown),
+        (Mode::Normal, "k", Action::MoveUp),
+        (Mode::Normal, "Return", Action::Copy),
+        (Mode::Normal, "y", Action::Copy),
+        (Mode::Normal, "p", Action::CopyPath),
+        (Mode::Normal, "o", Action::OpenExternal),
+        (Mode::Normal, "e", Action::OpenEditor),
+        (Mode::Normal, "i", Action::ToggleDetail),
+        (Mode::Normal, "s", Action::Search),
+        (Mode::Normal, "Ctrl+s", Action::Search),
+        (Mode::Normal, "r", Action::Retry),
+        (Mode::Normal, "v", Action::Reveal),
+        (Mode::Normal, "d", Action::Delete),
+        (Mode::Normal, "Delete", Action::Delete),
+        (Mode::Normal, "F1", Action::Help),
+        (Mode::Normal, "Ctrl+c", Action::Quit),
+        (Mode::Normal, "Escape", Action::Quit),
+        (Mode::Normal, "q", Action::Quit),
+        (Mode::Search, "Ctrl+j", Action::MoveDown),
+        (Mode::Search, "Ctrl+k", Action::MoveUp),
+        (Mode::Search, "Ctrl+y", Action::Copy),
+        (Mode::Search, "Ctrl+p", Action::CopyPath),
+        (Mode::Search, "Ctrl+o", Action::OpenExternal),
+        (Mode::Search, "Ctrl+e", Action::OpenEditor),
+        (Mode::Search, "Return", Action::FocusList),
+        (Mode::Search, "Ctrl+c", Action::ExitSearch),
+        (Mode::Search, "Escape", Action::ExitSearch),
+        (Mode::Image, "plus", Action::ZoomIn),
+        (Mode::Image, "equal", Action::ZoomIn),
+        (Mode::Image, "KP_Add", Action::ZoomIn),
+        (Mode::Image, "minus", Action::ZoomOut),
+        (Mode::Image, "KP_Subtract", Action::ZoomOut),
+        (Mode::Image, "f", Action::ZoomFit),
+        (Mode::Image, "1", Action::ZoomOriginal),
+        (Mode::Image, "h", Action::PanLeft),
+        (Mode::Image, "j", Action::PanDown),
+        (Mode::Image, "k", Action::PanUp),
+        (Mode::Image, "l", Action::PanRight),
+    ];
+
+    pub fn config_path() -> PathBuf {
+        gtk::glib::user_config_dir()
+            .join("clipboard_manager")
+            .join(Self::FILE_NAME)
+    }
+
+    /// Loads the default keymap with user overrides from `keymap.toml` applied on top.
+    /// A missing file is not an error.
+    pub fn load() -> Result<Self, String> {
+        let mut keymap = Self::default();
+        let path = Self::config_path();
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(keymap),
+            Err(err) => return Err(format!("Error reading {}: {}", path.display(), err)),
+        };
+
+        keymap
+            .apply_overrides(&content)
+            .map_err(|err| format!("Invalid keymap in {}: {}", path.display(), err))?;
+
+        Ok(keymap)
+    }
+
+    /// Each `[mode]` table maps an action name to a key or a list of keys, which replace
+    /// the default keys of that action in that mode. An empty list unbinds the action.
+    fn apply_overrides(&mut self, content: &str) -> Result<(), String> {
+        let table: toml::Table = content.parse().map_err(|err: toml::de::Error| err.message().to_string())?;
+
+        for (mode_name, actions) in &table {
+            let mode = Mode::from_name(mode_name)
+                .ok_or_else(|| format!("unknown mode '{}'", mode_name))?;
+            let actions = actions
+                .as_table()
+                .ok_or_else(|| format!("'{}' must be a table of actions", mode_name))?;
+
+            for (action_name, keys) in actions {
+                let action = Action::from_name(action_name)
+                    .ok_or_else(|| format!("unknown action '{}' in [{}]", action_name, mode_name))?;
+
+                let specs: Vec<&str> = match keys {
+                    toml::Value::String(key) => vec![key.as_str()],
+                    toml::Value::Array(keys) => keys
+                        .iter()
+                        .map(|key| key.as_str().ok_or_else(|| format!("keys of '{}' must be strings", action_name)))
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err(format!("keys of '{}' must be a string or a list of strings", action_name)),
+                };
+
+                let bindings = specs
+                    .into_iter()
+                    .map(KeyBinding::parse)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.bindings.retain(|(m, _, a)| !(*m == mode && *a == action));
+                for binding in bindings {
+                    self.bindings.retain(|(m, b, _)| !(*m == mode && *b == binding));
+                    self.bindings.push((mode, binding, action));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn action_for(&self, mode: Mode, event: &EventKey) -> Option<Action> {
+        let keyname = event.keyval().name().unwrap_or_else(|| gtk::glib::GString::from(""));
+        let ctrl = event.state().contains(ModifierType::CONTROL_MASK);
+
+        self.bindings
+            .iter()
+            .find(|(m, binding, _)| *m == mode && binding.matches(&keyname, ctrl))
+            .map(|(_, _, action)| *action)
+    }
+
+    /// Actions bound in `mode` with their key labels, in the order actions are declared.
+    pub fn bindings(&self, mode: Mode) -> Vec<(Action, Vec<String>)> {
+        Action::ALL
+            .into_iter()
+            .filter_map(|action| {
+                let keys: Vec<String> = self.bindings
+                    .iter()
+                    .filter(|(m, _, a)| *m == mode && *a == action)
+                    .map(|(_, binding, _)| binding.label())
+                    .collect();
+                (!keys.is_empty()).then_some((action, keys))
+            })
+            .collect()
+    }
+
+    pub fn modes() -> [Mode; 3] {
+        Mode::ALL
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Self::DEFAULT_BINDINGS
+            .iter()
+            .map(|(mode, spec, action)| {
+                let binding = KeyBinding::parse(spec).expect("default key bindings are valid");
+                (*mode, binding, *action)
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(keymap: &Keymap, mode: Mode, action: Action) -> Vec<String> {
+        keymap
+            .bindings(mode)
+            .into_iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, keys)| keys)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn parses_key_bindings() {
+        assert_eq!(KeyBinding::parse("j"), Ok(KeyBinding { ctrl: false, key: "j".to_string() }));
+        assert_eq!(KeyBinding::parse(" Ctrl+p "), Ok(KeyBinding { ctrl: true, key: "p".to_string() }));
+        assert_eq!(KeyBinding::parse("control+Return"), Ok(KeyBinding { ctrl: true, key: "Return".to_string() }));
+        assert_eq!(KeyBinding::parse("Ctrl+plus"), Ok(KeyBinding { ctrl: true, key: "plus".to_string() }));
+        assert!(KeyBinding::parse("+").is_err());
+        assert!(KeyBinding::parse("Enter").is_err());
+        assert!(KeyBinding::parse("Alt+p").is_err());
+        assert!(KeyBinding::parse("").is_err());
+    }
+
+    #[test]
+    fn labels_key_bindings() {
+        assert_eq!(KeyBinding::parse("Return").unwrap().label(), "Enter");
+        assert_eq!(KeyBinding::parse("Ctrl+Escape").unwrap().label(), "Ctrl+Esc");
+    }
+
+    #[test]
+    fn default_bindings() {
+        let keymap = Keymap::default();
+        assert_eq!(keys(&keymap, Mode::Normal, Action::Search), ["s", "Ctrl+s"]);
+        assert_eq!(keys(&keymap, Mode::Search, Action::CopyPath), ["Ctrl+p"]);
+    }
+
+    #[test]
+    fn overrides_replace_the_keys_of_an_action() {
+        let mut keymap = Keymap::default();
+        keymap.apply_overrides("[normal]\nsearch = \"slash\"\ncopy = [\"Return\", \"c\"]").unwrap();
+        assert_eq!(keys(&keymap, Mode::Normal, Action::Search), ["slash"]);
+        assert_eq!(keys(&keymap, Mode::Normal, Action::Copy), ["Enter", "c"]);
+        // Other modes keep their defaults.
+        assert_eq!(keys(&keymap, Mode::Search, Action::Copy), ["Ctrl+y"]);
+    }
+
+    #[test]
+    fn overrides_take_keys_from_other_actions() {
+        let mut keymap = Keymap::default();
+        keymap.apply_overrides("[normal]\nquit = \"d\"").unwrap();
+        assert_eq!(keys(&keymap, Mode::Normal, Action::Quit), ["d"]);
+        assert_eq!(keys(&keymap, Mode::Normal, Action::Delete), ["Delete"]);
+    }
+
+    #[test]
+    fn an_empty_list_unbinds_an_action() {
+        let mut keymap = Keymap::default();
+        keymap.apply_overrides("[normal]\nhelp = []").unwrap();
+        assert!(keys(&keymap, Mode::Normal, Action::Help).is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_overrides() {
+        for content in [
+            "[insert]\ncopy = \"y\"",
+            "[normal]\npaste = \"y\"",
+            "[normal]\ncopy = 1",
+            "[normal]\ncopy = [1]",
+            "[normal]\ncopy = \"Alt+y\"",
+            "normal = \"y\"",
+            "[normal",
+        ] {
+            assert!(Keymap::default().apply_overrides(content).is_err(), "{:?} should be rejected", content);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use gtk::{
-    gdk, prelude::*, ApplicationWindow, Box as GTKBox, Inhibit, ListBox, ScrolledWindow,
+    prelude::*, ApplicationWindow, Box as GTKBox, Inhibit, ListBox, ScrolledWindow,
     SearchEntry,
 };
 use std::rc::Rc;
@@ -12,13 +12,17 @@ pub mod entry_actions;
 pub mod entry_loading;
 pub mod search;
 pub mod help;
+pub mod keymap;
 
 use cursor_movement::move_cursor;
-use detail_views::{show_big_detail, toggle_detail};
-use entry_actions::{handle_copy_and_close, handle_open_in_external_app};
-use entry_loading::{load_all_entries, load_all_entries_if_reached_end};
+use detail_views::{handle_image_key, show_big_detail, toggle_detail};
+use entry_actions::{
+    handle_copy_and_close, handle_delete, handle_open_in_editor, handle_open_in_external_app, handle_toggle_revealed,
+};
+use entry_loading::{load_all_entries, load_next_page_if_reached_end, retry_failed_load};
 use search::enter_search_mode;
 use help::show_help_window;
+use keymap::{Action, Mode};
 
 pub fn setup_keyboard_handler(
     window: &ApplicationWindow,
@@ -38,12 +42,19 @@ pub fn setup_keyboard_handler(
     let detail_container_clone = detail_container.clone();
     let search_entry_clone = search_entry.clone();
 
-    let window_for_move = window.clone();
     let list_box_for_move = list_box.clone();
     let app_state_for_move = app_state.clone();
 
     list_box.connect_move_cursor(move |_, _step, _count| {
-        load_all_entries_if_reached_end(&list_box_for_move, &app_state_for_move, &window_for_move);
+        load_next_page_if_reached_end(&list_box_for_move, &app_state_for_move);
+    });
+
+    let list_box_for_retry = list_box.clone();
+    let app_state_for_retry = Rc::downgrade(&app_state);
+    app_state.status_bar.connect_retry(move || {
+        if let Some(app_state) = app_state_for_retry.upgrade() {
+            retry_failed_load(&list_box_for_retry, &app_state);
+        }
     });
 
     let list_box_clone_for_sc = list_box.clone();
@@ -65,22 +76,20 @@ pub fn setup_keyboard_handler(
     });
     
     main_box.connect_key_press_event(move |_, event| {
-        let keyval = event.keyval();
-        let keyname = keyval.name().unwrap_or_else(|| gtk::glib::GString::from(""));
-        let state = event.state();
-
-        if state.contains(gdk::ModifierType::CONTROL_MASK) && keyname == "c" {
-            window_clone.close();
+        if handle_image_key(&list_box_clone, &app_state, event) {
             return Inhibit(true);
         }
+        let Some(action) = app_state.keymap.action_for(Mode::Normal, event) else {
+            return Inhibit(false);
+        };
 
-        match keyname.as_str() {
-            "j" => {
-                load_all_entries_if_reached_end(&list_box_clone, &app_state, &window_clone);
-                return move_cursor(&list_box_clone, 1);
+        match action {
+            Action::MoveDown => {
+                load_next_page_if_reached_end(&list_box_clone, &app_state);
+                move_cursor(&list_box_clone, 1)
             }
-            "k" => move_cursor(&list_box_clone, -1),
-            "i" => toggle_detail(
+            Action::MoveUp => move_cursor(&list_box_clone, -1),
+            Action::ToggleDetail => toggle_detail(
                 &window_clone,
                 &main_box_clone,
                 &detail_scrolled_window_clone,
@@ -88,9 +97,10 @@ pub fn setup_keyboard_handler(
                 &list_box_clone,
                 &app_state
             ),
-            "e" | "o" => handle_open_in_external_app(&list_box_clone, &app_state),
-            "s" => {
-                load_all_entries(&list_box_clone, &app_state, &window_clone);
+            Action::OpenExternal => handle_open_in_external_app(&list_box_clone, &app_state),
+            Action::OpenEditor => handle_open_in_editor(&list_box_clone, &app_state),
+            Action::Search => {
+                load_all_entries(&list_box_clone, &app_state);
                 show_big_detail(
                     &main_box_clone,
                     &detail_scrolled_window_clone,
@@ -110,17 +120,32 @@ pub fn setup_keyboard_handler(
                 );
                 Inhibit(true)
             },
-            "Return" | "y" => handle_copy_and_close(&window_clone, &list_box_clone, &app_state, false),
-            "p" => handle_copy_and_close(&window_clone, &list_box_clone, &app_state, true),
-            "F1" => {
-                show_help_window(&window_clone);
+            Action::Copy => handle_copy_and_close(&window_clone, &list_box_clone, &app_state, false),
+            Action::CopyPath => handle_copy_and_close(&window_clone, &list_box_clone, &app_state, true),
+            Action::Help => {
+                show_help_window(&window_clone, &app_state.keymap);
+                Inhibit(true)
+            }
+            Action::Retry => {
+                retry_failed_load(&list_box_clone, &app_state);
                 Inhibit(true)
             }
-            "Escape" | "q" => {
+            Action::Reveal => handle_toggle_revealed(&list_box_clone, &app_state),
+            Action::Delete => handle_delete(&list_box_clone, &app_state),
+            Action::Quit => {
                 window_clone.close();
                 Inhibit(true)
             }
-            _ => Inhibit(false)
+            Action::ExitSearch
+            | Action::FocusList
+            | Action::ZoomIn
+            | Action::ZoomOut
+            | Action::ZoomFit
+            | Action::ZoomOriginal
+            | Action::PanLeft
+            | Action::PanDown
+            | Action::PanUp
+            | Action::PanRight => Inhibit(false),
         }
     });
 }
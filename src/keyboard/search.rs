@@ -1,11 +1,16 @@
-use gtk::{gdk, prelude::*, ApplicationWindow, Inhibit, ListBox, ListBoxRow, SearchEntry};
+use gtk::{prelude::*, ApplicationWindow, Inhibit, ListBox, ListBoxRow, SearchEntry};
 use std::rc::Rc;
 
 use crate::ui::AppState;
 
 use super::{
     cursor_movement::{navigate_and_refocus, select_first_row},
-    entry_actions::{handle_copy_and_close, handle_open_in_external_app},
+    entry_actions::{
+        handle_copy_and_close, handle_delete, handle_open_in_editor, handle_open_in_external_app,
+        handle_toggle_revealed,
+    },
+    help::show_help_window,
+    keymap::{Action, Mode},
 };
 
 fn set_search_entry_appearance(search_entry: &SearchEntry, error: bool) {
@@ -46,33 +51,54 @@ pub fn enter_search_mode(
     });
 
     search_entry.connect_key_press_event(move |_, event| {
-        let keyval = event.keyval();
-        let keyname = keyval.name().unwrap_or_else(|| gtk::glib::GString::from(""));
-        let state = event.state();
-        let ctrl = state.contains(gdk::ModifierType::CONTROL_MASK);
-
-        if keyname == "Escape" || (ctrl && keyname == "c") {
-            search_entry_clone.set_text("");
-            select_first_row(&list_box_clone, true);
-            return Inhibit(true);
-        } else if keyname == "Return" {
-            select_first_row(&list_box_clone, true);
-            return Inhibit(true);
-        } else if ctrl && keyname == "y" {
-            return handle_copy_and_close(&window_clone, &list_box_clone, &app_state_clone, false);
-        } else if ctrl && keyname == "p" {
-            return handle_copy_and_close(&window_clone, &list_box_clone, &app_state_clone, false);
-        } else if ctrl && keyname == "j" {
-            navigate_and_refocus(&list_box_clone, &search_entry_clone, 1);
-            return Inhibit(true);
-        } else if ctrl && keyname == "k" {
-            navigate_and_refocus(&list_box_clone, &search_entry_clone, -1);
-            return Inhibit(true);
-        } else if ctrl && (keyname == "e" || keyname == "o") {
-            return handle_open_in_external_app(&list_box_clone, &app_state_clone);
-        }
+        let Some(action) = app_state_clone.keymap.action_for(Mode::Search, event) else {
+            return Inhibit(false);
+        };
 
-        return Inhibit(false);
+        match action {
+            Action::ExitSearch => {
+                search_entry_clone.set_text("");
+                select_first_row(&list_box_clone, true);
+                Inhibit(true)
+            }
+            Action::FocusList => {
+                select_first_row(&list_box_clone, true);
+                Inhibit(true)
+            }
+            Action::Copy => handle_copy_and_close(&window_clone, &list_box_clone, &app_state_clone, false),
+            Action::CopyPath => handle_copy_and_close(&window_clone, &list_box_clone, &app_state_clone, true),
+            Action::MoveDown => {
+                navigate_and_refocus(&list_box_clone, &search_entry_clone, 1);
+                Inhibit(true)
+            }
+            Action::MoveUp => {
+                navigate_and_refocus(&list_box_clone, &search_entry_clone, -1);
+                Inhibit(true)
+            }
+            Action::OpenExternal => handle_open_in_external_app(&list_box_clone, &app_state_clone),
+            Action::OpenEditor => handle_open_in_editor(&list_box_clone, &app_state_clone),
+            Action::Reveal => handle_toggle_revealed(&list_box_clone, &app_state_clone),
+            Action::Delete => handle_delete(&list_box_clone, &app_state_clone),
+            Action::Help => {
+                show_help_window(&window_clone, &app_state_clone.keymap);
+                Inhibit(true)
+            }
+            Action::Quit => {
+                window_clone.close();
+                Inhibit(true)
+            }
+            Action::Search
+            | Action::ToggleDetail
+            | Action::Retry
+            | Action::ZoomIn
+            | Action::ZoomOut
+            | Action::ZoomFit
+            | Action::ZoomOriginal
+            | Action::PanLeft
+            | Action::PanDown
+            | Action::PanUp
+            | Action::PanRight => Inhibit(false),
+        }
     });
 }
 
@@ -1,73 +1,234 @@
+use gtk::{gio, glib, prelude::*};
 use std::{
-    io::{self, Write},
-    process::Command,
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    process::{Command, Output, Stdio},
+    thread,
 };
-use tempfile::NamedTempFile;
 
+pub mod backend;
 pub mod clipboard_entries;
+pub mod color_value;
+pub mod directory_listing;
+pub mod encrypted_history;
+pub mod encryption;
+pub mod error;
+pub mod file_info;
+pub mod file_preview;
+pub mod image_loader;
+pub mod secret_detection;
+pub mod session_files;
+pub mod source_app;
+pub mod structured_data;
+pub mod syntax_highlight;
+pub mod thumbnail_cache;
 
-pub fn copy_text_to_clipboard(text: &str) -> Result<(), io::Error> {
-    let mut child = Command::new("gpaste-client")
+pub use error::ClipboardError;
+
+const GPASTE_CLIENT: &str = "gpaste-client";
+
+fn spawn_error(program: &str, err: io::Error) -> ClipboardError {
+    if err.kind() == io::ErrorKind::NotFound {
+        ClipboardError::BackendMissing { program: program.to_string() }
+    } else {
+        ClipboardError::Io(err)
+    }
+}
+
+fn check_gpaste_output(output: Output, uuid: Option<&str>) -> Result<Output, ClipboardError> {
+    if output.status.success() {
+        return Ok(output);
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let stderr_lower = stderr.to_lowercase();
+
+    if let Some(uuid) = uuid {
+        if stderr_lower.contains("uuid") || stderr_lower.contains("not found") || stderr_lower.contains("no such") {
+            return Err(ClipboardError::EntryNotFound { uuid: uuid.to_string() });
+        }
+    }
+
+    Err(ClipboardError::BackendFailed { status: output.status, stderr })
+}
+
+/// Runs `gpaste-client` with `args` and returns its trimmed stdout. `uuid` names the entry the
+/// command refers to, so failures caused by a deleted entry can be reported as such.
+pub fn execute_gpaste_command(args: &[&str], uuid: Option<&str>) -> Result<String, ClipboardError> {
+    let output = Command::new(GPASTE_CLIENT)
+        .args(args)
+        .output()
+        .map_err(|e| spawn_error(GPASTE_CLIENT, e))?;
+
+    let output = check_gpaste_output(output, uuid)?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub fn copy_text_to_clipboard(text: &str) -> Result<(), ClipboardError> {
+    let mut child = Command::new(GPASTE_CLIENT)
         .arg("add")
-        .stdin(std::process::Stdio::piped())
-        .spawn()?;
-    
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| spawn_error(GPASTE_CLIENT, e))?;
+
     if let Some(stdin) = child.stdin.as_mut() {
         stdin.write_all(text.as_bytes())?;
     } else {
-        return Err(io::Error::new(io::ErrorKind::Other, "Failed to open stdin"));
+        return Err(io::Error::other("Failed to open stdin").into());
     }
-    
+
     let output = child.wait_with_output()?;
-    
-    if !output.status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("gpaste-client command failed with status: {}", output.status)
-        ));
-    }
-    
+    check_gpaste_output(output, None)?;
+
     Ok(())
 }
 
-pub fn copy_to_clipboard_by_gpaste_uuid(uuid: &str) -> Result<(), io::Error> {
-    let output = Command::new("gpaste-client")
-        .args(&["select", uuid])
-        .output()?;    
+/// Copies the text of the file at `file_path`, as opposed to the file itself. Files larger than
+/// `max_bytes` are refused rather than read whole.
+pub fn copy_file_contents_to_clipboard(file_path: &str, max_bytes: u64) -> Result<(), ClipboardError> {
+    let file = fs::File::open(file_path)?;
+    let metadata = file.metadata()?;
+    if !metadata.is_file() {
+        return Err(ClipboardError::NotText { path: file_path.to_string() });
+    }
+    if metadata.len() > max_bytes {
+        return Err(ClipboardError::TooLarge { path: file_path.to_string(), size: metadata.len(), limit: max_bytes });
+    }
+
+    // The file may grow after the size check; one byte past the limit is enough to notice.
+    let mut contents = Vec::new();
+    file.take(max_bytes + 1).read_to_end(&mut contents)?;
+    if contents.len() as u64 > max_bytes {
+        return Err(ClipboardError::TooLarge {
+            path: file_path.to_string(),
+            size: contents.len() as u64,
+            limit: max_bytes,
+        });
+    }
+
+    let text = String::from_utf8(contents).map_err(|_| ClipboardError::NotText { path: file_path.to_string() })?;
+    copy_text_to_clipboard(&text)
+}
+
+/// Runs [`copy_file_contents_to_clipboard`] on a worker thread, so reading the file does not
+/// stall the main loop, and calls `on_done` with the result on the main loop.
+pub fn copy_file_contents_async<F>(file_path: &str, max_bytes: u64, on_done: F)
+where
+    F: FnOnce(Result<(), ClipboardError>) + 'static,
+{
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
 
-    if !output.status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("gpaste-client command failed with status: {}", output.status)
-        ));
+    let path = file_path.to_string();
+    let worker_sender = sender.clone();
+    let spawned = thread::Builder::new()
+        .name("copy-file-contents".to_string())
+        .spawn(move || {
+            let _ = worker_sender.send(copy_file_contents_to_clipboard(&path, max_bytes));
+        });
+    if let Err(e) = spawned {
+        let _ = sender.send(Err(e.into()));
     }
-    
-    Ok(())
+
+    let mut on_done = Some(on_done);
+    receiver.attach(None, move |result| {
+        if let Some(on_done) = on_done.take() {
+            on_done(result);
+        }
+        glib::Continue(false)
+    });
 }
 
-pub fn save_to_tmp_file(content: &str) -> Result<String, io::Error> {
-    let mut tmp_file = NamedTempFile::new()?;
-    tmp_file.write_all(content.as_bytes())?;
-    tmp_file.flush()?;
-    
-    let path = tmp_file.path().to_path_buf();
-    
-    let _file = tmp_file.persist(&path)?;
-    
-    Ok(path.to_string_lossy().to_string())
+pub fn copy_to_clipboard_by_gpaste_uuid(uuid: &str) -> Result<(), ClipboardError> {
+    execute_gpaste_command(&["select", uuid], Some(uuid))?;
+    Ok(())
 }
 
-pub fn open_in_external_app(file_path: &str) -> Result<(), io::Error> {
+pub fn open_in_external_app(file_path: &str) -> Result<(), ClipboardError> {
     let output = Command::new("xdg-open")
         .arg(file_path)
-        .output()?;
-    
+        .output()
+        .map_err(|e| ClipboardError::OpenFailed {
+            target: file_path.to_string(),
+            reason: format!("could not run xdg-open: {}", e),
+        })?;
+
     if !output.status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("xdg-open command failed with status: {}", output.status)
-        ));
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(ClipboardError::OpenFailed {
+            target: file_path.to_string(),
+            reason: if stderr.is_empty() {
+                format!("xdg-open failed with {}", output.status)
+            } else {
+                stderr
+            },
+        });
+    }
+
+    Ok(())
+}
+
+/// Shows the file at `file_path` in the file manager, selected if the file manager supports
+/// it. Falls back to opening the containing folder.
+pub fn open_containing_folder(file_path: &str) -> Result<(), ClipboardError> {
+    let uri = gio::File::for_path(file_path).uri().to_string();
+    let shown = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE).and_then(|connection| {
+        connection.call_sync(
+            Some("org.freedesktop.FileManager1"),
+            "/org/freedesktop/FileManager1",
+            "org.freedesktop.FileManager1",
+            "ShowItems",
+            Some(&(vec![uri], "").to_variant()),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+        )
+    });
+
+    match shown {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            eprintln!("Error showing {} in the file manager: {}", file_path, e);
+            let folder = Path::new(file_path)
+                .parent()
+                .map(|parent| parent.to_string_lossy().to_string())
+                .unwrap_or_else(|| "/".to_string());
+            open_in_external_app(&folder)
+        }
     }
-    
+}
+
+pub fn open_in_editor(editor_command: &str, file_path: &str) -> Result<(), ClipboardError> {
+    open_files_in_editor(editor_command, &[file_path])
+}
+
+/// Runs `editor_command` once with all `file_paths` as arguments.
+pub fn open_files_in_editor<S: AsRef<str>>(editor_command: &str, file_paths: &[S]) -> Result<(), ClipboardError> {
+    let target = file_paths
+        .iter()
+        .map(|file_path| file_path.as_ref())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let open_failed = |reason: String| ClipboardError::OpenFailed {
+        target: target.clone(),
+        reason,
+    };
+
+    let argv = gtk::glib::shell_parse_argv(editor_command)
+        .map_err(|e| open_failed(format!("invalid editor command: {}", e)))?;
+
+    let Some((program, args)) = argv.split_first() else {
+        return Err(open_failed("editor command is empty".to_string()));
+    };
+
+    Command::new(program)
+        .args(args)
+        .args(file_paths.iter().map(|file_path| file_path.as_ref()))
+        .spawn()
+        .map_err(|e| open_failed(format!("could not run {}: {}", program.to_string_lossy(), e)))?;
+
     Ok(())
 }
@@ -1,18 +1,75 @@
-use gtk::{gio, prelude::*, Application};
+use gtk::{glib, prelude::*, Application};
+use std::{cell::Cell, env, process, rc::Rc};
 
-use crate::ui::build_ui;
+use clipboard_manager::session_files;
 
-mod constants;
+use crate::{recording::RecordingCommand, ui::activate};
+
+mod config;
+mod exclusion;
+mod expiry;
 mod get_clipboard_entries;
 mod keyboard;
+mod recording;
 mod ui;
 
+pub const APP_ID: &str = "com.example.clipboard-manager";
+
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match RecordingCommand::parse(&args) {
+        Some(Ok(command)) => process::exit(recording::run(command)),
+        Some(Err(e)) => {
+            eprintln!("{}\n{}", e, RecordingCommand::USAGE);
+            process::exit(2);
+        }
+        None => {}
+    }
+
     let app = Application::builder()
-        .application_id("com.example.clipboard-manager")
-        .flags(gio::ApplicationFlags::REPLACE)
+        .application_id(APP_ID)
         .build();
 
-    app.connect_activate(build_ui);
+    app.add_main_option(
+        "resident",
+        glib::Char::from(b'r'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        "Stay running in the background and show the picker on the next launch",
+        None,
+    );
+
+    let resident = Rc::new(Cell::new(false));
+
+    let resident_for_options = resident.clone();
+    app.connect_handle_local_options(move |app, options| {
+        if !options.contains("resident") {
+            return -1;
+        }
+        if let Err(e) = app.register(gtk::gio::Cancellable::NONE) {
+            eprintln!("Error registering the application: {}", e);
+            return 1;
+        }
+        if app.is_remote() {
+            eprintln!("Clipboard manager is already running.");
+            return 0;
+        }
+        resident_for_options.set(true);
+        -1
+    });
+
+    app.connect_startup(|app| {
+        recording::install_actions(app);
+        session_files::remove_stale_session_dirs();
+    });
+    // Outside resident mode the picker exits as soon as the opened application takes focus,
+    // so its files are kept for that application and removed on the next start instead.
+    let resident_for_shutdown = resident.clone();
+    app.connect_shutdown(move |_| {
+        if resident_for_shutdown.get() {
+            session_files::remove_session_dir();
+        }
+    });
+    app.connect_activate(move |app| activate(app, resident.get()));
     app.run();
 }
@@ -0,0 +1,322 @@
+use gtk::{
+    gio,
+    glib::{self, Continue, ToVariant},
+    prelude::*,
+    Application,
+};
+use std::{cell::RefCell, rc::Rc, sync::Arc, time::Duration};
+
+use clipboard_manager::backend::ClipboardBackend;
+
+use crate::config::Config;
+
+/// `clipboard_manager pause [--for DURATION]` or `clipboard_manager resume`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingCommand {
+    /// Pauses recording, until resumed or for `duration`.
+    Pause { duration: Option<Duration> },
+    Resume,
+}
+
+const PAUSE_ACTION: &str = "pause";
+const RESUME_ACTION: &str = "resume";
+const MAX_PAUSE: Duration = Duration::from_secs(24 * 60 * 60);
+const SIGINT: i32 = 2;
+const SIGTERM: i32 = 15;
+
+/// Parses durations such as `90`, `30s`, `5m` or `1h30m`; bare numbers are seconds.
+fn parse_duration(spec: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration '{}', expected e.g. 30s, 5m or 1h30m", spec);
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+
+    for c in spec.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        seconds = seconds.saturating_add(value.saturating_mul(unit));
+        number.clear();
+    }
+    if !number.is_empty() {
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        seconds = seconds.saturating_add(value);
+    }
+
+    let duration = Duration::from_secs(seconds);
+    if duration.is_zero() {
+        return Err(invalid());
+    }
+    if duration > MAX_PAUSE {
+        return Err(format!("recording can be paused for at most {}", format_duration(MAX_PAUSE)));
+    }
+    Ok(duration)
+}
+
+/// `1h 30m`, `5m` or `45s`.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let parts = [(seconds / 3600, "h"), (seconds % 3600 / 60, "m"), (seconds % 60, "s")];
+    parts
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl RecordingCommand {
+    pub const USAGE: &'static str = "Usage: clipboard_manager pause [--for DURATION] | resume";
+
+    /// Parses the command line arguments after the program name. Returns `None` if they are
+    /// not a recording command.
+    pub fn parse(args: &[String]) -> Option<Result<Self, String>> {
+        let (command, options) = args.split_first()?;
+        let parsed = match (command.as_str(), options) {
+            ("pause", []) => Ok(Self::Pause { duration: None }),
+            ("pause", [flag, duration]) if flag == "--for" => {
+                parse_duration(duration).map(|duration| Self::Pause { duration: Some(duration) })
+            }
+            ("pause", [flag]) => match flag.strip_prefix("--for=") {
+                Some(duration) => parse_duration(duration).map(|duration| Self::Pause { duration: Some(duration) }),
+                None => Err(format!("unexpected argument '{}'", flag)),
+            },
+            ("resume", []) => Ok(Self::Resume),
+            ("pause" | "resume", _) => Err(format!("unexpected arguments to '{}'", command)),
+            _ => return None,
+        };
+        Some(parsed)
+    }
+}
+
+fn load_backend() -> Arc<dyn ClipboardBackend> {
+    match Config::load() {
+        Ok(config) => config.backend.create(),
+        Err(e) => {
+            eprintln!("{}\nUsing the default backend.", e);
+            Config::default().backend.create()
+        }
+    }
+}
+
+/// The session bus connection, if a running instance owns the application's bus name. That
+/// may also be an open picker that is not resident; it keeps running until a timed pause it
+/// took over is done.
+fn resident_instance() -> Option<gio::DBusConnection> {
+    let connection = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE).ok()?;
+    let reply = connection
+        .call_sync(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "NameHasOwner",
+            Some(&(crate::APP_ID,).to_variant()),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+        )
+        .ok()?;
+    let (has_owner,) = reply.get::<(bool,)>()?;
+    has_owner.then_some(connection)
+}
+
+/// Asks the resident instance to run one of the actions set up by `install_actions`.
+fn activate_in_resident_instance(
+    connection: &gio::DBusConnection,
+    action: &str,
+    parameter: Option<&glib::Variant>,
+) -> Result<(), glib::Error> {
+    let object_path = format!("/{}", crate::APP_ID.replace('.', "/").replace('-', "_"));
+    let actions = gio::DBusActionGroup::get(connection, Some(crate::APP_ID), &object_path);
+    actions.activate_action(action, parameter);
+    connection.flush_sync(gio::Cancellable::NONE)
+}
+
+/// Waits for `duration` or until interrupted, whichever comes first.
+fn wait(duration: Duration) {
+    let main_loop = glib::MainLoop::new(None, false);
+
+    let timeout_loop = main_loop.clone();
+    glib::timeout_add_local_once(duration, move || timeout_loop.quit());
+    for signal in [SIGINT, SIGTERM] {
+        let signal_loop = main_loop.clone();
+        glib::unix_signal_add_local(signal, move || {
+            signal_loop.quit();
+            Continue(false)
+        });
+    }
+
+    main_loop.run();
+}
+
+/// Runs `command` and returns the exit status. A resident instance takes over timed pauses;
+/// without one, this process waits until the pause is over.
+pub fn run(command: RecordingCommand) -> i32 {
+    if let Some(connection) = resident_instance() {
+        let (action, parameter) = match command {
+            RecordingCommand::Pause { duration } => {
+                let seconds = duration.map_or(0, |duration| duration.as_secs());
+                (PAUSE_ACTION, Some(seconds.to_variant()))
+            }
+            RecordingCommand::Resume => (RESUME_ACTION, None),
+        };
+        if let Err(e) = activate_in_resident_instance(&connection, action, parameter.as_ref()) {
+            eprintln!("Error contacting the running clipboard manager: {}", e);
+            return 1;
+        }
+    } else {
+        let backend = load_backend();
+        let recording = matches!(command, RecordingCommand::Resume);
+        if let Err(e) = backend.set_recording(recording) {
+            eprintln!("Error {} recording: {}", if recording { "resuming" } else { "pausing" }, e);
+            return 1;
+        }
+        if let RecordingCommand::Pause { duration: Some(duration) } = command {
+            println!("Recording paused for {}. Press Ctrl+C to resume now.", format_duration(duration));
+            wait(duration);
+            if let Err(e) = backend.set_recording(true) {
+                eprintln!("Error resuming recording: {}", e);
+                return 1;
+            }
+            println!("Recording resumed.");
+            return 0;
+        }
+    }
+
+    match command {
+        RecordingCommand::Pause { duration: Some(duration) } => {
+            println!("Recording paused for {}.", format_duration(duration));
+        }
+        RecordingCommand::Pause { duration: None } => {
+            println!("Recording paused. Run `clipboard_manager resume` to resume.");
+        }
+        RecordingCommand::Resume => println!("Recording resumed."),
+    }
+    0
+}
+
+/// Cancels the pending resume, releasing the hold it kept on `app`.
+fn cancel_resume_timer(app: &Application, resume_timer: &RefCell<Option<glib::SourceId>>) {
+    if let Some(timer) = resume_timer.take() {
+        timer.remove();
+        app.release();
+    }
+}
+
+/// Adds the `pause` and `resume` actions that `run` activates in a resident instance. `pause`
+/// takes the length of the pause in seconds, 0 pausing until resumed. A pending resume holds
+/// the application, so a picker that is not resident stays running until it is done.
+pub fn install_actions(app: &Application) {
+    let backend = load_backend();
+    let resume_timer: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+
+    let pause = gio::SimpleAction::new(PAUSE_ACTION, Some(glib::VariantTy::UINT64));
+    let pause_backend = backend.clone();
+    let pause_timer = resume_timer.clone();
+    let pause_app = app.downgrade();
+    pause.connect_activate(move |_, parameter| {
+        let Some(app) = pause_app.upgrade() else {
+            return;
+        };
+        cancel_resume_timer(&app, &pause_timer);
+        if let Err(e) = pause_backend.set_recording(false) {
+            eprintln!("Error pausing recording: {}", e);
+            return;
+        }
+
+        let seconds = parameter.and_then(|parameter| parameter.get::<u64>()).unwrap_or(0);
+        if seconds == 0 {
+            return;
+        }
+        let backend = pause_backend.clone();
+        let fired_timer = pause_timer.clone();
+        let fired_app = app.downgrade();
+        let timer = glib::timeout_add_local_once(Duration::from_secs(seconds), move || {
+            fired_timer.take();
+            if let Err(e) = backend.set_recording(true) {
+                eprintln!("Error resuming recording: {}", e);
+            }
+            if let Some(app) = fired_app.upgrade() {
+                app.release();
+            }
+        });
+        app.hold();
+        pause_timer.replace(Some(timer));
+    });
+
+    let resume = gio::SimpleAction::new(RESUME_ACTION, None);
+    let resume_app = app.downgrade();
+    resume.connect_activate(move |_, _| {
+        if let Some(app) = resume_app.upgrade() {
+            cancel_resume_timer(&app, &resume_timer);
+        }
+        if let Err(e) = backend.set_recording(true) {
+            eprintln!("Error resuming recording: {}", e);
+        }
+    });
+
+    app.add_action(&pause);
+    app.add_action(&resume);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration(" 5m "), Ok(Duration::from_secs(5 * 60)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("24h"), Ok(MAX_PAUSE));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for spec in ["", "0", "0m", "m", "5 m", "5d", "-5m", "1.5h"] {
+            assert!(parse_duration(spec).is_err(), "{} should be rejected", spec);
+        }
+        assert_eq!(
+            parse_duration("25h"),
+            Err("recording can be paused for at most 24h".to_string())
+        );
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::from_secs(90 * 60)), "1h 30m");
+        assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+    }
+
+    #[test]
+    fn parses_commands() {
+        let parse = |arguments: &[&str]| RecordingCommand::parse(&args(arguments));
+        assert_eq!(parse(&["pause"]), Some(Ok(RecordingCommand::Pause { duration: None })));
+        assert_eq!(
+            parse(&["pause", "--for", "5m"]),
+            Some(Ok(RecordingCommand::Pause { duration: Some(Duration::from_secs(5 * 60)) }))
+        );
+        assert_eq!(
+            parse(&["pause", "--for=1h"]),
+            Some(Ok(RecordingCommand::Pause { duration: Some(Duration::from_secs(60 * 60)) }))
+        );
+        assert_eq!(parse(&["resume"]), Some(Ok(RecordingCommand::Resume)));
+        assert!(matches!(parse(&["resume", "now"]), Some(Err(_))));
+        assert!(matches!(parse(&["pause", "--until", "5m"]), Some(Err(_))));
+        assert_eq!(parse(&["--help"]), None);
+        assert_eq!(parse(&[]), None);
+    }
+}
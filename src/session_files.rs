@@ -0,0 +1,145 @@
+use std::{
+    env,
+    fs::{self, DirBuilder, OpenOptions, Permissions},
+    io::{self, Write},
+    os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{syntax_highlight::Language, ClipboardError};
+
+const DIR_NAME: &str = "clipboard_manager";
+const DIR_MODE: u32 = 0o700;
+const FILE_MODE: u32 = 0o600;
+
+fn runtime_dir() -> Result<PathBuf, ClipboardError> {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+        _ => Err(io::Error::other("XDG_RUNTIME_DIR is not set, not writing clipboard contents to disk").into()),
+    }
+}
+
+/// Creates `dir` readable only by the owner of `parent`, or checks that the existing one is
+/// a directory of theirs and tightens its permissions.
+fn create_private_dir(parent: &Path, dir: &Path) -> Result<(), ClipboardError> {
+    match DirBuilder::new().mode(DIR_MODE).create(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.uid() != fs::metadata(parent)?.uid() {
+        return Err(io::Error::other(format!("{} is not a private directory", dir.display())).into());
+    }
+    if metadata.mode() & 0o777 != DIR_MODE {
+        fs::set_permissions(dir, Permissions::from_mode(DIR_MODE))?;
+    }
+    Ok(())
+}
+
+fn root_dir() -> Result<PathBuf, ClipboardError> {
+    Ok(runtime_dir()?.join(DIR_NAME))
+}
+
+fn session_dir() -> Result<PathBuf, ClipboardError> {
+    Ok(root_dir()?.join(process::id().to_string()))
+}
+
+/// Only the characters GPaste uses in uuids, so an entry's files stay inside the directory.
+fn file_stem(uuid: &str) -> String {
+    uuid.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect()
+}
+
+/// Writes `contents` to the file of entry `uuid`, so it can be opened in another application,
+/// and returns its path. `extension` lets `xdg-open` pick a fitting application. The file lives
+/// in a directory of this process under `$XDG_RUNTIME_DIR`, which only the user can access and
+/// which the system empties at logout. It is removed with its entry. The directory is removed
+/// when a resident instance exits, and otherwise on the next start, since the opened
+/// application may still be reading the file after the picker closed. Writing the same entry
+/// again replaces the file.
+pub fn write_session_file(uuid: &str, contents: &str, extension: &str) -> Result<String, ClipboardError> {
+    let runtime_dir = runtime_dir()?;
+    let root_dir = root_dir()?;
+    create_private_dir(&runtime_dir, &root_dir)?;
+    let session_dir = session_dir()?;
+    create_private_dir(&runtime_dir, &session_dir)?;
+
+    let path = session_dir.join(format!("{}.{}", file_stem(uuid), extension));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(FILE_MODE)
+        .open(&path)?;
+    file.write_all(contents.as_bytes())?;
+    file.flush()?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Removes the files written for entry `uuid`, e.g. once it was deleted from the history.
+pub fn remove_session_files(uuid: &str) {
+    let Ok(dir_entries) = session_dir().and_then(|dir| fs::read_dir(dir).map_err(ClipboardError::from)) else {
+        return;
+    };
+    let stem = file_stem(uuid);
+    for path in dir_entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.file_stem().map(|name| *name == *stem).unwrap_or(false) {
+            if let Err(e) = fs::remove_file(&path) {
+                eprintln!("Error removing {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn remove_dir(dir: &Path) {
+    match fs::remove_dir_all(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => eprintln!("Error removing {}: {}", dir.display(), e),
+    }
+}
+
+/// Removes this process's files, when a resident instance exits.
+pub fn remove_session_dir() {
+    if let Ok(dir) = session_dir() {
+        remove_dir(&dir);
+    }
+}
+
+/// Removes the files of earlier processes that are no longer running.
+pub fn remove_stale_session_dirs() {
+    let Ok(dir_entries) = root_dir().and_then(|dir| fs::read_dir(dir).map_err(ClipboardError::from)) else {
+        return;
+    };
+    let own_pid = process::id();
+    for entry in dir_entries.filter_map(|entry| entry.ok()) {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        if pid != own_pid && !Path::new("/proc").join(pid.to_string()).exists() {
+            remove_dir(&entry.path());
+        }
+    }
+}
+
+/// Lines whose start marks text as Markdown.
+const MARKDOWN_MARKERS: &[&str] = &["# ", "## ", "### ", "```", "- [ ] ", "- [x] ", "> "];
+
+/// The extension to open copied text with: that of its programming language, `md` for text
+/// with Markdown headings, code blocks or task lists, otherwise `txt`.
+pub fn text_file_extension(text: &str) -> &'static str {
+    if let Some(extension) = Language::detect(text).and_then(|language| language.extension()) {
+        return extension;
+    }
+    let is_markdown = text
+        .lines()
+        .any(|line| MARKDOWN_MARKERS.iter().any(|marker| line.starts_with(marker)));
+    if is_markdown {
+        "md"
+    } else {
+        "txt"
+    }
+}
@@ -0,0 +1,60 @@
+use std::{env, process::Command};
+
+/// The application owning the focused window, as far as X11 tells: the instance and class
+/// names of its `WM_CLASS` and, for GTK applications, its application id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceApp {
+    pub names: Vec<String>,
+}
+
+impl SourceApp {
+    /// Whether any of the application's names equals `name`, ignoring case.
+    pub fn matches(&self, name: &str) -> bool {
+        self.names.iter().any(|own| own.eq_ignore_ascii_case(name))
+    }
+}
+
+fn xprop(args: &[&str]) -> Option<String> {
+    let output = Command::new("xprop").args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The quoted strings of a property line such as `WM_CLASS(STRING) = "keepassxc", "KeePassXC"`.
+fn quoted_values(line: &str) -> Vec<String> {
+    let Some((_, values)) = line.split_once(" = ") else {
+        return Vec::new();
+    };
+    values
+        .split(',')
+        .map(|value| value.trim().trim_matches('"').to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// Looks up the application of the focused window with `xprop`. Returns `None` when there is
+/// no X11 display, `xprop` is missing or the focused window is a native Wayland one.
+pub fn focused_application() -> Option<SourceApp> {
+    let active = xprop(&["-root", "_NET_ACTIVE_WINDOW"])?;
+    let window_id = active.split_whitespace().last().filter(|id| id.starts_with("0x") && *id != "0x0")?;
+
+    let properties = xprop(&["-id", window_id, "WM_CLASS", "_GTK_APPLICATION_ID"])?;
+    let names: Vec<String> = properties
+        .lines()
+        .filter(|line| line.starts_with("WM_CLASS") || line.starts_with("_GTK_APPLICATION_ID"))
+        .flat_map(quoted_values)
+        .collect();
+
+    (!names.is_empty()).then_some(SourceApp { names })
+}
+
+/// Why the focused application cannot be looked up, if it cannot: there is no X11 display
+/// (a plain Wayland session without XWayland) or `xprop` cannot be run.
+pub fn focus_detection_unavailable() -> Option<String> {
+    if env::var_os("DISPLAY").filter(|display| !display.is_empty()).is_none() {
+        return Some("there is no X11 display".to_string());
+    }
+    if xprop(&["-root", "_NET_ACTIVE_WINDOW"]).is_none() {
+        return Some("`xprop` is not installed or cannot reach the display".to_string());
+    }
+    None
+}
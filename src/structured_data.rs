@@ -0,0 +1,345 @@
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Text larger than this is not checked for structured data.
+const MAX_DETECTED_LEN: usize = 4 * 1024 * 1024;
+
+/// Without a `---` marker, text needs this many `key: value` lines to count as YAML.
+const MIN_YAML_MAPPING_LINES: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl DataFormat {
+    /// File extension of the format, e.g. `json`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DataFormat::Json => "json",
+            DataFormat::Yaml => "yaml",
+            DataFormat::Toml => "toml",
+        }
+    }
+}
+
+impl fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DataFormat::Json => "JSON",
+            DataFormat::Yaml => "YAML",
+            DataFormat::Toml => "TOML",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Why text that looks like structured data does not parse, with the 1-based position of the
+/// problem when the parser reports one.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub message: String,
+    pub location: Option<(usize, usize)>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "line {}, column {}: {}", line, column, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Copied text holding JSON, YAML or TOML. Values of all three formats are represented as
+/// JSON values.
+#[derive(Debug, Clone)]
+pub struct StructuredData {
+    pub format: DataFormat,
+    pub value: Result<Value, ParseError>,
+}
+
+/// Parser messages end with the position, which is reported separately.
+fn strip_location(message: &str) -> String {
+    match message.rfind(" at line ") {
+        Some(end) => message[..end].to_string(),
+        None => message.to_string(),
+    }
+}
+
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map(|line| line.chars().count()).unwrap_or(0) + 1;
+    (line, column)
+}
+
+fn parse_json(text: &str) -> Result<Value, ParseError> {
+    serde_json::from_str(text).map_err(|e| ParseError {
+        message: strip_location(&e.to_string()),
+        location: Some((e.line(), e.column())),
+    })
+}
+
+fn parse_toml(text: &str) -> Result<Value, ParseError> {
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| ParseError {
+        message: e.message().to_string(),
+        location: e.span().map(|span| line_and_column(text, span.start)),
+    })?;
+    Ok(toml_to_json(toml::Value::Table(table)))
+}
+
+fn parse_yaml(text: &str) -> Result<Value, ParseError> {
+    let value: serde_yaml::Value = serde_yaml::from_str(text).map_err(|e| ParseError {
+        message: strip_location(&e.to_string()),
+        location: e.location().map(|location| (location.line(), location.column())),
+    })?;
+    Ok(yaml_to_json(value))
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(integer) => Value::from(integer),
+        toml::Value::Float(float) => Value::from(float),
+        toml::Value::Boolean(boolean) => Value::Bool(boolean),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => Value::Array(array.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => {
+            Value::Object(table.into_iter().map(|(key, value)| (key, toml_to_json(value))).collect())
+        }
+    }
+}
+
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(boolean) => Value::Bool(boolean),
+        serde_yaml::Value::Number(number) => {
+            if let Some(integer) = number.as_i64() {
+                Value::from(integer)
+            } else if let Some(integer) = number.as_u64() {
+                Value::from(integer)
+            } else {
+                number.as_f64().map(Value::from).unwrap_or(Value::Null)
+            }
+        }
+        serde_yaml::Value::String(string) => Value::String(string),
+        serde_yaml::Value::Sequence(sequence) => Value::Array(sequence.into_iter().map(yaml_to_json).collect()),
+        serde_yaml::Value::Mapping(mapping) => {
+            let object: Map<String, Value> = mapping
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        serde_yaml::Value::String(key) => key,
+                        key => serde_yaml::to_string(&key).map(|key| key.trim().to_string()).unwrap_or_default(),
+                    };
+                    (key, yaml_to_json(value))
+                })
+                .collect();
+            Value::Object(object)
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+/// The first line that is neither blank nor a comment.
+fn first_content_line(text: &str) -> Option<&str> {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Whether `line` is a TOML table header such as `[server]` or `[[servers]]`.
+fn is_toml_header(line: &str) -> bool {
+    let name = line.trim_start_matches('[').trim_end_matches(']');
+    line.starts_with('[')
+        && line.ends_with(']')
+        && !name.is_empty()
+        && name.chars().all(|c| c.is_alphanumeric() || "_-.\"' ".contains(c))
+}
+
+/// Whether `line` is a YAML mapping entry such as `name: value`, `name:` or `- name: value`,
+/// with a key without spaces, unlike `Note: ...` in prose.
+fn is_yaml_mapping_line(line: &str) -> bool {
+    let line = line.trim();
+    let line = line.strip_prefix("- ").unwrap_or(line);
+    let Some((key, rest)) = line.split_once(':') else {
+        return false;
+    };
+    !key.is_empty()
+        && key.chars().all(|c| c.is_alphanumeric() || "_-.\"'".contains(c))
+        && (rest.is_empty() || rest.starts_with(' '))
+}
+
+fn is_container(value: &Value) -> bool {
+    matches!(value, Value::Object(_) | Value::Array(_))
+}
+
+impl StructuredData {
+    /// Recognises `text` as JSON, TOML or YAML. Text that clearly tries to be one of them but
+    /// does not parse is recognised too, so the detail view can point out the error. The text
+    /// is parsed as it is shown, so error positions match what the user sees.
+    pub fn detect(text: &str) -> Option<Self> {
+        if text.len() > MAX_DETECTED_LEN {
+            return None;
+        }
+        let trimmed = text.trim();
+        let first_line = first_content_line(trimmed)?;
+
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            let value = parse_json(text);
+            // Invalid JSON is only reported for text that has the shape of JSON; a TOML
+            // `[table]` header or a `{placeholder}` is not broken JSON.
+            let looks_like_json = (trimmed.ends_with('}') || trimmed.ends_with(']'))
+                && trimmed.contains('"')
+                && !is_toml_header(first_line);
+            if value.is_ok() || looks_like_json {
+                return Some(Self { format: DataFormat::Json, value });
+            }
+        }
+
+        // A `[table]` header or `key = value` line starts TOML.
+        let looks_like_toml = is_toml_header(first_line)
+            || first_line
+                .split_once('=')
+                .map(|(key, _)| {
+                    let key = key.trim();
+                    !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || "_-.\"' ".contains(c))
+                })
+                .unwrap_or(false);
+        if looks_like_toml {
+            return match parse_toml(text) {
+                Ok(value) if value.as_object().map(|table| !table.is_empty()).unwrap_or(false) => {
+                    Some(Self { format: DataFormat::Toml, value: Ok(value) })
+                }
+                Ok(_) => None,
+                Err(e) if is_toml_header(first_line) => Some(Self { format: DataFormat::Toml, value: Err(e) }),
+                Err(_) => None,
+            };
+        }
+
+        // Nearly any text is valid YAML, so only text with several `key: value` lines counts,
+        // unless it starts with a document marker.
+        let explicit_yaml = first_line == "---";
+        let mapping_lines = trimmed.lines().filter(|line| is_yaml_mapping_line(line)).count();
+        if !explicit_yaml && mapping_lines < MIN_YAML_MAPPING_LINES {
+            return None;
+        }
+        match parse_yaml(text) {
+            Ok(value) if is_container(&value) => Some(Self { format: DataFormat::Yaml, value: Ok(value) }),
+            Ok(_) => None,
+            Err(e) if explicit_yaml => Some(Self { format: DataFormat::Yaml, value: Err(e) }),
+            Err(_) => None,
+        }
+    }
+
+    /// The data as compact JSON.
+    pub fn minified(&self) -> Option<String> {
+        let value = self.value.as_ref().ok()?;
+        serde_json::to_string(value).ok()
+    }
+
+    /// The data pretty-printed in its own format.
+    pub fn pretty(&self) -> Option<String> {
+        let value = self.value.as_ref().ok()?;
+        let pretty = match self.format {
+            DataFormat::Json => serde_json::to_string_pretty(value).ok(),
+            DataFormat::Yaml => serde_yaml::to_string(value).ok(),
+            DataFormat::Toml => toml::to_string_pretty(value).ok(),
+        };
+        // TOML cannot hold every JSON value, e.g. null.
+        pretty.or_else(|| serde_json::to_string_pretty(value).ok())
+    }
+
+    /// Short description such as `JSON object · 3 keys`.
+    pub fn summary(&self) -> String {
+        match &self.value {
+            Ok(Value::Object(object)) => format!("{} object · {} keys", self.format, object.len()),
+            Ok(Value::Array(array)) => format!("{} list · {} items", self.format, array.len()),
+            Ok(_) => format!("{} value", self.format),
+            Err(_) => format!("Invalid {}", self.format),
+        }
+    }
+}
+
+/// Path of a child value in JSONPath notation, e.g. `$.items[2].name`.
+pub fn child_path(parent: &str, key: Option<&str>, index: Option<usize>) -> String {
+    match (key, index) {
+        (Some(key), _) if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+            format!("{}.{}", parent, key)
+        }
+        (Some(key), _) => format!("{}[{}]", parent, Value::String(key.to_string())),
+        (None, Some(index)) => format!("{}[{}]", parent, index),
+        (None, None) => parent.to_string(),
+    }
+}
+
+/// JSON Pointer of a child value, e.g. `/items/2/name`, for looking the value up again.
+pub fn child_pointer(parent: &str, key: &str) -> String {
+    format!("{}/{}", parent, key.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect_format(text: &str) -> Option<DataFormat> {
+        StructuredData::detect(text).map(|data| data.format)
+    }
+
+    #[test]
+    fn detects_json() {
+        let data = StructuredData::detect(r#"{"name": "app", "tags": [1, 2]}"#).unwrap();
+        assert_eq!(data.format, DataFormat::Json);
+        assert_eq!(data.summary(), "JSON object · 2 keys");
+        assert_eq!(data.minified().as_deref(), Some(r#"{"name":"app","tags":[1,2]}"#));
+    }
+
+    #[test]
+    fn reports_json_errors_against_the_shown_text() {
+        let data = StructuredData::detect("\n  {\"name\": }").unwrap();
+        assert_eq!(data.format, DataFormat::Json);
+        assert_eq!(data.value.unwrap_err().location.map(|(line, _)| line), Some(2));
+    }
+
+    #[test]
+    fn placeholders_are_not_json() {
+        assert_eq!(detect_format("{placeholder}"), None);
+        assert_eq!(detect_format("[1]"), Some(DataFormat::Json));
+    }
+
+    #[test]
+    fn detects_toml() {
+        assert_eq!(detect_format("[server]\nport = 8080"), Some(DataFormat::Toml));
+        assert_eq!(detect_format("name = \"app\"\nversion = 2"), Some(DataFormat::Toml));
+
+        let broken = StructuredData::detect("[server]\nport = ").unwrap();
+        assert_eq!(broken.format, DataFormat::Toml);
+        assert_eq!(broken.value.unwrap_err().location.map(|(line, _)| line), Some(2));
+    }
+
+    #[test]
+    fn detects_yaml_with_several_mapping_lines() {
+        assert_eq!(detect_format("name: app\nversion: 2"), Some(DataFormat::Yaml));
+        assert_eq!(detect_format("- name: a\n- name: b"), Some(DataFormat::Yaml));
+        assert_eq!(detect_format("---\nname: app"), Some(DataFormat::Yaml));
+    }
+
+    #[test]
+    fn prose_is_not_yaml() {
+        assert_eq!(detect_format("name: app"), None);
+        assert_eq!(detect_format("Note: the build is broken.\nPlease fix it: soon."), None);
+        assert_eq!(detect_format("Just some text."), None);
+    }
+
+    #[test]
+    fn builds_child_paths() {
+        assert_eq!(child_path("$", Some("items"), None), "$.items");
+        assert_eq!(child_path("$.items", None, Some(2)), "$.items[2]");
+        assert_eq!(child_path("$", Some("a b"), None), "$[\"a b\"]");
+        assert_eq!(child_pointer("/items", "a/b~c"), "/items/a~1b~0c");
+    }
+}
@@ -0,0 +1,267 @@
+use gtk::glib;
+use std::{path::Path, sync::OnceLock, thread};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{FontStyle, Theme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// Lines longer than this, e.g. minified code, are left unstyled: highlighting them can take
+/// seconds.
+const MAX_HIGHLIGHTED_LINE_LEN: usize = 16 * 1024;
+
+/// Only this many characters at the start of copied text are scanned for language markers.
+const DETECTION_SAMPLE_LEN: usize = 16 * 1024;
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme(variant: ThemeVariant) -> &'static Theme {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    let themes = THEMES.get_or_init(ThemeSet::load_defaults);
+    let name = match variant {
+        ThemeVariant::Light => "InspiredGitHub",
+        ThemeVariant::Dark => "base16-ocean.dark",
+    };
+    &themes.themes[name]
+}
+
+/// Colour scheme of the highlighted text, matching the background it is shown on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeVariant {
+    Light,
+    Dark,
+}
+
+/// A language the previews can highlight.
+#[derive(Debug, Clone, Copy)]
+pub struct Language {
+    syntax: &'static SyntaxReference,
+}
+
+impl Language {
+    fn from_syntax(syntax: &'static SyntaxReference) -> Option<Self> {
+        (syntax.name != "Plain Text").then_some(Self { syntax })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.syntax.name
+    }
+
+    /// The usual file extension of the language, e.g. `rs`.
+    pub fn extension(&self) -> Option<&'static str> {
+        self.syntax.file_extensions.first().map(String::as_str)
+    }
+
+    /// The language of a file, judged by its extension or, for files such as `Makefile`, its
+    /// name.
+    pub fn for_path(path: &str) -> Option<Self> {
+        let path = Path::new(path);
+        let syntaxes = syntax_set();
+        let syntax = path
+            .extension()
+            .and_then(|ext| syntaxes.find_syntax_by_extension(&ext.to_string_lossy()))
+            .or_else(|| {
+                path.file_name()
+                    .and_then(|name| syntaxes.find_syntax_by_extension(&name.to_string_lossy()))
+            })?;
+        Self::from_syntax(syntax)
+    }
+
+    /// Guesses the language of copied text: from a shebang or similar first line, otherwise
+    /// from keywords typical of a language at the start of its lines.
+    pub fn detect(text: &str) -> Option<Self> {
+        let syntaxes = syntax_set();
+        let first_line = text.lines().find(|line| !line.trim().is_empty())?;
+        if let Some(syntax) = syntaxes.find_syntax_by_first_line(first_line) {
+            return Self::from_syntax(syntax);
+        }
+
+        let sample = match text.char_indices().nth(DETECTION_SAMPLE_LEN) {
+            Some((end, _)) => &text[..end],
+            None => text,
+        };
+        let token = detect_token(sample)?;
+        Self::from_syntax(syntaxes.find_syntax_by_token(token)?)
+    }
+}
+
+/// Line prefixes that mark a language, keyed by the extension syntect knows it by.
+const LANGUAGE_MARKERS: &[(&str, &[&str])] = &[
+    ("rs", &["fn ", "pub fn ", "use ", "impl ", "let ", "let mut ", "struct ", "enum ", "mod ", "#[derive"]),
+    ("py", &["def ", "import ", "from ", "class ", "elif ", "print(", "if __name__"]),
+    ("js", &["function ", "const ", "let ", "var ", "export ", "import ", "console.log("]),
+    ("c", &["#include ", "#define ", "int main(", "typedef ", "static ", "void "]),
+    ("go", &["package ", "func ", "import (", "type ", "var "]),
+    ("java", &["public class ", "private ", "public ", "import java", "package "]),
+    ("sh", &["echo ", "export ", "if [ ", "sudo ", "cd ", "mkdir ", "git ", "$ "]),
+    ("sql", &["select ", "insert into ", "update ", "delete from ", "create table ", "from ", "where "]),
+    ("css", &["body {", "color: ", "margin: ", "padding: ", "display: ", "@media ", "@import "]),
+];
+
+fn detect_token(sample: &str) -> Option<&'static str> {
+    let trimmed = sample.trim();
+
+    if trimmed.starts_with("diff --git") || (trimmed.starts_with("--- ") && trimmed.contains("\n+++ ")) {
+        return Some("diff");
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('[')) && trimmed.contains("\":") {
+        return Some("json");
+    }
+    if trimmed.starts_with('<') && trimmed.ends_with('>') {
+        let lowercase = trimmed.to_lowercase();
+        let is_html = lowercase.starts_with("<!doctype html") || lowercase.contains("<html") || lowercase.contains("<div");
+        return Some(if is_html { "html" } else { "xml" });
+    }
+
+    // Every line counts for at most one marker, so the score is the number of lines that
+    // look like the language.
+    let lines: Vec<String> = sample
+        .lines()
+        .map(|line| line.trim_start().to_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect();
+    let (token, score) = LANGUAGE_MARKERS
+        .iter()
+        .map(|(token, markers)| {
+            let score = lines
+                .iter()
+                .filter(|line| markers.iter().any(|marker| line.starts_with(marker)))
+                .count();
+            (*token, score)
+        })
+        .max_by_key(|(_, score)| *score)?;
+
+    // A single matching line is too weak a hint; ordinary prose starts lines with "from " too.
+    let required = 2.max(lines.len() / 4);
+    (score >= required).then_some(token)
+}
+
+/// How a stretch of highlighted text is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextStyle {
+    pub foreground: (u8, u8, u8),
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// Style of the bytes `start..end` of a piece of highlighted text.
+#[derive(Debug, Clone, Copy)]
+pub struct StyledRange {
+    pub start: usize,
+    pub end: usize,
+    pub style: TextStyle,
+}
+
+/// Highlights text that arrives in pieces. Only complete lines are highlighted, since the
+/// parser's state carries over from one line to the next; the rest is kept for the next piece.
+pub struct Highlighter {
+    lines: HighlightLines<'static>,
+    pending: String,
+}
+
+impl Highlighter {
+    pub fn new(language: Language, variant: ThemeVariant) -> Self {
+        Self {
+            lines: HighlightLines::new(language.syntax, theme(variant)),
+            pending: String::new(),
+        }
+    }
+
+    /// Adds `text` and returns the complete lines received so far with their styles, with
+    /// offsets relative to the returned text.
+    pub fn push(&mut self, text: &str) -> (String, Vec<StyledRange>) {
+        self.pending.push_str(text);
+        let complete_len = match self.pending.rfind('\n') {
+            Some(newline) => newline + 1,
+            None => return (String::new(), Vec::new()),
+        };
+        let rest = self.pending.split_off(complete_len);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        let styles = self.highlight(&complete);
+        (complete, styles)
+    }
+
+    /// Returns the last, unterminated line with its styles.
+    pub fn finish(mut self) -> (String, Vec<StyledRange>) {
+        let rest = std::mem::take(&mut self.pending);
+        let styles = self.highlight(&rest);
+        (rest, styles)
+    }
+
+    fn highlight(&mut self, text: &str) -> Vec<StyledRange> {
+        let syntaxes = syntax_set();
+        let mut styles = Vec::new();
+        let mut line_start = 0;
+
+        for line in LinesWithEndings::from(text) {
+            if line.len() <= MAX_HIGHLIGHTED_LINE_LEN {
+                match self.lines.highlight_line(line, syntaxes) {
+                    Ok(regions) => {
+                        let mut start = line_start;
+                        for (style, region) in regions {
+                            styles.push(StyledRange {
+                                start,
+                                end: start + region.len(),
+                                style: TextStyle {
+                                    foreground: (style.foreground.r, style.foreground.g, style.foreground.b),
+                                    bold: style.font_style.contains(FontStyle::BOLD),
+                                    italic: style.font_style.contains(FontStyle::ITALIC),
+                                },
+                            });
+                            start += region.len();
+                        }
+                    }
+                    Err(e) => eprintln!("Error highlighting text: {}", e),
+                }
+            }
+            line_start += line.len();
+        }
+
+        styles
+    }
+}
+
+/// Highlights all of `text` at once.
+pub fn highlight_text(text: &str, language: Language, variant: ThemeVariant) -> Vec<StyledRange> {
+    let mut highlighter = Highlighter::new(language, variant);
+    let (complete, mut styles) = highlighter.push(text);
+    let offset = complete.len();
+    let (_, rest_styles) = highlighter.finish();
+    styles.extend(rest_styles.into_iter().map(|range| StyledRange {
+        start: range.start + offset,
+        end: range.end + offset,
+        ..range
+    }));
+    styles
+}
+
+/// Detects the language of `text` and highlights it on a worker thread, as loading the syntax
+/// definitions and highlighting can both take long enough to stall the main loop. The receiver
+/// gets `None` if `text` does not look like code.
+pub fn highlight_text_async(
+    text: &str,
+    variant: ThemeVariant,
+) -> glib::Receiver<Option<(Language, Vec<StyledRange>)>> {
+    let (sender, receiver) = glib::MainContext::channel(glib::PRIORITY_DEFAULT_IDLE);
+
+    let text = text.to_string();
+    let worker_sender = sender.clone();
+    let spawned = thread::Builder::new()
+        .name("syntax-highlight".to_string())
+        .spawn(move || {
+            let highlighted =
+                Language::detect(&text).map(|language| (language, highlight_text(&text, language, variant)));
+            let _ = worker_sender.send(highlighted);
+        });
+
+    if let Err(e) = spawned {
+        eprintln!("Error starting syntax highlighting: {}", e);
+        let _ = sender.send(None);
+    }
+
+    receiver
+}
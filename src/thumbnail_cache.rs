@@ -0,0 +1,269 @@
+use gtk::{
+    gdk_pixbuf::{prelude::*, Pixbuf, PixbufLoader},
+    glib::{self, ChecksumType},
+};
+use std::{
+    fs::{self, DirBuilder, File, OpenOptions, Permissions},
+    io::Write,
+    os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::encryption::EncryptionKey;
+
+/// Row thumbnails stored as PNG files, keyed by the source path, its modification time and
+/// size, and the thumbnail size. Editing or replacing the source therefore never serves a
+/// stale thumbnail. The least recently used files are removed once the cache grows beyond
+/// its size limit.
+///
+/// An encrypted cache keeps the thumbnails encrypted with the key from the session keyring.
+/// The key is only fetched when a thumbnail is first read or written, and without it the
+/// cache stays unused rather than falling back to plain files.
+#[derive(Debug)]
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    stores_since_eviction: AtomicUsize,
+    encrypted: bool,
+    key: OnceLock<Option<EncryptionKey>>,
+}
+
+impl ThumbnailCache {
+    /// Eviction scans the whole directory, so it only runs after this many new thumbnails.
+    const EVICT_EVERY: usize = 20;
+    const PLAIN_EXTENSION: &'static str = "png";
+    const ENCRYPTED_EXTENSION: &'static str = "enc";
+    const PARTIAL_EXTENSION: &'static str = "part";
+    /// Thumbnails show private clipboard contents, so only the user may read them, as the
+    /// freedesktop thumbnail specification requires.
+    const DIR_MODE: u32 = 0o700;
+    const FILE_MODE: u32 = 0o600;
+    /// Partial files older than this were left behind by an interrupted store.
+    const PARTIAL_FILE_MAX_AGE: Duration = Duration::from_secs(60);
+
+    pub fn new(dir: PathBuf, max_bytes: u64, encrypted: bool) -> Self {
+        Self {
+            dir,
+            max_bytes,
+            stores_since_eviction: AtomicUsize::new(0),
+            encrypted,
+            key: OnceLock::new(),
+        }
+    }
+
+    pub fn default_dir() -> PathBuf {
+        glib::user_cache_dir()
+            .join("clipboard_manager")
+            .join("thumbnails")
+    }
+
+    fn cache_file(&self, source: &str, width: i32, height: i32) -> Option<PathBuf> {
+        let metadata = fs::metadata(source).ok()?;
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        let key = format!(
+            "{}\0{}.{:09}\0{}\0{}x{}",
+            source,
+            mtime.as_secs(),
+            mtime.subsec_nanos(),
+            metadata.len(),
+            width,
+            height
+        );
+        let checksum = glib::compute_checksum_for_data(ChecksumType::Md5, key.as_bytes())?;
+        Some(self.dir.join(format!("{}.{}", checksum, self.extension())))
+    }
+
+    fn extension(&self) -> &'static str {
+        if self.encrypted {
+            Self::ENCRYPTED_EXTENSION
+        } else {
+            Self::PLAIN_EXTENSION
+        }
+    }
+
+    /// The key of an encrypted cache, fetched from the keyring once. `None` if it could not
+    /// be fetched, which is reported the first time.
+    fn encryption_key(&self) -> Option<&EncryptionKey> {
+        self.key
+            .get_or_init(|| {
+                EncryptionKey::from_keyring()
+                    .map_err(|e| eprintln!("Error getting the thumbnail encryption key, not caching thumbnails: {}", e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    fn read_thumbnail(&self, cache_file: &Path) -> Option<Pixbuf> {
+        if !self.encrypted {
+            return Pixbuf::from_file(cache_file).ok();
+        }
+        let key = self.encryption_key()?;
+        let png = match key.decrypt(&fs::read(cache_file).ok()?) {
+            Ok(png) => png,
+            Err(e) => {
+                eprintln!("Error reading thumbnail {}: {}", cache_file.display(), e);
+                let _ = fs::remove_file(cache_file);
+                return None;
+            }
+        };
+        let loader = PixbufLoader::with_type(Self::PLAIN_EXTENSION).ok()?;
+        loader.write(&png).ok()?;
+        loader.close().ok()?;
+        loader.pixbuf()
+    }
+
+    fn write_thumbnail(&self, file: &Path, thumbnail: &Pixbuf) -> Result<(), String> {
+        let png = thumbnail
+            .save_to_bufferv(Self::PLAIN_EXTENSION, &[])
+            .map_err(|e| e.to_string())?;
+        let contents = if self.encrypted {
+            let key = self.encryption_key().ok_or_else(|| "no encryption key".to_string())?;
+            key.encrypt(&png)?
+        } else {
+            png
+        };
+
+        let mut output = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(Self::FILE_MODE)
+            .open(file)
+            .map_err(|e| e.to_string())?;
+        output.write_all(&contents).map_err(|e| e.to_string())
+    }
+
+    /// Creates the cache directory readable only by the user, and tightens the permissions of
+    /// one created by an earlier version.
+    fn create_dir(&self) -> std::io::Result<()> {
+        DirBuilder::new().recursive(true).mode(Self::DIR_MODE).create(&self.dir)?;
+        let metadata = fs::metadata(&self.dir)?;
+        if metadata.permissions().mode() & 0o777 != Self::DIR_MODE {
+            fs::set_permissions(&self.dir, Permissions::from_mode(Self::DIR_MODE))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the cached thumbnail of `source` at `width`×`height`, if there is one.
+    pub fn lookup(&self, source: &str, width: i32, height: i32) -> Option<Pixbuf> {
+        let cache_file = self.cache_file(source, width, height)?;
+        if !cache_file.exists() {
+            return None;
+        }
+        let pixbuf = self.read_thumbnail(&cache_file)?;
+
+        // The modification time doubles as the last-used time for eviction.
+        if let Ok(file) = File::options().append(true).open(&cache_file) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Some(pixbuf)
+    }
+
+    /// Saves `thumbnail` as the thumbnail of `source` at `width`×`height`. Failures are only
+    /// reported, as the thumbnail can always be decoded again.
+    pub fn store(&self, source: &str, width: i32, height: i32, thumbnail: &Pixbuf) {
+        let Some(cache_file) = self.cache_file(source, width, height) else {
+            return;
+        };
+        if self.encrypted && self.encryption_key().is_none() {
+            return;
+        }
+
+        if let Err(e) = self.create_dir() {
+            eprintln!("Error creating thumbnail cache {}: {}", self.dir.display(), e);
+            return;
+        }
+
+        // Written under a temporary name first so readers never see a partial file.
+        let partial_file = cache_file.with_extension(format!("{}.{}", self.extension(), Self::PARTIAL_EXTENSION));
+        if let Err(e) = self.write_thumbnail(&partial_file, thumbnail) {
+            eprintln!("Error saving thumbnail {}: {}", partial_file.display(), e);
+            let _ = fs::remove_file(&partial_file);
+            return;
+        }
+        if let Err(e) = fs::rename(&partial_file, &cache_file) {
+            eprintln!("Error saving thumbnail {}: {}", cache_file.display(), e);
+            let _ = fs::remove_file(&partial_file);
+            return;
+        }
+
+        if self.stores_since_eviction.fetch_add(1, Ordering::Relaxed) + 1 >= Self::EVICT_EVERY {
+            self.evict();
+        }
+    }
+
+    /// Removes the least recently used thumbnails until the cache fits its size limit. Plain
+    /// thumbnails are removed first when the cache is encrypted, and encrypted ones when it
+    /// no longer is, as are partial files of interrupted stores.
+    pub fn evict(&self) {
+        self.stores_since_eviction.store(0, Ordering::Relaxed);
+
+        let Ok(dir_entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        let paths: Vec<PathBuf> = dir_entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+        let abandoned = paths.iter().filter(|path| is_abandoned_partial_file(path)).cloned();
+        let (current, stale): (Vec<PathBuf>, Vec<PathBuf>) = paths
+            .iter()
+            .filter(|path| is_thumbnail(path))
+            .cloned()
+            .partition(|path| has_extension(path, self.extension()));
+        for path in stale.into_iter().chain(abandoned) {
+            if let Err(e) = fs::remove_file(&path) {
+                eprintln!("Error removing thumbnail {}: {}", path.display(), e);
+            }
+        }
+
+        let mut thumbnails: Vec<(SystemTime, u64, PathBuf)> = current
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), path))
+            })
+            .collect();
+
+        let mut total: u64 = thumbnails.iter().map(|(_, size, _)| size).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+
+        thumbnails.sort_by_key(|(used, _, _)| *used);
+        for (_, size, path) in thumbnails {
+            if total <= self.max_bytes {
+                break;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => total -= size,
+                Err(e) => eprintln!("Error removing thumbnail {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().map(|ext| ext == extension).unwrap_or(false)
+}
+
+/// A partial file no store is still writing, judged by its age.
+fn is_abandoned_partial_file(path: &Path) -> bool {
+    if !has_extension(path, ThumbnailCache::PARTIAL_EXTENSION) {
+        return false;
+    }
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .map(|age| age > ThumbnailCache::PARTIAL_FILE_MAX_AGE)
+        .unwrap_or(false)
+}
+
+fn is_thumbnail(path: &Path) -> bool {
+    has_extension(path, ThumbnailCache::PLAIN_EXTENSION) || has_extension(path, ThumbnailCache::ENCRYPTED_EXTENSION)
+}
@@ -1,7 +1,16 @@
 use gtk::ListBoxRow;
-use std::{cell::RefCell, collections::HashMap};
+use std::{cell::RefCell, collections::HashMap, sync::Arc};
 
-use clipboard_manager::clipboard_entries::ClipboardEntry;
+use clipboard_manager::{
+    backend::{ChangeWatch, ClipboardBackend}, clipboard_entries::ClipboardEntry, encrypted_history::EncryptedHistory,
+    thumbnail_cache::ThumbnailCache,
+};
+
+use crate::{
+    config::Config, expiry::ExpiryScheduler, get_clipboard_entries::HistorySnapshot, keyboard::keymap::Keymap,
+};
+
+use super::{loading_indicator::LoadingIndicator, status_bar::StatusBar};
 
 #[derive(PartialEq)]
 pub enum DetailsVisibility {
@@ -14,10 +23,29 @@ pub struct AppState {
     pub rows: RefCell<Vec<ListBoxRow>>,
     pub row_to_entry_map: RefCell<HashMap<ListBoxRow, Box<dyn ClipboardEntry>>>,
     pub details_visibility: RefCell<DetailsVisibility>,
-    pub all_entries_loaded: RefCell<bool>,
+    /// The history pages are loaded from, taken by the first load and renewed by every sync.
+    pub history_snapshot: RefCell<Option<HistorySnapshot>>,
+    /// Offset in `history_snapshot` of the next page to load, or `None` once the whole
+    /// history is loaded.
+    pub next_offset: RefCell<Option<usize>>,
+    pub entries_loading: RefCell<bool>,
+    pub load_all_requested: RefCell<bool>,
+    pub sync_requested: RefCell<bool>,
     pub search_query: RefCell<Option<String>>,
     pub filtered_rows: RefCell<Option<Vec<ListBoxRow>>>,
     pub search_cache: RefCell<HashMap<String, Vec<ListBoxRow>>>,
     pub last_selected_row: RefCell<Option<ListBoxRow>>,
+    pub keymap: Keymap,
+    pub config: Config,
+    pub backend: Arc<dyn ClipboardBackend>,
+    pub thumbnail_cache: Option<Arc<ThumbnailCache>>,
+    /// Where `backend` keeps the items it encrypted; entries decrypt them from there.
+    pub encrypted_history: Arc<EncryptedHistory>,
+    pub change_watch: RefCell<Option<ChangeWatch>>,
+    pub expiry: ExpiryScheduler,
+    /// Removes copies of excluded applications; only kept in resident mode.
+    pub exclusion_watch: RefCell<Option<ChangeWatch>>,
+    pub status_bar: StatusBar,
+    pub loading_indicator: LoadingIndicator,
 }
 
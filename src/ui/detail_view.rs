@@ -8,6 +8,7 @@ pub fn create_detail_view(width: i32) -> (ScrolledWindow, Box) {
         .build();
 
     let detail_container = Box::new(Orientation::Vertical, 0);
+    detail_container.style_context().add_class("clipboard-detail");
     detail_scrolled_window.add(&detail_container);
 
     (detail_scrolled_window, detail_container)
@@ -9,6 +9,7 @@ pub fn show_error(window: &ApplicationWindow, message: &str) {
     
     let error_label = Label::new(Some(&full_message));
     error_label.set_margin(10);
+    error_label.style_context().add_class("clipboard-error");
     
     let window_clone = window.clone();
     window.connect_key_press_event(move |_, event| {
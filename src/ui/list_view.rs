@@ -1,8 +1,8 @@
-use gtk::{prelude::*, Box as GTKBox, ListBox, ListBoxRow, ScrolledWindow};
-use std::{collections::HashMap, rc::Rc};
+use gtk::{glib, prelude::*, Box as GTKBox, ListBox, ListBoxRow, ScrolledWindow};
+use std::{cell::Cell, collections::HashMap, rc::Rc};
 
 use clipboard_manager::clipboard_entries::ClipboardEntry;
-use crate::{constants::*, ui::app_state::{AppState, DetailsVisibility}};
+use crate::ui::app_state::{AppState, DetailsVisibility};
 
 
 pub fn append_to_list_view(
@@ -47,14 +47,89 @@ pub fn create_list_view(
     (list_scrolled_window, list_box, rows, row_to_entry_map)
 }
 
+/// How many pages above and below the viewport are rendered ahead of scrolling.
+const RENDER_AHEAD_PAGES: f64 = 1.0;
+
+/// Builds the widgets of the rows that are in or near the viewport. Rows further away stay
+/// placeholders until they are scrolled to.
+pub fn render_visible_rows(scrolled_window: &ScrolledWindow, list_box: &ListBox, app_state: &AppState) {
+    let adjustment = scrolled_window.vadjustment();
+    let page_size = adjustment.page_size();
+    if page_size <= 0.0 {
+        return;
+    }
+
+    let top = (adjustment.value() - page_size * RENDER_AHEAD_PAGES).max(0.0);
+    let bottom = adjustment.value() + page_size * (1.0 + RENDER_AHEAD_PAGES);
+
+    let row_map = app_state.row_to_entry_map.borrow();
+    let mut index = list_box
+        .row_at_y(top as i32)
+        .map(|row| row.index())
+        .unwrap_or(0);
+
+    while let Some(row) = list_box.row_at_index(index) {
+        if row.allocation().y() as f64 > bottom {
+            break;
+        }
+        if let Some(entry) = row_map.get(&row) {
+            entry.render_row();
+        }
+        index += 1;
+    }
+}
+
+/// Renders the rows near the viewport whenever it scrolls or changes size. The handlers only
+/// keep a weak reference to `app_state`, which holds the rows, so they do not keep it alive.
+pub fn setup_lazy_rendering(
+    scrolled_window: &ScrolledWindow,
+    list_box: &ListBox,
+    app_state: &Rc<AppState>,
+) {
+    let render_scheduled = Rc::new(Cell::new(false));
+    let app_state = Rc::downgrade(app_state);
+
+    let schedule_render = {
+        let scrolled_window = scrolled_window.clone();
+        let list_box = list_box.clone();
+        move || {
+            if render_scheduled.replace(true) {
+                return;
+            }
+            let scrolled_window = scrolled_window.clone();
+            let list_box = list_box.clone();
+            let app_state = app_state.clone();
+            let render_scheduled = render_scheduled.clone();
+            glib::idle_add_local_once(move || {
+                render_scheduled.set(false);
+                if let Some(app_state) = app_state.upgrade() {
+                    render_visible_rows(&scrolled_window, &list_box, &app_state);
+                }
+            });
+        }
+    };
+    let schedule_render = Rc::new(schedule_render);
+
+    let adjustment = scrolled_window.vadjustment();
+    let schedule_on_scroll = schedule_render.clone();
+    adjustment.connect_value_changed(move |_| schedule_on_scroll());
+    let schedule_on_resize = schedule_render.clone();
+    adjustment.connect_changed(move |_| schedule_on_resize());
+    list_box.connect_size_allocate(move |_, _| schedule_render());
+}
+
 pub fn setup_list_selection_handler(
     list_box: &ListBox,
     detail_container: &GTKBox,
-    app_state: Rc<AppState>,
+    app_state: &Rc<AppState>,
 ) {
     let detail_container_clone = detail_container.clone();
+    let app_state = Rc::downgrade(app_state);
     
     list_box.connect_row_selected(move |_, row| {
+        let Some(app_state) = app_state.upgrade() else {
+            return;
+        };
         if *app_state.details_visibility.borrow() == DetailsVisibility::Hidden {
             return;
         }
@@ -78,10 +153,11 @@ pub fn setup_list_selection_handler(
         let search_query = app_state.search_query.borrow();
         entry.set_highlight_in_row(search_query.clone());
         
+        let window_config = &app_state.config.window;
         let (width, height) = match *app_state.details_visibility.borrow() {
-            DetailsVisibility::Big => (INFO_BOX_BIG_WIDTH, INFO_BOX_BIG_HEIGHT),
-            DetailsVisibility::Normal => (INFO_BOX_WIDTH, APP_HEIGHT),
-            _ => (INFO_BOX_WIDTH, APP_HEIGHT),
+            DetailsVisibility::Big => (window_config.detail_big_width, window_config.detail_big_height),
+            DetailsVisibility::Normal => (window_config.detail_width, window_config.height),
+            _ => (window_config.detail_width, window_config.height),
         };
         let detail_widget = entry.create_more_info_widget(width, height, search_query.clone());
         
@@ -0,0 +1,47 @@
+use gtk::{prelude::*, Box as GTKBox, Label, Orientation, Spinner};
+
+/// Spinner with a short message, shown below the list while entries load in the background.
+pub struct LoadingIndicator {
+    container: GTKBox,
+    spinner: Spinner,
+    label: Label,
+}
+
+impl LoadingIndicator {
+    const MARGIN: i32 = 5;
+
+    pub fn new() -> Self {
+        let container = GTKBox::new(Orientation::Horizontal, Self::MARGIN);
+        container.set_margin(Self::MARGIN);
+        container.style_context().add_class("clipboard-loading");
+
+        let spinner = Spinner::new();
+        let label = Label::new(None);
+        label.set_xalign(0.0);
+        label.style_context().add_class("dim-label");
+
+        container.pack_start(&spinner, false, false, 0);
+        container.pack_start(&label, true, true, 0);
+
+        container.show_all();
+        container.hide();
+        container.set_no_show_all(true);
+
+        Self { container, spinner, label }
+    }
+
+    pub fn widget(&self) -> &GTKBox {
+        &self.container
+    }
+
+    pub fn start(&self, message: &str) {
+        self.label.set_text(message);
+        self.spinner.start();
+        self.container.show();
+    }
+
+    pub fn stop(&self) {
+        self.spinner.stop();
+        self.container.hide();
+    }
+}
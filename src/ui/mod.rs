@@ -1,33 +1,110 @@
 use gtk::{
-    prelude::*, Application, ApplicationWindow, Box as GTKBox, Orientation,
+    prelude::*, Application, ApplicationWindow, Box as GTKBox, Inhibit, Label, Orientation,
 };
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fs, path::PathBuf, rc::Rc, thread, time::SystemTime};
 
 use crate::{
-    constants::*,
-    get_clipboard_entries::get_clipboard_entries,
-    keyboard::setup_keyboard_handler,
+    config::Config,
+    exclusion::{exclusions_unenforceable, watch_exclusions},
+    expiry::ExpiryScheduler,
+    keyboard::{
+        detail_views::hide_detail,
+        entry_loading::{load_next_page, setup_scroll_paging, watch_history_changes},
+        keymap::Keymap,
+        search::rebuild_list,
+        setup_keyboard_handler,
+    },
 };
 
 mod app_state;
 mod detail_view;
 mod error_label;
 mod list_view;
+mod loading_indicator;
+mod status_bar;
+mod style;
 
 use detail_view::create_detail_view;
-use list_view::{create_list_view, setup_list_selection_handler};
+use list_view::{create_list_view, setup_lazy_rendering, setup_list_selection_handler};
+use loading_indicator::LoadingIndicator;
+use style::{install_styles, user_css_path};
 
 pub use app_state::{AppState, DetailsVisibility};
 pub use error_label::show_error;
+pub use status_bar::{RetryAction, StatusBar};
 pub use list_view::append_to_list_view;
 
 
+/// Modification times of `config.toml`, `keymap.toml` and `style.css`, to tell whether a
+/// resident instance has to rebuild the picker.
+#[derive(Debug, PartialEq, Eq)]
+struct ConfigFiles(Vec<Option<SystemTime>>);
+
+impl ConfigFiles {
+    fn read() -> Self {
+        let paths: [PathBuf; 3] = [Config::config_path(), Keymap::config_path(), user_css_path()];
+        Self(
+            paths
+                .iter()
+                .map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+                .collect(),
+        )
+    }
+}
+
+thread_local! {
+    /// The state of the config files the current window was built from.
+    static BUILT_FROM: RefCell<Option<ConfigFiles>> = const { RefCell::new(None) };
+}
+
+fn build_ui_from_current_config(app: &Application, resident: bool) -> ApplicationWindow {
+    BUILT_FROM.with(|built_from| built_from.replace(Some(ConfigFiles::read())));
+    build_ui(app, resident)
+}
+
+fn config_files_changed() -> bool {
+    BUILT_FROM.with(|built_from| built_from.borrow().as_ref() != Some(&ConfigFiles::read()))
+}
+
+/// Shows the picker, or hides it if it is already visible. In resident mode the first
+/// activation only builds the window, so later ones can show it without any loading; they
+/// rebuild it if the config, keymap or stylesheet was edited in the meantime.
+pub fn activate(app: &Application, resident: bool) {
+    let existing_window = app
+        .windows()
+        .into_iter()
+        .find_map(|window| window.downcast::<ApplicationWindow>().ok());
+
+    match existing_window {
+        Some(window) if window.is_visible() => window.close(),
+        Some(window) if config_files_changed() => {
+            // SAFETY: nothing uses the window after this; the new one replaces it.
+            unsafe { window.destroy() };
+            build_ui_from_current_config(app, resident).present();
+        }
+        Some(window) => window.present(),
+        None => {
+            let window = build_ui_from_current_config(app, resident);
+            if resident {
+                // Keeps the process alive while the window is hidden.
+                app.hold();
+            } else {
+                window.present();
+            }
+        }
+    }
+}
+
+/// Builds the picker window without showing it. In resident mode closing the window only
+/// hides it.
+fn build_ui(app: &Application, resident: bool) -> ApplicationWindow {
+    let config = Config::load();
+    let app_height = config.as_ref().map_or(Config::default().window.height, |c| c.window.height);
 
-pub fn build_ui(app: &Application) {
     let window = ApplicationWindow::builder()
         .application(app)
         .title("Clipboard Manager")
-        .default_height(APP_HEIGHT)
+        .default_height(app_height)
         .decorated(false)
         .modal(true)  
         .build();
@@ -38,33 +115,59 @@ pub fn build_ui(app: &Application) {
         }
     });
 
-    let root_box = GTKBox::new(Orientation::Vertical, 0);
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            show_error(&window, &e);
+            return window;
+        }
+    };
 
-    let main_box = GTKBox::new(Orientation::Horizontal, 0);
+    let status_bar = StatusBar::new();
 
-    let entries = match get_clipboard_entries(
-        INITIAL_ENTRIES,
-        ENTRIES_WIDTH,
-        ROW_IMAGE_MAX_HEIGHT,
-        ROW_TEXT_MAX_LINES,
-    ) {
-        Ok(entries) if !entries.is_empty() => entries,
-        Ok(_) => {
-            show_error(&window, "No clipboard entries available.");
-            return;
-        }
+    if let Err(e) = install_styles(&config) {
+        status_bar.show_error(&e, None);
+    }
+
+    let keymap = match Keymap::load() {
+        Ok(keymap) => keymap,
         Err(e) => {
-            show_error(&window, &format!("Error fetching clipboard entries: {}", e));
-            return;
+            show_error(&window, &e);
+            return window;
         }
     };
 
-    let (list_scrolled_window, list_box, rows, row_to_entry_map) = create_list_view(entries, ENTRIES_WIDTH);
+    let loading_indicator = LoadingIndicator::new();
 
-    let (detail_scrolled_window, detail_container) = create_detail_view(INFO_BOX_WIDTH);
+    let thumbnail_cache = config.thumbnail_cache();
+    if let Some(cache) = thumbnail_cache.clone() {
+        // Trims what earlier runs left behind without delaying startup.
+        thread::spawn(move || cache.evict());
+    }
+
+    let encrypted_history = config.encrypted_history();
+    let backend = config.create_backend(encrypted_history.clone());
+    let expiry = ExpiryScheduler::new(&config.secrets, backend.clone());
+
+    let root_box = GTKBox::new(Orientation::Vertical, 0);
+
+    let main_box = GTKBox::new(Orientation::Horizontal, 0);
+
+    let (list_scrolled_window, list_box, rows, row_to_entry_map) = create_list_view(Vec::new(), config.window.list_width);
+
+    let (detail_scrolled_window, detail_container) = create_detail_view(config.window.detail_width);
 
     main_box.pack_start(&list_scrolled_window, false, false, 0);
+    let paused_label = Label::new(Some("Recording is paused. Run `clipboard_manager resume` to resume."));
+    paused_label.set_xalign(0.0);
+    paused_label.set_margin(5);
+    paused_label.style_context().add_class("clipboard-paused");
+    paused_label.set_no_show_all(true);
+
+    root_box.pack_start(&paused_label, false, false, 0);
+    root_box.pack_start(status_bar.widget(), false, false, 0);
     root_box.pack_start(&main_box, true, true, 0);
+    root_box.pack_start(loading_indicator.widget(), false, false, 0);
 
     let search_entry = gtk::SearchEntry::new();
     search_entry.set_placeholder_text(Some("Press 's' to search..."));
@@ -76,18 +179,96 @@ pub fn build_ui(app: &Application) {
         rows: RefCell::new(rows),
         row_to_entry_map: RefCell::new(row_to_entry_map),
         details_visibility: RefCell::new(DetailsVisibility::Hidden),
-        all_entries_loaded: RefCell::new(false),
+        history_snapshot: RefCell::new(None),
+        next_offset: RefCell::new(Some(0)),
+        entries_loading: RefCell::new(false),
+        load_all_requested: RefCell::new(false),
+        sync_requested: RefCell::new(false),
         filtered_rows: RefCell::new(None),
         search_query: RefCell::new(None),
         search_cache: RefCell::new(HashMap::new()),
         last_selected_row: RefCell::new(None),
+        keymap,
+        backend,
+        thumbnail_cache,
+        encrypted_history,
+        config,
+        status_bar,
+        loading_indicator,
+        change_watch: RefCell::new(None),
+        expiry,
+        exclusion_watch: RefCell::new(None),
+    });
+
+    load_next_page(&list_box, &app_state);
+    watch_history_changes(&list_box, &app_state);
+    if let Some(reason) = exclusions_unenforceable(&app_state.config.exclusions, resident) {
+        eprintln!("Warning: {}", reason);
+        app_state.status_bar.show_warning(&reason);
+    } else if resident {
+        // Only the newest item can be excluded, which is never encrypted, so the backend itself
+        // is watched; reading it does not move items into the encrypted history on this thread.
+        let watch = watch_exclusions(app_state.config.backend.create(), &app_state.config.exclusions);
+        app_state.exclusion_watch.replace(watch);
+    }
+
+    if resident {
+        window.connect_delete_event(|window, _| {
+            window.hide();
+            Inhibit(true)
+        });
+    }
+
+    // The subscriptions would otherwise outlive a window replaced after a config change.
+    let app_state_for_destroy = Rc::downgrade(&app_state);
+    window.connect_destroy(move |_| {
+        if let Some(app_state) = app_state_for_destroy.upgrade() {
+            app_state.change_watch.replace(None);
+            app_state.exclusion_watch.replace(None);
+        }
+    });
+
+    // A resident window is shown again as it was left, so it starts over each time: search
+    // closed, every loaded row listed, the detail view hidden and the first row selected.
+    let list_box_for_show = list_box.clone();
+    let search_entry_for_show = search_entry.clone();
+    let scrolled_window_for_show = list_scrolled_window.clone();
+    let root_box_for_show = root_box.clone();
+    let main_box_for_show = main_box.clone();
+    let detail_scrolled_window_for_show = detail_scrolled_window.clone();
+    let app_state_for_show = Rc::downgrade(&app_state);
+    window.connect_show(move |window| {
+        let Some(app_state) = app_state_for_show.upgrade() else {
+            return;
+        };
+        paused_label.set_visible(app_state.backend.is_recording() == Some(false));
+
+        if search_entry_for_show.parent().is_some() {
+            search_entry_for_show.set_text("");
+            root_box_for_show.remove(&search_entry_for_show);
+        }
+        if app_state.search_query.borrow().is_some() || app_state.filtered_rows.borrow().is_some() {
+            rebuild_list(&list_box_for_show, &app_state, "", &search_entry_for_show);
+        }
+        if *app_state.details_visibility.borrow() != DetailsVisibility::Hidden {
+            hide_detail(window, &main_box_for_show, &detail_scrolled_window_for_show, &app_state);
+        }
+
+        scrolled_window_for_show.vadjustment().set_value(0.0);
+        if let Some(first_row) = list_box_for_show.row_at_index(0) {
+            list_box_for_show.select_row(Some(&first_row));
+            first_row.grab_focus();
+        }
     });
 
     setup_list_selection_handler(
         &list_box,
         &detail_container,
-        app_state.clone(),
+        &app_state,
     );
+
+    setup_lazy_rendering(&list_scrolled_window, &list_box, &app_state);
+    setup_scroll_paging(&list_scrolled_window, &list_box, &app_state);
     
     setup_keyboard_handler(
         &window,
@@ -100,5 +281,6 @@ pub fn build_ui(app: &Application) {
         app_state,
     );
 
-    window.show_all();
+    root_box.show_all();
+    window
 }
@@ -0,0 +1,163 @@
+use gtk::{prelude::*, InfoBar, Label, MessageType, ResponseType};
+use std::cell::RefCell;
+
+use clipboard_manager::ClipboardError;
+
+/// Load operation that can be re-run from the status bar after it failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAction {
+    LoadNextPage,
+    LoadAllEntries,
+}
+
+/// Inline message bar shown above the list. Unlike `show_error`, it leaves the rest of the
+/// window untouched.
+pub struct StatusBar {
+    info_bar: InfoBar,
+    label: Label,
+    retry_button: gtk::Button,
+    retry_action: RefCell<Option<RetryAction>>,
+    entry_failures: RefCell<Vec<String>>,
+}
+
+impl StatusBar {
+    const MAX_LISTED_FAILURES: usize = 10;
+
+    pub fn new() -> Self {
+        let info_bar = InfoBar::new();
+        info_bar.set_show_close_button(true);
+
+        let label = Label::new(None);
+        label.set_xalign(0.0);
+        label.set_line_wrap(true);
+        info_bar.content_area().pack_start(&label, true, true, 0);
+
+        let retry_button = info_bar
+            .add_button("Retry", ResponseType::Apply)
+            .expect("InfoBar creates a button for the retry response");
+
+        info_bar.connect_response(|info_bar, response| {
+            if response == ResponseType::Close {
+                info_bar.hide();
+            }
+        });
+
+        info_bar.show_all();
+        info_bar.hide();
+        info_bar.set_no_show_all(true);
+
+        Self {
+            info_bar,
+            label,
+            retry_button,
+            retry_action: RefCell::new(None),
+            entry_failures: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn widget(&self) -> &InfoBar {
+        &self.info_bar
+    }
+
+    /// Calls `f` when the Retry button is clicked; `f` is expected to call `take_retry`.
+    pub fn connect_retry<F: Fn() + 'static>(&self, f: F) {
+        self.info_bar.connect_response(move |_, response| {
+            if response == ResponseType::Apply {
+                f();
+            }
+        });
+    }
+
+    pub fn show_error(&self, message: &str, retry: Option<RetryAction>) {
+        self.show(MessageType::Error, message, retry);
+    }
+
+    /// Shows `err` prefixed with `context`, adding a hint on how to fix the problem where
+    /// there is one.
+    pub fn show_clipboard_error(&self, context: &str, err: &ClipboardError, retry: Option<RetryAction>) {
+        let hint = match err {
+            ClipboardError::BackendMissing { .. } => Some(
+                "GPaste is not installed. Install it with your package manager, e.g. \
+                 `sudo apt-get install gpaste`, `sudo dnf install gpaste` or `sudo pacman -S gpaste`.",
+            ),
+            ClipboardError::BackendFailed { .. } => Some("Check that the GPaste daemon is running."),
+            ClipboardError::EntryNotFound { .. } => Some("The entry was removed from the history; reload the list."),
+            ClipboardError::OpenFailed { .. } => Some("Check that an application is registered for this file type."),
+            ClipboardError::TooLarge { .. } => Some("Raise `preview.max_file_size_kb` in the config to copy it."),
+            ClipboardError::Decryption { .. } => Some("Check that the session keyring is unlocked."),
+            ClipboardError::NotText { .. } | ClipboardError::Io(_) => None,
+        };
+
+        let message = match hint {
+            Some(hint) => format!("{}: {}\n{}", context, err, hint),
+            None => format!("{}: {}", context, err),
+        };
+        self.show_error(&message, retry);
+    }
+
+    /// Shows a warning. A failed load that can still be retried keeps its Retry button.
+    pub fn show_warning(&self, message: &str) {
+        let retry = *self.retry_action.borrow();
+        self.show(MessageType::Warning, message, retry);
+    }
+
+    pub fn show_info(&self, message: &str) {
+        self.show(MessageType::Info, message, None);
+    }
+
+    /// Records history items that could not be loaded and summarises them in a warning,
+    /// with the individual reasons in the tooltip. Failures already recorded by an earlier
+    /// load are not counted twice.
+    pub fn add_entry_failures(&self, failures: Vec<String>) {
+        let mut all_failures = self.entry_failures.borrow_mut();
+        let previous_count = all_failures.len();
+        for failure in failures {
+            if !all_failures.contains(&failure) {
+                all_failures.push(failure);
+            }
+        }
+        if all_failures.len() == previous_count {
+            return;
+        }
+
+        let message = match all_failures.len() {
+            1 => "1 clipboard entry could not be loaded.".to_string(),
+            count => format!("{} clipboard entries could not be loaded.", count),
+        };
+        let mut details: Vec<&str> = all_failures
+            .iter()
+            .take(Self::MAX_LISTED_FAILURES)
+            .map(String::as_str)
+            .collect();
+        if all_failures.len() > Self::MAX_LISTED_FAILURES {
+            details.push("…");
+        }
+        let tooltip = details.join("\n");
+        drop(all_failures);
+
+        self.show_warning(&message);
+        self.label.set_tooltip_text(Some(&tooltip));
+    }
+
+    /// Takes the pending retry action, hiding the bar if there was one.
+    pub fn take_retry(&self) -> Option<RetryAction> {
+        let action = self.retry_action.borrow_mut().take();
+        if action.is_some() {
+            self.hide();
+        }
+        action
+    }
+
+    pub fn hide(&self) {
+        self.info_bar.hide();
+    }
+
+    fn show(&self, message_type: MessageType, message: &str, retry: Option<RetryAction>) {
+        self.info_bar.set_message_type(message_type);
+        self.label.set_text(message);
+        self.label.set_tooltip_text(None);
+        self.retry_button.set_visible(retry.is_some());
+        *self.retry_action.borrow_mut() = retry;
+        self.info_bar.show();
+    }
+}
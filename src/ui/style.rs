@@ -0,0 +1,125 @@
+use gtk::{
+    gdk, gio, gio::prelude::SettingsExt as GioSettingsExt, prelude::*, traits::SettingsExt as GtkSettingsExt,
+    CssProvider, Settings, StyleContext,
+};
+use std::{cell::RefCell, path::PathBuf};
+
+use crate::config::Config;
+
+const LIGHT_CSS: &str = include_str!("style/light.css");
+const DARK_CSS: &str = include_str!("style/dark.css");
+
+const INTERFACE_SCHEMA: &str = "org.gnome.desktop.interface";
+const COLOR_SCHEME_KEY: &str = "color-scheme";
+
+thread_local! {
+    /// The bundled stylesheet, installed once and kept in line with the colour scheme.
+    static THEME_PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+    /// Providers for the config's highlight colours and `style.css`, replaced when the picker
+    /// is rebuilt.
+    static USER_PROVIDERS: RefCell<Vec<CssProvider>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn user_css_path() -> PathBuf {
+    gtk::glib::user_config_dir()
+        .join("clipboard_manager")
+        .join("style.css")
+}
+
+/// Installs the bundled light or dark stylesheet, the highlight colours from the config and
+/// the user's `style.css`, in increasing order of priority. The bundled stylesheet follows
+/// the system colour scheme while the app runs. Calling it again replaces the highlight
+/// colours and `style.css` installed before, e.g. after they were edited.
+pub fn install_styles(config: &Config) -> Result<(), String> {
+    let Some(screen) = gdk::Screen::default() else {
+        return Err("No default screen to apply styles to".to_string());
+    };
+
+    THEME_PROVIDER.with(|theme_provider| {
+        if theme_provider.borrow().is_some() {
+            return;
+        }
+        let provider = CssProvider::new();
+        load_theme(&provider);
+        StyleContext::add_provider_for_screen(&screen, &provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        watch_color_scheme(provider.clone());
+        theme_provider.replace(Some(provider));
+    });
+
+    USER_PROVIDERS.with(|user_providers| {
+        for provider in user_providers.borrow_mut().drain(..) {
+            StyleContext::remove_provider_for_screen(&screen, &provider);
+        }
+
+        let highlight_css = config.highlight_css();
+        if !highlight_css.is_empty() {
+            let highlight_provider = CssProvider::new();
+            highlight_provider
+                .load_from_data(highlight_css.as_bytes())
+                .map_err(|e| format!("Invalid highlight colours: {}", e))?;
+            StyleContext::add_provider_for_screen(&screen, &highlight_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1);
+            user_providers.borrow_mut().push(highlight_provider);
+        }
+
+        let user_css = user_css_path();
+        if user_css.exists() {
+            let user_provider = CssProvider::new();
+            user_provider
+                .load_from_path(&user_css.to_string_lossy())
+                .map_err(|e| format!("Error loading {}: {}", user_css.display(), e))?;
+            StyleContext::add_provider_for_screen(&screen, &user_provider, gtk::STYLE_PROVIDER_PRIORITY_USER);
+            user_providers.borrow_mut().push(user_provider);
+        }
+
+        Ok(())
+    })
+}
+
+fn load_theme(provider: &CssProvider) {
+    let css = if prefers_dark() { DARK_CSS } else { LIGHT_CSS };
+    if let Err(e) = provider.load_from_data(css.as_bytes()) {
+        eprintln!("Error loading bundled stylesheet: {}", e);
+    }
+}
+
+fn interface_settings() -> Option<gio::Settings> {
+    let schema = gio::SettingsSchemaSource::default()?.lookup(INTERFACE_SCHEMA, true)?;
+    schema
+        .has_key(COLOR_SCHEME_KEY)
+        .then(|| gio::Settings::new(INTERFACE_SCHEMA))
+}
+
+fn prefers_dark() -> bool {
+    if let Some(settings) = interface_settings() {
+        if settings.string(COLOR_SCHEME_KEY) == "prefer-dark" {
+            return true;
+        }
+    }
+
+    let Some(settings) = Settings::default() else {
+        return false;
+    };
+
+    settings.is_gtk_application_prefer_dark_theme()
+        || settings
+            .gtk_theme_name()
+            .map(|name| name.to_lowercase().ends_with("-dark"))
+            .unwrap_or(false)
+}
+
+fn watch_color_scheme(provider: CssProvider) {
+    if let Some(settings) = interface_settings() {
+        let provider = provider.clone();
+        let settings_clone = settings.clone();
+        settings.connect_changed(Some(COLOR_SCHEME_KEY), move |_, _| {
+            let _keep_alive = &settings_clone;
+            load_theme(&provider);
+        });
+    }
+
+    if let Some(settings) = Settings::default() {
+        let provider_for_dark = provider.clone();
+        settings.connect_gtk_application_prefer_dark_theme_notify(move |_| load_theme(&provider_for_dark));
+        settings.connect_gtk_theme_name_notify(move |_| load_theme(&provider));
+    }
+}